@@ -0,0 +1,87 @@
+//! Times a single fixed step of the built in physics, with both broad phases, for a few sizes of scene
+//!
+//! Only the physics module is used so this runs without a window or a gpu, `cargo bench` prints the time per step
+
+use cgmath::Vector2;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use eframe_quads_and_circles::physics::{
+    self, Backend, Boundary, BroadPhase, PhysicsCircle, PhysicsSettings,
+};
+
+/// The same as the default tick rate of the app
+const TIME_STEP: f32 = 1.0 / 100.0;
+
+/// The app's boundary filled with a grid of `count` circles, like "Add 5000 Circles" does
+fn circle_grid(boundary: &Boundary, count: u32) -> Vec<PhysicsCircle> {
+    let size = boundary.max - boundary.min;
+    let columns = ((count as f32 * size.x / size.y).sqrt().ceil() as u32).max(1);
+    let rows = count.div_ceil(columns);
+    let spacing = Vector2 {
+        x: size.x / columns as f32,
+        y: size.y / rows as f32,
+    };
+    let radius = spacing.x.min(spacing.y) * 0.4;
+    (0..count)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            let position = Vector2 {
+                x: boundary.min.x + (column as f32 + 0.5) * spacing.x,
+                y: boundary.min.y + (row as f32 + 0.5) * spacing.y,
+            };
+            // a little sideways movement so circles actually collide instead of falling in columns
+            let velocity = Vector2 {
+                x: if i % 2 == 0 { 1.0 } else { -1.0 },
+                y: 0.0,
+            };
+            PhysicsCircle::new(position, velocity, radius)
+        })
+        .collect()
+}
+
+fn settings(broad_phase: BroadPhase) -> PhysicsSettings {
+    PhysicsSettings {
+        gravity: Vector2 { x: 0.0, y: -9.81 },
+        boundary: Boundary {
+            min: Vector2 { x: -7.0, y: -3.5 },
+            max: Vector2 { x: 7.0, y: 3.5 },
+            restitution: 0.9,
+        },
+        backend: Backend::BuiltIn,
+        broad_phase,
+        attractor: None,
+        substeps: 1,
+    }
+}
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    for (name, broad_phase) in [
+        ("brute_force", BroadPhase::BruteForce),
+        ("spatial_hash", BroadPhase::SpatialHash),
+    ] {
+        let settings = settings(broad_phase);
+        for count in [100, 1_000, 10_000] {
+            // testing every pair of 10k circles takes long enough that fewer samples are still stable
+            group.sample_size(
+                if broad_phase == BroadPhase::BruteForce && count >= 10_000 {
+                    10
+                } else {
+                    100
+                },
+            );
+            let circles = circle_grid(&settings.boundary, count);
+            group.bench_with_input(BenchmarkId::new(name, count), &circles, |b, circles| {
+                // every step starts from the same scene, so only the step is timed and not the clone
+                b.iter_batched_ref(
+                    || circles.clone(),
+                    |circles| physics::step(circles, &[], &settings, TIME_STEP),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0)
+@binding(0)
+var image: texture_2d<f32>;
+
+@group(0)
+@binding(1)
+var image_sampler: sampler;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // a single quad covering the whole viewport, texture coordinates go down while clip space goes up
+    let clip = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+    output.clip_position = vec4<f32>(clip, 0.0, 1.0);
+    output.uv = vec2<f32>(clip.x * 0.5 + 0.5, 0.5 - clip.y * 0.5);
+
+    return output;
+}
+
+// the image is in an srgb format so sampling it gives linear colors, which srgb targets encode when writing
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, input.uv);
+}
+
+// non srgb targets store whatever is written, so the colors have to be encoded here instead,
+// they are premultiplied so the alpha is taken out first and put back after
+@fragment
+fn pixel_encode_srgb(input: VertexOutput) -> @location(0) vec4<f32> {
+    let sampled = textureSample(image, image_sampler, input.uv);
+    if sampled.a <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    let color = sampled.rgb / sampled.a;
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    let encoded = select(high, low, color <= vec3<f32>(0.0031308));
+    return vec4<f32>(encoded * sampled.a, sampled.a);
+}
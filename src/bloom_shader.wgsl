@@ -0,0 +1,94 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct Bloom {
+    radius: f32,
+    intensity: f32,
+};
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(0)
+var image: texture_2d<f32>;
+
+@group(0)
+@binding(1)
+var image_sampler: sampler;
+
+@group(0)
+@binding(2)
+var<uniform> bloom: Bloom;
+
+@group(0)
+@binding(3)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // a single quad covering the whole viewport, texture coordinates go down while clip space goes up
+    let clip = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+    output.clip_position = vec4<f32>(clip, 0.0, 1.0);
+    output.uv = vec2<f32>(clip.x * 0.5 + 0.5, 0.5 - clip.y * 0.5);
+
+    return output;
+}
+
+// one direction of the gaussian blur, the radius is the standard deviation in texels and the taps go out to twice that
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let texel = direction / vec2<f32>(textureDimensions(image));
+    let sigma = max(bloom.radius, 0.001);
+    let taps = min(i32(ceil(sigma * 2.0)), 32);
+
+    var sum = textureSampleLevel(image, image_sampler, uv, 0.0);
+    var total_weight = 1.0;
+    for (var i = 1; i <= taps; i += 1) {
+        let offset = f32(i);
+        let weight = exp(-offset * offset / (2.0 * sigma * sigma));
+        sum += weight * textureSampleLevel(image, image_sampler, uv + texel * offset, 0.0);
+        sum += weight * textureSampleLevel(image, image_sampler, uv - texel * offset, 0.0);
+        total_weight += weight * 2.0;
+    }
+    return sum / total_weight;
+}
+
+@fragment
+fn blur_horizontal(input: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(input.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn blur_vertical(input: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(input.uv, vec2<f32>(0.0, 1.0));
+}
+
+// added on top of whatever is already in the target, the alpha is left alone
+@fragment
+fn composite(input: VertexOutput) -> @location(0) vec4<f32> {
+    let glow = textureSampleLevel(image, image_sampler, input.uv, 0.0).rgb * bloom.intensity;
+    return vec4<f32>(encode_output(glow), 0.0);
+}
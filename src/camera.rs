@@ -0,0 +1,427 @@
+use cgmath::{Array, Vector2, VectorSpace};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::{
+    f32::consts::{PI, TAU},
+    fmt,
+};
+
+/// Rotates `v` counterclockwise by `angle` radians
+pub fn rotate(v: Vector2<f32>, angle: f32) -> Vector2<f32> {
+    let (sin, cos) = angle.sin_cos();
+    Vector2 {
+        x: v.x * cos - v.y * sin,
+        y: v.x * sin + v.y * cos,
+    }
+}
+
+/// What "Copy View" puts on the clipboard, just enough to see exactly the same thing somewhere else
+#[derive(Serialize, Deserialize)]
+struct View {
+    position: [f32; 2],
+    zoom: f32,
+    #[serde(default)]
+    rotation: f32,
+}
+
+#[derive(Debug)]
+pub enum ViewError {
+    Json(serde_json::Error),
+    NotFinite,
+    /// The zoom has to be between the cameras min and max zoom
+    ZoomOutOfRange {
+        zoom: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewError::Json(error) => write!(f, "invalid view: {error}"),
+            ViewError::NotFinite => write!(f, "view has a value that isnt finite"),
+            ViewError::ZoomOutOfRange { zoom, min, max } => {
+                write!(f, "view zoom {zoom} isnt between {min} and {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ViewError {}
+
+impl From<serde_json::Error> for ViewError {
+    fn from(error: serde_json::Error) -> Self {
+        ViewError::Json(error)
+    }
+}
+
+pub struct Camera {
+    pub position: Vector2<f32>,
+    pub zoom: f32,
+    /// How far the view is turned counterclockwise, in radians, so the world appears turned clockwise
+    pub rotation: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+}
+
+impl Camera {
+    /// Sets the zoom, keeping it between `min_zoom` and `max_zoom`, a nan zoom is ignored
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if zoom.is_nan() {
+            return;
+        }
+        self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+    }
+
+    /// Resets anything that isnt finite, or a zoom that isnt positive, so one bad value cant break the view for good
+    pub fn make_finite(&mut self) {
+        if !self.position.is_finite() {
+            self.position = Vector2 { x: 0.0, y: 0.0 };
+        }
+        if !self.zoom.is_finite() || self.zoom <= 0.0 {
+            self.zoom = 1.0_f32.clamp(self.min_zoom, self.max_zoom);
+        }
+        if !self.rotation.is_finite() {
+            self.rotation = 0.0;
+        }
+    }
+
+    /// The position, zoom and rotation as a single line of json, for sharing exactly what is on screen
+    pub fn view_string(&self) -> String {
+        serde_json::to_string(&View {
+            position: [self.position.x, self.position.y],
+            zoom: self.zoom,
+            rotation: self.rotation,
+        })
+        .unwrap()
+    }
+
+    /// Moves the camera to a view from [`Camera::view_string`], nothing changes unless every value is usable
+    pub fn apply_view_string(&mut self, text: &str) -> Result<(), ViewError> {
+        let view: View = serde_json::from_str(text.trim())?;
+        let position = Vector2::from(view.position);
+        if !position.is_finite() || !view.zoom.is_finite() || !view.rotation.is_finite() {
+            return Err(ViewError::NotFinite);
+        }
+        if !(self.min_zoom..=self.max_zoom).contains(&view.zoom) {
+            return Err(ViewError::ZoomOutOfRange {
+                zoom: view.zoom,
+                min: self.min_zoom,
+                max: self.max_zoom,
+            });
+        }
+        self.position = position;
+        self.zoom = view.zoom;
+        self.rotation = view.rotation.rem_euclid(TAU);
+        Ok(())
+    }
+
+    /// The position and zoom that would fit the box from `min` to `max` in a view with the given aspect,
+    /// `margin` is how much bigger than the box the view should be
+    pub fn framing(
+        &self,
+        min: Vector2<f32>,
+        max: Vector2<f32>,
+        aspect: f32,
+        margin: f32,
+    ) -> (Vector2<f32>, f32) {
+        let center = (min + max) * 0.5;
+        let half_size = (max - min) * 0.5 * margin;
+        // the size of the box once its turned to line up with the view
+        let (sin, cos) = self.rotation.sin_cos();
+        let half_size = Vector2 {
+            x: cos.abs() * half_size.x + sin.abs() * half_size.y,
+            y: sin.abs() * half_size.x + cos.abs() * half_size.y,
+        };
+        // the view is 1 / zoom world units from the center to the top, and aspect / zoom to the side
+        let zoom = (1.0 / half_size.y).min(aspect / half_size.x);
+        (center, zoom.clamp(self.min_zoom, self.max_zoom))
+    }
+
+    /// Converts a point in screen space inside of `rect` to world space
+    pub fn screen_to_world(&self, screen_pos: egui::Pos2, rect: egui::Rect) -> Vector2<f32> {
+        let aspect = rect.width() / rect.height();
+        let ndc = ((screen_pos - rect.left_top()) / rect.size() * 2.0 - egui::vec2(1.0, 1.0))
+            * egui::vec2(1.0, -1.0);
+        let view = Vector2 {
+            x: ndc.x * aspect / self.zoom,
+            y: ndc.y / self.zoom,
+        };
+        rotate(view, self.rotation) + self.position
+    }
+
+    /// Converts a point in world space to screen space inside of `rect`, the inverse of [`Camera::screen_to_world`]
+    pub fn world_to_screen(&self, world_pos: Vector2<f32>, rect: egui::Rect) -> egui::Pos2 {
+        let aspect = rect.width() / rect.height();
+        let view = rotate(world_pos - self.position, -self.rotation);
+        let ndc = egui::vec2(view.x * self.zoom / aspect, view.y * self.zoom);
+        rect.left_top() + (ndc * egui::vec2(1.0, -1.0) + egui::vec2(1.0, 1.0)) * 0.5 * rect.size()
+    }
+
+    /// Converts a movement in screen space inside of `rect` to world space, such as a drag or a velocity
+    pub fn screen_to_world_delta(
+        &self,
+        screen_delta: egui::Vec2,
+        rect: egui::Rect,
+    ) -> Vector2<f32> {
+        // the view is 2 / zoom world units tall and pixels are square
+        let world_per_point = 2.0 / (self.zoom * rect.height());
+        let view = Vector2 {
+            x: screen_delta.x * world_per_point,
+            y: -screen_delta.y * world_per_point,
+        };
+        rotate(view, self.rotation)
+    }
+
+    /// Moves the camera so the world follows the pointer being dragged by `screen_delta`
+    pub fn pan_by(&mut self, screen_delta: egui::Vec2, rect: egui::Rect) {
+        self.position -= self.screen_to_world_delta(screen_delta, rect);
+    }
+
+    /// Multiplies the zoom by `factor` while keeping the world point under `screen_pos` in place
+    pub fn zoom_at(&mut self, factor: f32, screen_pos: egui::Pos2, rect: egui::Rect) {
+        let world_pos = self.screen_to_world(screen_pos, rect);
+        self.set_zoom(self.zoom * factor);
+        self.move_point_to(world_pos, screen_pos, rect);
+    }
+
+    /// Moves the camera so `world_pos` is under `screen_pos`, keeping the zoom and rotation
+    pub fn move_point_to(
+        &mut self,
+        world_pos: Vector2<f32>,
+        screen_pos: egui::Pos2,
+        rect: egui::Rect,
+    ) {
+        self.position += world_pos - self.screen_to_world(screen_pos, rect);
+    }
+}
+
+/// Smoothly moves a camera from where it was to a target over `duration` seconds
+pub struct CameraAnimation {
+    pub start_position: Vector2<f32>,
+    pub start_zoom: f32,
+    pub start_rotation: f32,
+    pub target_position: Vector2<f32>,
+    pub target_zoom: f32,
+    pub target_rotation: f32,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl CameraAnimation {
+    pub fn new(
+        camera: &Camera,
+        target_position: Vector2<f32>,
+        target_zoom: f32,
+        target_rotation: f32,
+        duration: f32,
+    ) -> CameraAnimation {
+        CameraAnimation {
+            start_position: camera.position,
+            start_zoom: camera.zoom,
+            start_rotation: camera.rotation,
+            target_position,
+            target_zoom,
+            target_rotation,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+
+    /// Advances the animation by `dt` seconds and applies it to `camera`, returns `true` once it has finished
+    pub fn update(&mut self, camera: &mut Camera, dt: f32) -> bool {
+        self.elapsed += dt;
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        };
+        // smoothstep so it eases in and out
+        let t = t * t * (3.0 - 2.0 * t);
+
+        camera.position = self.start_position.lerp(self.target_position, t);
+        // zoom is interpolated geometrically so it feels like a constant zoom speed
+        camera.set_zoom(self.start_zoom * (self.target_zoom / self.start_zoom).powf(t));
+        // the short way round, so going from just under a full turn to 0 doesnt spin all the way back
+        let turn = (self.target_rotation - self.start_rotation + PI).rem_euclid(TAU) - PI;
+        camera.rotation = (self.start_rotation + turn * t).rem_euclid(TAU);
+
+        self.elapsed >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+    use proptest::prelude::*;
+
+    fn camera(position: Vector2<f32>, zoom: f32, rotation: f32) -> Camera {
+        Camera {
+            position,
+            zoom,
+            rotation,
+            min_zoom: 0.001,
+            max_zoom: 1000.0,
+        }
+    }
+
+    /// How far off a point can be after going to world space and back, a few rounding errors of the
+    /// biggest values involved, the world position gets multiplied by the pixels per world unit on the way back
+    fn tolerance(camera: &Camera, rect: egui::Rect) -> f32 {
+        let aspect = rect.width() / rect.height();
+        let world_size = camera.position.magnitude() + (aspect + 1.0) / camera.zoom;
+        let pixels_per_unit = camera.zoom * rect.height() * 0.5;
+        let screen_size = rect.max.to_vec2().length();
+        16.0 * f32::EPSILON * (world_size * pixels_per_unit + screen_size)
+    }
+
+    fn assert_near(a: egui::Pos2, b: egui::Pos2, tolerance: f32) {
+        assert!(
+            (a - b).length() <= tolerance,
+            "({}, {}) != ({}, {})",
+            a.x,
+            a.y,
+            b.x,
+            b.y
+        );
+    }
+
+    #[test]
+    fn screen_world_roundtrip() {
+        let cameras = [
+            camera(Vector2 { x: 0.0, y: 0.0 }, 1.0, 0.0),
+            camera(Vector2 { x: 3.5, y: -2.0 }, 0.25, 0.0),
+            camera(Vector2 { x: -100.0, y: 40.0 }, 8.0, 0.0),
+        ];
+        let rects = [
+            egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(800.0, 600.0)),
+            // not at the origin and taller than it is wide
+            egui::Rect::from_min_size(egui::pos2(120.0, 30.0), egui::vec2(300.0, 900.0)),
+        ];
+        let points = [
+            egui::pos2(0.0, 0.0),
+            egui::pos2(123.0, 456.0),
+            egui::pos2(799.0, 1.0),
+        ];
+        for camera in &cameras {
+            for rect in rects {
+                for point in points {
+                    let world = camera.screen_to_world(point, rect);
+                    assert_near(
+                        camera.world_to_screen(world, rect),
+                        point,
+                        tolerance(camera, rect),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn camera_position_is_at_the_center() {
+        let camera = camera(Vector2 { x: 3.5, y: -2.0 }, 0.25, 0.0);
+        let rect = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(800.0, 600.0));
+        assert_near(
+            camera.world_to_screen(camera.position, rect),
+            rect.center(),
+            tolerance(&camera, rect),
+        );
+    }
+
+    #[test]
+    fn rotated_screen_world_roundtrip() {
+        let rect = egui::Rect::from_min_size(egui::pos2(50.0, 10.0), egui::vec2(1024.0, 500.0));
+        for rotation in [0.3, PI * 0.5, PI, 5.0] {
+            let camera = camera(Vector2 { x: 2.0, y: -7.5 }, 1.5, rotation);
+            for point in [egui::pos2(50.0, 10.0), egui::pos2(700.0, 321.0)] {
+                let world = camera.screen_to_world(point, rect);
+                assert_near(
+                    camera.world_to_screen(world, rect),
+                    point,
+                    tolerance(&camera, rect),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rotation_turns_the_world_clockwise() {
+        let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(400.0, 400.0));
+        let camera = camera(Vector2 { x: 0.0, y: 0.0 }, 1.0, PI * 0.5);
+        // with the view turned a quarter counterclockwise, the world to the right is now straight down
+        assert_near(
+            camera.world_to_screen(Vector2 { x: 0.5, y: 0.0 }, rect),
+            egui::pos2(200.0, 300.0),
+            tolerance(&camera, rect),
+        );
+    }
+
+    /// Anywhere near the origin, zoomed from far out to far in, turned any amount, and viewports from a
+    /// single pixel across up to thousands wide or tall
+    ///
+    /// The position, zoom and rotation are given separately as the camera itself isnt debug printable
+    fn any_view() -> impl Strategy<Value = (Vector2<f32>, f32, f32, egui::Rect)> {
+        (
+            (-1000.0f32..1000.0, -1000.0f32..1000.0),
+            0.01f32..100.0,
+            -10.0f32..10.0,
+            (-1000.0f32..1000.0, -1000.0f32..1000.0),
+            (1.0f32..4000.0, 1.0f32..4000.0),
+        )
+            .prop_map(|((x, y), zoom, rotation, (left, top), (width, height))| {
+                (
+                    Vector2 { x, y },
+                    zoom,
+                    rotation,
+                    egui::Rect::from_min_size(egui::pos2(left, top), egui::vec2(width, height)),
+                )
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn any_screen_world_roundtrip(
+            (position, zoom, rotation, rect) in any_view(),
+            (u, v) in (0.0f32..=1.0, 0.0f32..=1.0),
+        ) {
+            let camera = camera(position, zoom, rotation);
+            let point = rect.lerp_inside(egui::vec2(u, v));
+            let world = camera.screen_to_world(point, rect);
+            let roundtrip = camera.world_to_screen(world, rect);
+            prop_assert!(
+                (roundtrip - point).length() <= tolerance(&camera, rect),
+                "{point:?} came back as {roundtrip:?}"
+            );
+        }
+
+        #[test]
+        fn any_camera_position_is_at_the_center((position, zoom, rotation, rect) in any_view()) {
+            let camera = camera(position, zoom, rotation);
+            let center = camera.world_to_screen(camera.position, rect);
+            prop_assert!(
+                (center - rect.center()).length() <= tolerance(&camera, rect),
+                "{center:?} isnt the center {:?}",
+                rect.center()
+            );
+        }
+    }
+
+    #[test]
+    fn extreme_aspect_roundtrip() {
+        let camera = camera(Vector2 { x: 12.0, y: -3.0 }, 0.5, 1.0);
+        for size in [egui::vec2(4000.0, 1.0), egui::vec2(1.0, 4000.0)] {
+            let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), size);
+            for (u, v) in [(0.0, 0.0), (1.0, 1.0), (0.25, 0.75)] {
+                let point = rect.lerp_inside(egui::vec2(u, v));
+                let world = camera.screen_to_world(point, rect);
+                assert_near(
+                    camera.world_to_screen(world, rect),
+                    point,
+                    tolerance(&camera, rect),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) capsule_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) capsule_index: u32,
+    @location(1) world_position: vec2<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+struct Capsule {
+    start: vec2<f32>,
+    end: vec2<f32>,
+    radius: f32,
+    color: vec3<f32>,
+};
+
+@group(1)
+@binding(0)
+var<storage, read> capsules: array<Capsule>;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.capsule_index = input.capsule_index;
+
+    let uv = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+
+    let capsule = capsules[input.capsule_index];
+
+    let offset = capsule.end - capsule.start;
+    let capsule_length = length(offset);
+    var direction = vec2<f32>(1.0, 0.0);
+    if capsule_length > 0.0 {
+        direction = offset / capsule_length;
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // the quad covers the segment and a radius around it, so with no length it is the same quad as a circle
+    let half_length = capsule_length * 0.5 + capsule.radius;
+    let center = (capsule.start + capsule.end) * 0.5;
+
+    output.world_position = center + direction * uv.x * half_length + normal * uv.y * capsule.radius;
+    output.clip_position = world_to_clip(output.world_position);
+
+    return output;
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let capsule = capsules[input.capsule_index];
+
+    // signed distance to the edge in world units, negative inside, the max keeps coincident endpoints from dividing by zero
+    let segment = capsule.end - capsule.start;
+    let from_start = input.world_position - capsule.start;
+    let t = clamp(dot(from_start, segment) / max(dot(segment, segment), 0.000001), 0.0, 1.0);
+    let from_segment = from_start - segment * t;
+    let segment_distance = length(from_segment);
+    let distance = segment_distance - capsule.radius;
+
+    // the same screen space gradient as the circles, so the edges are anti-aliased the same way
+    let normal = from_segment / max(segment_distance, 0.0001);
+    let gradient = vec2<f32>(dot(normal, dpdx(input.world_position)), dot(normal, dpdy(input.world_position)));
+    let pixel_size = length(gradient);
+    let edge_width = pixel_size * max(output.edge_softness, 0.01);
+
+    let alpha = clamp(0.5 - distance / edge_width, 0.0, 1.0);
+    if alpha <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(encode_output(capsule.color) * alpha, alpha);
+}
@@ -5,7 +5,7 @@ struct VertexInput {
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) circle_index: u32,
+    @location(0) @interpolate(flat) circle_index: u32,
     @location(1) uv: vec2<f32>,
 };
 
@@ -13,16 +13,57 @@ struct Camera {
     position: vec2<f32>,
     aspect: f32,
     zoom: f32,
+    rotation: f32,
 };
 
 @group(0)
 @binding(0)
 var<uniform> camera: Camera;
 
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
 struct Circle {
     position: vec2<f32>,
     color: vec3<f32>,
     radius: f32,
+    outline_color: vec3<f32>,
+    outline_width: f32,
+    alpha: f32,
+    color2: vec3<f32>,
+    gradient_angle: f32,
+    use_gradient: u32,
+    layer: i32,
+    emissive: f32,
 };
 
 @group(1)
@@ -41,16 +82,110 @@ fn vertex(input: VertexInput) -> VertexOutput {
 
     let world_position = output.uv * circles[input.circle_index].radius + circles[input.circle_index].position;
 
-    output.clip_position = vec4<f32>((world_position - camera.position) * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+    output.clip_position = world_to_clip(world_position);
 
     return output;
 }
 
+// the color inside of the circle, `distance` is negative inside and `edge_width` is the world size of the anti-aliased edge
+fn circle_color(circle: Circle, uv: vec2<f32>, distance: f32, edge_width: f32) -> vec3<f32> {
+    // uv is -1 to 1 across the circle in every direction, so this goes from 0 to 1 along the gradient
+    let direction = vec2<f32>(cos(circle.gradient_angle), sin(circle.gradient_angle));
+    let t = clamp(dot(uv, direction) * 0.5 + 0.5, 0.0, 1.0);
+    let fill_color = select(circle.color, mix(circle.color, circle.color2, t), circle.use_gradient != 0u);
+
+    // most circles have no outline, so they skip working out where it starts
+    if circle.outline_width <= 0.0 {
+        return fill_color;
+    }
+
+    let fill = clamp(0.5 - (distance + circle.outline_width) / edge_width, 0.0, 1.0);
+    return mix(circle.outline_color, fill_color, fill);
+}
+
 @fragment
 fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
-    if dot(input.uv, input.uv) > 1.0 {
+    let circle = circles[input.circle_index];
+
+    // signed distance to the edge of the circle in world units, negative inside
+    let uv_length = length(input.uv);
+    let distance = (uv_length - 1.0) * circle.radius;
+
+    // the screen space gradient of the distance, worked out by hand because fwidth of the
+    // non-linear length is unreliable along the diagonal of the quad on some drivers
+    let normal = input.uv / max(uv_length, 0.0001);
+    let gradient = vec2<f32>(dot(normal, dpdx(input.uv)), dot(normal, dpdy(input.uv))) * circle.radius;
+    let pixel_size = length(gradient);
+
+    // the edge is spread over edge_softness pixels, in screen space so it stays the same width at any zoom
+    let edge_width = pixel_size * max(output.edge_softness, 0.01);
+
+    let alpha = clamp(0.5 - distance / edge_width, 0.0, 1.0);
+    if alpha <= 0.0 {
         discard;
     }
 
-    return vec4<f32>(circles[input.circle_index].color, 1.0);
+    let color = circle_color(circle, input.uv, distance, edge_width);
+
+    // the edge coverage multiplies into the circles own alpha so transparent circles stay smooth
+    let coverage = alpha * circle.alpha;
+    return vec4<f32>(encode_output(color) * coverage, coverage);
+}
+
+// the same as pixel with a hard edge, without the derivatives or the anti-aliasing which are most of the cost
+// when there are lots of tiny circles
+@fragment
+fn pixel_fast(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv_length_squared = dot(input.uv, input.uv);
+    if uv_length_squared > 1.0 {
+        discard;
+    }
+
+    let circle = circles[input.circle_index];
+    let distance = (sqrt(uv_length_squared) - 1.0) * circle.radius;
+    // a tiny edge width makes the outline hard too
+    let color = circle_color(circle, input.uv, distance, 1e-6);
+
+    return vec4<f32>(encode_output(color) * circle.alpha, circle.alpha);
+}
+
+// the glow drawn into the bloom target, which is linear and added together so the order doesnt matter
+@fragment
+fn emissive(input: VertexOutput) -> @location(0) vec4<f32> {
+    let circle = circles[input.circle_index];
+
+    let uv_length = length(input.uv);
+    let distance = (uv_length - 1.0) * circle.radius;
+
+    let normal = input.uv / max(uv_length, 0.0001);
+    let gradient = vec2<f32>(dot(normal, dpdx(input.uv)), dot(normal, dpdy(input.uv))) * circle.radius;
+    let pixel_size = length(gradient);
+
+    let edge_width = pixel_size * max(output.edge_softness, 0.01);
+
+    let alpha = clamp(0.5 - distance / edge_width, 0.0, 1.0);
+    if alpha <= 0.0 || circle.emissive <= 0.0 {
+        discard;
+    }
+
+    let color = circle_color(circle, input.uv, distance, edge_width);
+    let coverage = alpha * circle.alpha;
+    return vec4<f32>(color * circle.emissive * coverage, coverage);
+}
+
+// how much of the pixel the circle covers, added up in the density target so overlapping circles count each
+@fragment
+fn density(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv_length = length(input.uv);
+    let distance = (uv_length - 1.0) * circles[input.circle_index].radius;
+
+    let normal = input.uv / max(uv_length, 0.0001);
+    let gradient = vec2<f32>(dot(normal, dpdx(input.uv)), dot(normal, dpdy(input.uv))) * circles[input.circle_index].radius;
+    let pixel_size = length(gradient);
+
+    let coverage = clamp(0.5 - distance / max(pixel_size, 1e-12), 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(coverage, 0.0, 0.0, 0.0);
 }
@@ -0,0 +1,208 @@
+use cgmath::Vector3;
+use eframe::egui;
+
+/// egui colors are srgb encoded while the shapes are given linear colors, so the shaders can blend and
+/// mix gradients in linear space, and then encode them for whatever format the target is
+pub fn srgb_to_linear(color: egui::Color32) -> Vector3<f32> {
+    let [r, g, b, _] = color.to_array();
+    Vector3 {
+        x: decode_srgb(r as f32 / 255.0),
+        y: decode_srgb(g as f32 / 255.0),
+        z: decode_srgb(b as f32 / 255.0),
+    }
+}
+
+/// The inverse of [`srgb_to_linear`], for editing shape colors with eguis color pickers
+pub fn linear_to_srgb(color: Vector3<f32>) -> egui::Color32 {
+    let [r, g, b] = [color.x, color.y, color.z]
+        .map(|channel| (encode_srgb(channel.clamp(0.0, 1.0)) * 255.0).round() as u8);
+    egui::Color32::from_rgb(r, g, b)
+}
+
+/// The srgb transfer function for a single 0 to 1 channel, the same one the shaders use
+pub fn decode_srgb(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn encode_srgb(channel: f32) -> f32 {
+    if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Goes from blue at `0.0` through cyan, green and yellow to red at `1.0`, as a linear color
+pub fn speed_ramp(t: f32) -> Vector3<f32> {
+    // in srgb as thats how color ramps are usually written down
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 1.0],
+        [0.0, 1.0, 1.0],
+        [0.0, 1.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [1.0, 0.0, 0.0],
+    ];
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let fraction = position - index as f32;
+    let [r, g, b] = [0, 1, 2].map(|channel| {
+        let (from, to) = (STOPS[index][channel], STOPS[index + 1][channel]);
+        decode_srgb(from + (to - from) * fraction)
+    });
+    Vector3 { x: r, y: g, z: b }
+}
+
+/// Which controls [`color_edit`] shows, shared by every color edit so picking one applies everywhere
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum ColorEditMode {
+    #[default]
+    Rgb,
+    Hsv,
+    Hex,
+}
+
+/// Edits a linear shape color with eguis color button, along with rgb or hsv sliders or a hex field,
+/// all of which work with the srgb encoded color like most color pickers do
+pub fn color_edit(ui: &mut egui::Ui, color: &mut Vector3<f32>) -> egui::Response {
+    let mode_id = egui::Id::new("color_edit_mode");
+    let mut mode: ColorEditMode = ui.data_mut(|data| *data.get_temp_mut_or_default(mode_id));
+    let mut srgb = linear_to_srgb(*color);
+    let mut changed = false;
+
+    let mut response = ui
+        .vertical(|ui| {
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba(&mut srgb).changed();
+                ui.selectable_value(&mut mode, ColorEditMode::Rgb, "RGB");
+                ui.selectable_value(&mut mode, ColorEditMode::Hsv, "HSV");
+                ui.selectable_value(&mut mode, ColorEditMode::Hex, "Hex");
+            });
+            match mode {
+                ColorEditMode::Rgb => {
+                    let [mut r, mut g, mut b, _] = srgb.to_array();
+                    let mut rgb_changed = false;
+                    for (channel, label) in [(&mut r, "R"), (&mut g, "G"), (&mut b, "B")] {
+                        rgb_changed |= ui
+                            .add(egui::Slider::new(channel, 0..=255).text(label))
+                            .changed();
+                    }
+                    if rgb_changed {
+                        srgb = egui::Color32::from_rgb(r, g, b);
+                        changed = true;
+                    }
+                }
+                ColorEditMode::Hsv => {
+                    let [r, g, b, _] = srgb.to_array();
+                    let [mut hue, mut saturation, mut value] =
+                        rgb_to_hsv([r, g, b].map(|channel| channel as f32 / 255.0));
+                    let mut hsv_changed = false;
+                    hsv_changed |= ui
+                        .add(egui::Slider::new(&mut hue, 0.0..=360.0).text("H"))
+                        .changed();
+                    hsv_changed |= ui
+                        .add(egui::Slider::new(&mut saturation, 0.0..=1.0).text("S"))
+                        .changed();
+                    hsv_changed |= ui
+                        .add(egui::Slider::new(&mut value, 0.0..=1.0).text("V"))
+                        .changed();
+                    if hsv_changed {
+                        let [r, g, b] = hsv_to_rgb([hue, saturation, value])
+                            .map(|channel| (channel * 255.0).round() as u8);
+                        srgb = egui::Color32::from_rgb(r, g, b);
+                        changed = true;
+                    }
+                }
+                ColorEditMode::Hex => {
+                    let [r, g, b, _] = srgb.to_array();
+                    let formatted = format!("#{r:02X}{g:02X}{b:02X}");
+                    // the text being typed is kept until it is a valid color, or the field loses focus
+                    let text_id = ui.id().with("color_edit_hex");
+                    let mut text: String = ui
+                        .data_mut(|data| data.get_temp(text_id))
+                        .unwrap_or_else(|| formatted.clone());
+                    let text_response =
+                        ui.add(egui::TextEdit::singleline(&mut text).desired_width(70.0));
+                    if text_response.changed() {
+                        if let Some(parsed) = parse_hex(&text) {
+                            srgb = parsed;
+                            changed = true;
+                        }
+                    }
+                    if text_response.has_focus() {
+                        ui.data_mut(|data| data.insert_temp(text_id, text));
+                    } else {
+                        ui.data_mut(|data| data.remove::<String>(text_id));
+                    }
+                }
+            }
+        })
+        .response;
+
+    ui.data_mut(|data| data.insert_temp(mode_id, mode));
+    if changed {
+        *color = srgb_to_linear(srgb);
+        response.mark_changed();
+    }
+    response
+}
+
+/// `#RRGGBB` or `#RGB`, the `#` is optional
+fn parse_hex(text: &str) -> Option<egui::Color32> {
+    let hex = text.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        6 => Some(egui::Color32::from_rgb(
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        )),
+        // each digit is doubled, so #F80 is #FF8800
+        3 => Some(egui::Color32::from_rgb(
+            channel(&hex[0..1])? * 17,
+            channel(&hex[1..2])? * 17,
+            channel(&hex[2..3])? * 17,
+        )),
+        _ => None,
+    }
+}
+
+/// Hue in degrees from 0 to 360, saturation and value from 0 to 1
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let range = max - min;
+    let hue = if range == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / range).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / range + 2.0)
+    } else {
+        60.0 * ((r - g) / range + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { range / max };
+    [hue, saturation, max]
+}
+
+fn hsv_to_rgb([hue, saturation, value]: [f32; 3]) -> [f32; 3] {
+    let chroma = value * saturation;
+    let sector = (hue / 60.0).rem_euclid(6.0);
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let [r, g, b] = match sector as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    let m = value - chroma;
+    [r + m, g + m, b + m]
+}
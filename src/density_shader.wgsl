@@ -0,0 +1,119 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct Density {
+    max_density: f32,
+    ramp: u32,
+};
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(0)
+var density_texture: texture_2d<f32>;
+
+@group(0)
+@binding(1)
+var density_sampler: sampler;
+
+@group(0)
+@binding(2)
+var<uniform> density: Density;
+
+@group(0)
+@binding(3)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn decode_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // a single quad covering the whole viewport, texture coordinates go down while clip space goes up
+    let clip = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+    output.clip_position = vec4<f32>(clip, 0.0, 1.0);
+    output.uv = vec2<f32>(clip.x * 0.5 + 0.5, 0.5 - clip.y * 0.5);
+
+    return output;
+}
+
+// evenly spaced stops picked by t from 0 to 1, in srgb as thats how color ramps are usually written down
+fn ramp_color(t: f32) -> vec3<f32> {
+    var stops: array<vec3<f32>, 5>;
+    switch density.ramp {
+        // viridis
+        case 1u: {
+            stops = array<vec3<f32>, 5>(
+                vec3<f32>(0.267, 0.005, 0.329),
+                vec3<f32>(0.229, 0.322, 0.545),
+                vec3<f32>(0.128, 0.567, 0.551),
+                vec3<f32>(0.369, 0.789, 0.383),
+                vec3<f32>(0.993, 0.906, 0.144),
+            );
+        }
+        // grayscale
+        case 2u: {
+            stops = array<vec3<f32>, 5>(
+                vec3<f32>(0.0),
+                vec3<f32>(0.25),
+                vec3<f32>(0.5),
+                vec3<f32>(0.75),
+                vec3<f32>(1.0),
+            );
+        }
+        // heat
+        default: {
+            stops = array<vec3<f32>, 5>(
+                vec3<f32>(0.0, 0.0, 0.5),
+                vec3<f32>(0.0, 0.5, 1.0),
+                vec3<f32>(1.0, 1.0, 0.0),
+                vec3<f32>(1.0, 0.3, 0.0),
+                vec3<f32>(1.0, 1.0, 1.0),
+            );
+        }
+    }
+    let position = clamp(t, 0.0, 1.0) * 4.0;
+    let index = min(u32(position), 3u);
+    return decode_srgb(mix(stops[index], stops[index + 1u], position - f32(index)));
+}
+
+// the density is how many circles cover each pixel, the ramp goes from one circle up to max_density of them
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let value = textureSampleLevel(density_texture, density_sampler, input.uv, 0.0).r;
+    if value <= 0.0 {
+        discard;
+    }
+
+    let t = (value - 1.0) / max(density.max_density - 1.0, 0.001);
+    // the anti-aliased edges of lone circles fade out instead of being cut off
+    let alpha = clamp(value, 0.0, 1.0);
+    return vec4<f32>(encode_output(ramp_color(t)) * alpha, alpha);
+}
@@ -0,0 +1,101 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) ellipse_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) ellipse_index: u32,
+    @location(1) uv: vec2<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+struct Ellipse {
+    position: vec2<f32>,
+    color: vec3<f32>,
+    radii: vec2<f32>,
+};
+
+@group(1)
+@binding(0)
+var<storage, read> ellipses: array<Ellipse>;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.ellipse_index = input.ellipse_index;
+
+    output.uv = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+
+    let world_position = output.uv * ellipses[input.ellipse_index].radii + ellipses[input.ellipse_index].position;
+
+    output.clip_position = world_to_clip(world_position);
+
+    return output;
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let ellipse = ellipses[input.ellipse_index];
+
+    // implicit distance in the ellipse's own space, dividing by its screen space gradient
+    // turns it into a distance in pixels, so the edge is the same width whatever the radii are
+    let uv_length = length(input.uv);
+    let distance = uv_length - 1.0;
+
+    let normal = input.uv / max(uv_length, 0.0001);
+    let gradient = vec2<f32>(dot(normal, dpdx(input.uv)), dot(normal, dpdy(input.uv)));
+    let pixel_size = length(gradient);
+
+    let alpha = clamp(0.5 - distance / pixel_size, 0.0, 1.0);
+    if alpha <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(encode_output(ellipse.color) * alpha, alpha);
+}
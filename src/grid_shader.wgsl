@@ -0,0 +1,94 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec2<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+struct Grid {
+    spacing: f32,
+    major_every: u32,
+    minor_color: vec3<f32>,
+    major_color: vec3<f32>,
+};
+
+@group(1)
+@binding(0)
+var<uniform> grid: Grid;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // a single quad covering the whole screen, the inverse of the camera transform gives the world position
+    let clip = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+    output.clip_position = vec4<f32>(clip, 0.0, 1.0);
+    let view = clip * vec2<f32>(camera.aspect, 1.0) / camera.zoom;
+    let s = sin(camera.rotation);
+    let c = cos(camera.rotation);
+    output.world_position = vec2<f32>(view.x * c - view.y * s, view.x * s + view.y * c) + camera.position;
+
+    return output;
+}
+
+// how much of the pixel is covered by a 1 pixel wide line on every whole value of `cell`,
+// the derivative is passed in because the gl backend would also emit the fwidth into the vertex shader
+fn line_coverage(cell: vec2<f32>, cells_per_pixel: vec2<f32>) -> f32 {
+    let distance = abs(fract(cell - 0.5) - 0.5) / cells_per_pixel;
+    return 1.0 - min(min(distance.x, distance.y), 1.0);
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let cell = input.world_position / grid.spacing;
+    let cells_per_pixel = fwidth(cell);
+    let major_every = f32(max(grid.major_every, 1u));
+
+    // fade the minor lines out before they get so close together that they fill the screen
+    let fade = 1.0 - smoothstep(0.1, 0.25, max(cells_per_pixel.x, cells_per_pixel.y));
+    let minor = line_coverage(cell, cells_per_pixel) * fade;
+    let major = line_coverage(cell / major_every, cells_per_pixel / major_every);
+
+    let coverage = max(minor, major);
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    let color = select(grid.minor_color, grid.major_color, major >= minor);
+    return vec4<f32>(encode_output(color) * coverage, coverage);
+}
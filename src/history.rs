@@ -0,0 +1,88 @@
+use crate::scene::Scene;
+use std::collections::VecDeque;
+
+/// Snapshots of the scene from before each edit, so the edits can be undone and redone
+pub struct History {
+    undo_stack: VecDeque<Scene>,
+    redo_stack: Vec<Scene>,
+    /// The oldest snapshots are dropped once there are more than this many
+    max_entries: usize,
+}
+
+impl History {
+    pub fn new(max_entries: usize) -> History {
+        History {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            max_entries,
+        }
+    }
+
+    /// Records `scene` as it was before an edit, anything that was undone can no longer be redone
+    pub fn push(&mut self, scene: Scene) {
+        self.redo_stack.clear();
+        self.undo_stack.push_back(scene);
+        while self.undo_stack.len() > self.max_entries {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Puts `scene` back to before the last edit, returns false if there was nothing to undo
+    ///
+    /// The ids keep counting up from where they got to, so circles added after an undo get ids of their own
+    pub fn undo(&mut self, scene: &mut Scene) -> bool {
+        let Some(mut previous) = self.undo_stack.pop_back() else {
+            return false;
+        };
+        previous.continue_ids_from(scene);
+        self.redo_stack.push(std::mem::replace(scene, previous));
+        true
+    }
+
+    pub fn redo(&mut self, scene: &mut Scene) -> bool {
+        let Some(mut next) = self.redo_stack.pop() else {
+            return false;
+        };
+        next.continue_ids_from(scene);
+        self.undo_stack.push_back(std::mem::replace(scene, next));
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::GpuCircle;
+    use cgmath::Vector2;
+
+    #[test]
+    fn ids_arent_reused_after_undo() {
+        let mut history = History::new(10);
+        let mut scene = Scene::new();
+        history.push(scene.clone());
+        let undone = scene.add_circle(GpuCircle::new(Vector2 { x: 0.0, y: 0.0 }));
+
+        assert!(history.undo(&mut scene));
+        let added = scene.add_circle(GpuCircle::new(Vector2 { x: 1.0, y: 0.0 }));
+        assert_ne!(added, undone);
+
+        // redoing goes back to a scene that had only handed out the undone id
+        assert!(history.redo(&mut scene));
+        let again = scene.add_circle(GpuCircle::new(Vector2 { x: 2.0, y: 0.0 }));
+        assert_ne!(again, undone);
+        assert_ne!(again, added);
+    }
+}
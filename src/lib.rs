@@ -0,0 +1,2 @@
+//! The parts of the app that dont need a window or a gpu, so they can be benchmarked on their own
+pub mod physics;
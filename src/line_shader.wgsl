@@ -0,0 +1,119 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) line_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) line_index: u32,
+    @location(1) uv: vec2<f32>,
+    // world distance from where the drawn quad starts, the dashes are measured along it
+    @location(2) along: f32,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+struct Line {
+    start: vec2<f32>,
+    end: vec2<f32>,
+    width: f32,
+    color: vec3<f32>,
+    dash_length: f32,
+    gap_length: f32,
+};
+
+@group(1)
+@binding(0)
+var<storage, read> lines: array<Line>;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.line_index = input.line_index;
+
+    output.uv = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+
+    let line = lines[input.line_index];
+
+    let offset = line.end - line.start;
+    let line_length = length(offset);
+    var direction = vec2<f32>(1.0, 0.0);
+    if line_length > 0.0 {
+        direction = offset / line_length;
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // a very short line is drawn at least as long as it is wide so it never disappears
+    let half_length = max(line_length, line.width) * 0.5;
+    let center = (line.start + line.end) * 0.5;
+
+    output.along = (output.uv.x + 1.0) * half_length;
+
+    let world_position = center + direction * output.uv.x * half_length + normal * output.uv.y * line.width * 0.5;
+
+    output.clip_position = world_to_clip(world_position);
+
+    return output;
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let line = lines[input.line_index];
+
+    // along is linear in world space so its derivative is the world size of a pixel, which keeps the dashes
+    // the same size on screen at any zoom
+    let pixel_size = length(vec2<f32>(dpdx(input.along), dpdy(input.along)));
+    if line.dash_length > 0.0 {
+        let along = input.along / max(pixel_size, 1e-12);
+        let period = line.dash_length + max(line.gap_length, 0.0);
+        if along - floor(along / period) * period >= line.dash_length {
+            discard;
+        }
+    }
+
+    return vec4<f32>(encode_output(line.color), 1.0);
+}
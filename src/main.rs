@@ -1,191 +1,3236 @@
 #![allow(dead_code, unused)]
 
-use cgmath::{prelude::*, Vector2};
+use camera::{Camera, CameraAnimation, ViewError};
+use cgmath::{prelude::*, Vector2, Vector3};
 use eframe::{
     egui,
-    egui_wgpu::{Callback, WgpuConfiguration},
+    egui_wgpu::{self, Callback, WgpuConfiguration},
     wgpu::{self},
-    NativeOptions, Renderer,
 };
-use rendering::{create_render_state, GpuCamera, GpuCircle, RenderCallback};
-use std::collections::HashSet;
+use eframe_quads_and_circles::physics::{
+    self, Attractor, Backend, Boundary, BroadPhase, PhysicsCircle, PhysicsSettings, SpatialHash,
+};
+use history::History;
+use minimap::{Corner, Minimap};
+use presets::Preset;
+use rand::{rngs::StdRng, SeedableRng};
+use recorder::Recorder;
+use rendering::{
+    create_render_state, device_descriptor, gpu_time, instance_buffer_capacities, load_texture,
+    presentation_latency, render_to_image, supported_sample_counts, upload_texture, AlphaMode,
+    CircleQuality, DensityRamp, GpuBloom, GpuCamera, GpuCapsule, GpuCircle, GpuDensity, GpuEllipse,
+    GpuGrid, GpuLine, GpuPolygon, GpuRectangle, GpuRing, GpuSprite, GpuTime, RenderCallback,
+    Shapes,
+};
+use scene::{CircleSpring, LimitPolicy, Scene, SceneError, ShapeId};
+use settings::{PowerPreference, PresentMode, Settings};
+use spawner::Spawner;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    f32::consts::TAU,
+    sync::Arc,
+};
+use trails::Trails;
 
+mod camera;
+mod color;
+mod history;
+mod minimap;
+mod presets;
+mod recorder;
 mod rendering;
+mod scene;
+mod settings;
+mod spawner;
+mod svg;
+mod trails;
 
-struct Camera {
-    position: Vector2<f32>,
-    zoom: f32,
+const DEFAULT_CAMERA_ZOOM: f32 = 0.25;
+const DEFAULT_GRAVITY: Vector2<f32> = Vector2 { x: 0.0, y: -9.81 };
+const CAMERA_ANIMATION_DURATION: f32 = 0.3;
+const FIT_ALL_MARGIN: f32 = 1.1;
+/// How many points egui scrolls for one notch of a mouse wheel
+const SCROLL_POINTS_PER_NOTCH: f32 = 50.0;
+/// How far duplicated circles are moved from the originals, in world units
+const DUPLICATE_OFFSET: Vector2<f32> = Vector2 { x: 0.25, y: -0.25 };
+
+/// How many edits can be undone
+const MAX_HISTORY_ENTRIES: usize = 100;
+
+/// How far towards white the circle under the pointer is drawn
+const HOVER_BRIGHTEN: f32 = 0.25;
+
+/// How far the crosshair reaches out from the pointer, in pixels
+const CROSSHAIR_SIZE: f32 = 8.0;
+/// The lines, end markers and distance labels of the measure tool
+const MEASUREMENT_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 200, 0);
+
+/// The radius of circles placed with a click rather than dragged out
+const DEFAULT_BRUSH_RADIUS: f32 = 0.5;
+
+/// How many frame times the graph in the info window shows
+const FRAME_TIME_SAMPLES: usize = 240;
+/// Where the reference line in the frame time graph is drawn
+const TARGET_FRAME_TIME: std::time::Duration = std::time::Duration::from_micros(16_667);
+/// The frame rates that can be picked in the info window, `None` repaints as soon as the last frame is done
+const FRAME_RATE_LIMITS: [Option<u32>; 4] = [Some(30), Some(60), Some(120), None];
+
+const SCENE_PATH: &str = "scene.json";
+const SELECTION_PATH: &str = "selection.json";
+const SCREENSHOT_PATH: &str = "screenshot.png";
+const SVG_PATH: &str = "scene.svg";
+const RECORDING_DIRECTORY: &str = "recording";
+const SETTINGS_PATH: &str = "settings.json";
+
+/// What the primary button does in the central panel
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Select,
+    DrawCircle,
+    /// Clicking twice measures the distance between the two points
+    Measure,
+}
+
+/// What was right clicked to open the context menu
+#[derive(Clone, Copy)]
+enum ContextMenuTarget {
+    Circle(ShapeId),
+    /// Where in the world the empty space was clicked
+    Empty(Vector2<f32>),
 }
 
 struct App {
-    last_frame_time: Option<std::time::Instant>,
+    last_frame_time: Option<web_time::Instant>,
+    /// Frames per second, see [`FRAME_RATE_LIMITS`]
+    frame_rate_limit: Option<u32>,
+    circle_quality: CircleQuality,
+    /// The most recent frame times, oldest first
+    frame_times: VecDeque<std::time::Duration>,
     info_window_open: bool,
     background_color: egui::Color32,
     physics_ticks: u32,
     physics_time: std::time::Duration,
+    max_physics_steps: u32,
+    physics_steps_last_frame: u32,
     time_scale: f32,
+    /// Stops the physics steps without touching `time_scale`, so unpausing goes back to the same speed
+    paused: bool,
+    /// Set by "Step" to run a single physics step while paused
+    step_requested: bool,
+    physics: PhysicsSettings,
+    #[cfg(feature = "rapier")]
+    rapier_world: physics::rapier::RapierWorld,
+    spawner: Spawner,
+    trails: Trails,
+    /// The stiffness "Connect With Springs" gives the springs it adds
+    spring_stiffness: f32,
+    /// Seeds everything random, the random circles and the spawner, so a run can be repeated exactly
+    seed: u64,
+    /// How many circles "Regenerate" fills the boundary with
+    random_circle_count: u32,
+    /// Nothing adds more shapes than this, see [`Scene::make_room`]
+    max_shapes: usize,
+    /// What happens when an edit like drawing, pasting or duplicating would go over the shape limit,
+    /// the spawner has a policy of its own
+    edit_limit_policy: LimitPolicy,
+    /// How many shapes the last refused edit would have added, until one gets through
+    shapes_refused: Option<usize>,
+    /// How hard holding F pulls the circles towards the cursor, shift pushes them away instead
+    attractor_strength: f32,
+    /// An attractor that stays in place when the cursor one isnt held, from the last loaded preset
+    fixed_attractor: Option<Attractor>,
+    physics_time_last_frame: std::time::Duration,
+    draw_boundary: bool,
+    show_grid: bool,
+    /// Distance between the minor grid lines in world units
+    grid_spacing: f32,
+    grid_major_every: u32,
+    /// Puts dragged, added and duplicated circles on the grid, only while it is shown
+    snap_to_grid: bool,
+    /// Makes the emissive circles glow
+    bloom_enabled: bool,
+    /// In pixels of the half size bloom target
+    bloom_radius: f32,
+    bloom_intensity: f32,
+    /// Shows how many circles cover each pixel as a heatmap instead of drawing them
+    density_enabled: bool,
+    density_ramp: DensityRamp,
+    /// How many overlapping circles reach the end of the ramp
+    max_density: f32,
+    /// How many pixels wide the anti-aliased edge of the circles is
+    edge_softness: f32,
     camera: Camera,
+    camera_animation: Option<CameraAnimation>,
+    /// The circle the camera keeps centered, cleared by panning or when the circle is removed
+    follow: Option<ShapeId>,
+    /// Roughly how many seconds the camera takes to catch up with the followed circle, 0 to stay exactly on it
+    follow_smoothing: f32,
+    minimap: Minimap,
+    /// Sprites arent part of the scene as the textures they use only exist until the app is closed
+    sprites: Vec<GpuSprite>,
+    /// What was drawn last frame, kept so its vectors can be reused once egui is done with them
+    frame_shapes: Option<Arc<Shapes>>,
+    sprite_path: String,
+    /// The texture id and size in pixels of every image loaded so far, so adding the same one again reuses it
+    sprite_textures: HashMap<String, (u32, [u32; 2])>,
+    sprite_error: Option<String>,
+    fit_all_requested: bool,
+    scroll_zoom_factor: f32,
+    /// How many times `scroll_zoom_factor` is applied per point scrolled
+    ///
+    /// egui 0.23 only has the raw `scroll_delta`, later versions split it into a smoothed and a raw delta,
+    /// the raw one is what the zoom wants as the camera is moved straight away rather than animated
+    scroll_zoom_sensitivity: f32,
+    /// Two finger scrolling on a trackpad pans instead of zooming, pinching still zooms
+    ///
+    /// egui doesnt say whether scrolling came from a mouse wheel or a trackpad so this is up to the user
+    scroll_pans: bool,
+    /// How many screen heights per second the keyboard pans the camera by
+    ///
+    /// WASD always pans, the arrow keys only pan while nothing is selected as they nudge the selection otherwise
+    keyboard_pan_speed: f32,
+    keyboard_pan_fast_multiplier: f32,
+    /// How many radians per second Q and E turn the camera by
+    keyboard_turn_speed: f32,
+    scene: Scene,
+    history: History,
+    /// The scene from before the current drag, recorded in the history once the drag ends
+    scene_before_drag: Option<Scene>,
+    /// The scene from before the inspector started changing it, recorded in the history once nothing is being dragged
+    scene_before_inspector_edit: Option<Scene>,
+    /// The scene from before the arrow keys started nudging the selection, recorded in the history once they are let go
+    scene_before_nudge: Option<Scene>,
+    scene_file_error: Option<String>,
+    /// A view string from "Copy View" to be applied by "Paste View"
+    view_text: String,
+    view_error: Option<String>,
+    /// The file the circles are imported from by "Import CSV"
+    csv_path: String,
+    /// How the last csv import went
+    csv_import_status: Option<String>,
+    tool: Tool,
+    /// Linear like the shape colors
+    brush_color: Vector3<f32>,
+    /// The circle being dragged out with the draw circle tool, added to the scene on release
+    drawing_circle: Option<GpuCircle>,
+    /// Only circles can be selected, so delete and duplicate only ever act on circles, the other shapes in
+    /// the scene dont have ids to select them by
+    selected: HashSet<ShapeId>,
+    /// Where the rubber band selection started and where the pointer is now, in world space
+    selection_box: Option<(Vector2<f32>, Vector2<f32>)>,
+    /// The circle being dragged with the primary button, and where it is relative to the pointer
+    grabbed: Option<(ShapeId, Vector2<f32>)>,
+    /// The world position under the pointer last frame, `None` when it isnt over the central panel
+    cursor_world_position: Option<Vector2<f32>>,
+    /// Every finished measurement, kept until cleared
+    measurements: Vec<(Vector2<f32>, Vector2<f32>)>,
+    /// Where the measurement being made started, the cursor is the other end until the second click
+    measure_start: Option<Vector2<f32>>,
+    /// The world point under the pointer when the right drag started, which panning keeps under the pointer
+    pan_anchor: Option<Vector2<f32>>,
+    /// The open context menu, with where on the screen it was opened
+    context_menu: Option<(egui::Pos2, ContextMenuTarget)>,
+    /// Set by "Edit..." to expand the inspector next frame
+    inspector_open_requested: bool,
+    show_crosshair: bool,
+    /// Draws an arrow from every circle along its velocity, only for looking at and not part of the physics
+    show_velocities: bool,
+    /// Outlines the cells of a spatial hash of the circles and tints them by how many circles are in each
+    show_spatial_hash: bool,
+    shape_list_open: bool,
+    /// Only shapes whose name or type contains this are listed, ignoring case
+    shape_list_filter: String,
+    /// Moves the camera to a shape when it is clicked in the shape list
+    center_on_click: bool,
+    /// How many world units long an arrow is for each unit per second of speed
+    velocity_scale: f32,
+    /// Linear like the shape colors
+    velocity_color: Vector3<f32>,
+    /// Draws every circle with a color from [`color::speed_ramp`] by how fast it is going instead of its own color
+    color_by_speed: bool,
+    /// The speeds that get the slow and fast ends of the ramp, in units per second
+    speed_color_range: (f32, f32),
+    /// Draws the name of every named circle above it
+    show_labels: bool,
+    /// Labels the circles without a name with their id
+    label_ids: bool,
+    /// In points, at the default zoom when scaling with the zoom
+    label_font_size: f32,
+    label_scale_with_zoom: bool,
+    /// The smallest and largest font size a label scaled with the zoom can have, in points
+    label_font_size_range: (f32, f32),
+    /// Whether letting go of a grabbed circle gives it the pointers velocity
+    throw_on_release: bool,
+    /// 1 turns msaa off, the renderer falls back to a lower count if this one isnt supported
+    sample_count: u32,
+    settings: Settings,
+    /// The present mode the surface was created with, `settings` holds the one for the next launch
+    launch_present_mode: PresentMode,
+    /// Like `launch_present_mode` but for the adapter
+    launch_power_preference: PowerPreference,
+    settings_error: Option<String>,
+    export_requested: bool,
+    svg_export_requested: bool,
+    /// `Some` while recording, each frame is then simulated as taking exactly `1 / record_frame_rate` seconds
+    recorder: Option<Recorder>,
+    record_frame_rate: u32,
+    /// Only every nth simulated frame is written, so the frames play back at the recorded speed at
+    /// `record_frame_rate / record_every_nth` fps
+    record_every_nth: u32,
+    /// How the last recording went, shown once its stopped
+    record_status: Option<String>,
+    export_width: u32,
+    export_height: u32,
+    export_error: Option<String>,
 }
 
 impl App {
-    fn new(cc: &eframe::CreationContext) -> App {
+    fn new(cc: &eframe::CreationContext, settings: Settings) -> App {
         create_render_state(cc);
+
+        let mut scene = Scene::new();
+        scene.add_circle(GpuCircle::new(cgmath::vec2(0.0, 0.0)).with_color_rgb(1.0, 0.0, 0.0));
+        scene.add_circle_with_velocity(
+            GpuCircle::new(cgmath::vec2(-4.0, 2.5))
+                .with_color_rgb(0.0, 0.0, 1.0)
+                .with_radius(0.5),
+            cgmath::vec2(2.0, 0.0),
+        );
+        scene.add_circle_with_velocity(
+            GpuCircle::new(cgmath::vec2(4.0, 2.5))
+                .with_color_rgb(0.0, 1.0, 0.0)
+                .with_radius(0.5)
+                .with_alpha(0.5),
+            cgmath::vec2(-2.0, 0.0),
+        );
+        scene.add_rectangle(
+            GpuRectangle::new(cgmath::vec2(4.0, -1.5))
+                .with_color_rgb(1.0, 1.0, 0.0)
+                .with_size(cgmath::vec2(2.0, 1.0))
+                .with_rotation(0.3)
+                .with_corner_radius(0.2)
+                .with_outline(cgmath::vec3(1.0, 0.5, 0.0), 0.08)
+                .with_dashes(12.0, 8.0),
+        );
+        scene.add_ellipse(GpuEllipse {
+            position: cgmath::vec2(-4.0, -1.5),
+            color: cgmath::vec3(1.0, 0.0, 1.0),
+            radii: cgmath::vec2(1.5, 0.5),
+        });
+        scene.add_capsule(GpuCapsule {
+            start: cgmath::vec2(-1.0, 2.5),
+            end: cgmath::vec2(1.0, 3.0),
+            radius: 0.25,
+            color: cgmath::vec3(0.2, 1.0, 0.4),
+        });
+        scene.add_ring(GpuRing {
+            position: cgmath::vec2(4.0, 2.5),
+            inner_radius: 0.35,
+            outer_radius: 0.5,
+            start_angle: TAU * 0.25,
+            end_angle: TAU,
+            color: cgmath::vec3(0.2, 0.6, 1.0),
+        });
+        scene.add_polygon(
+            GpuPolygon::regular(cgmath::vec2(-4.0, 2.5), 0.5, 6)
+                .with_color(cgmath::vec3(1.0, 0.8, 0.2)),
+        );
+        scene.add_line(GpuLine {
+            start: cgmath::vec2(-4.0, -1.5),
+            end: cgmath::vec2(4.0, -1.5),
+            width: 0.05,
+            color: cgmath::vec3(1.0, 1.0, 1.0),
+            dash_length: 0.0,
+            gap_length: 0.0,
+        });
+
+        let mut camera = Camera {
+            position: settings.camera_position,
+            zoom: DEFAULT_CAMERA_ZOOM,
+            rotation: 0.0,
+            min_zoom: 0.001,
+            max_zoom: 1000.0,
+        };
+        // the settings file could have been edited by hand
+        camera.set_zoom(settings.camera_zoom);
+        camera.make_finite();
+        let [r, g, b] = settings.background_color;
+        let mut spawner = Spawner::new(Vector2 { x: -6.0, y: -3.0 }, 0);
+        spawner.limit_policy = settings.spawner_limit_policy;
+
         App {
             last_frame_time: None,
+            frame_rate_limit: settings.frame_rate_limit.filter(|&limit| limit > 0),
+            circle_quality: settings.circle_quality,
+            frame_times: VecDeque::with_capacity(FRAME_TIME_SAMPLES),
             info_window_open: true,
-            background_color: egui::Color32::from_rgb(0, 0, 0),
-            physics_ticks: 100,
+            background_color: egui::Color32::from_rgb(r, g, b),
+            physics_ticks: settings.physics_ticks.clamp(1, 1000),
             physics_time: std::time::Duration::ZERO,
-            time_scale: 1.0,
-            camera: Camera {
-                position: Vector2 { x: 0.0, y: 0.0 },
-                zoom: 0.25,
+            max_physics_steps: 10,
+            physics_steps_last_frame: 0,
+            time_scale: if settings.time_scale.is_finite() {
+                settings.time_scale
+            } else {
+                1.0
             },
+            paused: false,
+            step_requested: false,
+            physics: PhysicsSettings {
+                gravity: DEFAULT_GRAVITY,
+                boundary: Boundary {
+                    min: Vector2 { x: -7.0, y: -3.5 },
+                    max: Vector2 { x: 7.0, y: 3.5 },
+                    restitution: 0.9,
+                },
+                backend: Backend::BuiltIn,
+                broad_phase: BroadPhase::SpatialHash,
+                attractor: None,
+                substeps: 1,
+            },
+            #[cfg(feature = "rapier")]
+            rapier_world: physics::rapier::RapierWorld::new(),
+            spawner,
+            trails: Trails::new(),
+            spring_stiffness: 0.5,
+            seed: 0,
+            random_circle_count: 200,
+            max_shapes: settings.max_shapes.max(1),
+            edit_limit_policy: settings.edit_limit_policy,
+            shapes_refused: None,
+            attractor_strength: 20.0,
+            fixed_attractor: None,
+            physics_time_last_frame: std::time::Duration::ZERO,
+            draw_boundary: true,
+            show_grid: true,
+            grid_spacing: 1.0,
+            grid_major_every: 5,
+            snap_to_grid: false,
+            bloom_enabled: false,
+            bloom_radius: 4.0,
+            bloom_intensity: 1.0,
+            density_enabled: false,
+            density_ramp: DensityRamp::default(),
+            max_density: 8.0,
+            edge_softness: 1.0,
+            camera,
+            camera_animation: None,
+            follow: None,
+            follow_smoothing: 0.1,
+            sprites: Vec::new(),
+            frame_shapes: None,
+            sprite_path: "sprite.png".to_string(),
+            sprite_textures: HashMap::new(),
+            sprite_error: None,
+            minimap: Minimap {
+                enabled: false,
+                size: 200.0,
+                corner: Corner::BottomRight,
+            },
+            fit_all_requested: false,
+            scroll_zoom_factor: 0.9,
+            // one wheel notch zooms by the factor once
+            scroll_zoom_sensitivity: 1.0 / SCROLL_POINTS_PER_NOTCH,
+            scroll_pans: false,
+            keyboard_pan_speed: 1.0,
+            keyboard_pan_fast_multiplier: 3.0,
+            keyboard_turn_speed: std::f32::consts::FRAC_PI_2,
+            scene,
+            history: History::new(MAX_HISTORY_ENTRIES),
+            scene_before_drag: None,
+            scene_before_inspector_edit: None,
+            scene_before_nudge: None,
+            scene_file_error: None,
+            view_text: String::new(),
+            view_error: None,
+            csv_path: "circles.csv".to_string(),
+            csv_import_status: None,
+            tool: Tool::Select,
+            brush_color: color::srgb_to_linear(egui::Color32::from_rgb(255, 128, 0)),
+            drawing_circle: None,
+            selected: HashSet::new(),
+            selection_box: None,
+            grabbed: None,
+            cursor_world_position: None,
+            measurements: Vec::new(),
+            measure_start: None,
+            pan_anchor: None,
+            context_menu: None,
+            inspector_open_requested: false,
+            show_crosshair: false,
+            show_velocities: false,
+            show_spatial_hash: false,
+            shape_list_open: false,
+            shape_list_filter: String::new(),
+            center_on_click: true,
+            velocity_scale: 0.25,
+            color_by_speed: false,
+            speed_color_range: (0.0, 10.0),
+            velocity_color: Vector3 {
+                x: 0.1,
+                y: 1.0,
+                z: 0.2,
+            },
+            show_labels: true,
+            label_ids: false,
+            label_font_size: 14.0,
+            label_scale_with_zoom: false,
+            label_font_size_range: (8.0, 32.0),
+            throw_on_release: true,
+            sample_count: 1,
+            launch_present_mode: settings.present_mode,
+            launch_power_preference: settings.power_preference,
+            settings,
+            settings_error: None,
+            export_requested: false,
+            svg_export_requested: false,
+            recorder: None,
+            record_frame_rate: 60,
+            record_every_nth: 1,
+            record_status: None,
+            export_width: 3840,
+            export_height: 2160,
+            export_error: None,
         }
     }
 }
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
-        let time = std::time::Instant::now();
-        let dt = time.duration_since(self.last_frame_time.unwrap_or(time));
-        self.last_frame_time = Some(time);
-        self.physics_time += dt.mul_f32(self.time_scale.abs());
+impl App {
+    fn physics_time_step(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(1) / self.physics_ticks
+    }
+
+    /// How far between the last physics step and the next one the simulation is, from `0.0` to `1.0`
+    fn physics_alpha(&self) -> f32 {
+        (self.physics_time.as_secs_f32() / self.physics_time_step().as_secs_f32()).min(1.0)
+    }
+
+    /// Runs as many fixed physics steps as `dt` covers, returning how many ran
+    ///
+    /// At most `max_physics_steps` are run, any time beyond that is dropped so a long
+    /// hitch doesnt cause more and more steps every frame
+    ///
+    /// While paused no time passes, and only a requested step is run
+    fn advance_physics(&mut self, dt: std::time::Duration) -> u32 {
+        let start_time = web_time::Instant::now();
+        let steps = if self.paused {
+            let step_requested = std::mem::take(&mut self.step_requested);
+            if step_requested {
+                self.physics_step();
+            }
+            step_requested as u32
+        } else {
+            let (steps, left_over) = physics::fixed_steps(
+                self.physics_time,
+                dt.mul_f32(self.time_scale.abs()),
+                self.physics_time_step(),
+                self.max_physics_steps,
+            );
+            self.physics_time = left_over;
+            for _ in 0..steps {
+                self.physics_step();
+            }
+            steps
+        };
+        self.physics_time_last_frame = start_time.elapsed();
+        steps
+    }
 
-        let time_step = std::time::Duration::from_secs(1) / self.physics_ticks;
+    /// One fixed physics step, backwards when the time scale is negative
+    fn physics_step(&mut self) {
+        let time_step = self.physics_time_step();
         let ts = time_step.as_secs_f32() * self.time_scale.signum();
-        while self.physics_time >= time_step {
-            // maybe do phyiscs stuff here
+        // lifetimes count down even when running backwards, there is no way to bring a circle back
+        self.spawner
+            .update(&mut self.scene, self.max_shapes, time_step.as_secs_f32());
+        self.scene.age_circles(time_step.as_secs_f32());
+        // recorded before the step so the trail never gets ahead of where the circle is drawn
+        self.trails.record(&self.scene);
+        match self.physics.backend {
+            Backend::BuiltIn => self.scene.step_physics(&self.physics, ts),
+            #[cfg(feature = "rapier")]
+            Backend::Rapier => {
+                self.scene
+                    .step_physics_rapier(&mut self.rapier_world, &self.physics, ts)
+            }
+        }
+    }
+
+    /// Rounds `position` to the nearest crossing of the minor grid lines when snapping to the grid
+    fn snap(&self, position: Vector2<f32>) -> Vector2<f32> {
+        if !(self.snap_to_grid && self.show_grid) {
+            return position;
+        }
+        (position / self.grid_spacing).map(f32::round) * self.grid_spacing
+    }
+
+    /// How far duplicates are moved from the originals, a whole grid cell when snapping so they stay on the grid
+    fn duplicate_offset(&self) -> Vector2<f32> {
+        if !(self.snap_to_grid && self.show_grid) {
+            return DUPLICATE_OFFSET;
+        }
+        Vector2 {
+            x: self.grid_spacing,
+            y: -self.grid_spacing,
+        }
+    }
+
+    /// How far one press of an arrow key nudges the selection, a whole grid cell when snapping so it stays on the grid
+    ///
+    /// Shift nudges by ten times as far, or a major grid cell when snapping
+    fn nudge_step(&self, fast: bool) -> f32 {
+        match (self.snap_to_grid && self.show_grid, fast) {
+            (true, false) => self.grid_spacing,
+            (true, true) => self.grid_spacing * self.grid_major_every as f32,
+            (false, false) => self.grid_spacing * 0.1,
+            (false, true) => self.grid_spacing,
+        }
+    }
+
+    /// Jumps to a view from [`Camera::view_string`], without animating so it is exactly what was copied
+    fn apply_view(&mut self, text: &str) -> Result<(), ViewError> {
+        self.camera.apply_view_string(text)?;
+        self.follow = None;
+        self.camera_animation = None;
+        Ok(())
+    }
+
+    /// Makes room for an edit that adds `count` shapes following the edit limit policy, returning whether they fit
+    ///
+    /// The scene is pushed to the history first when they do, so undo also brings back any recycled circles
+    fn prepare_to_add(&mut self, count: usize) -> bool {
+        let mut scene = self.scene.clone();
+        if !scene.make_room(count, self.max_shapes, self.edit_limit_policy) {
+            self.shapes_refused = Some(count);
+            return false;
+        }
+        self.shapes_refused = None;
+        self.history.push(std::mem::replace(&mut self.scene, scene));
+        true
+    }
+
+    /// Adds copies of the circles moved by the duplicate offset and selects them
+    ///
+    /// They are copied before making room, as recycling could remove the originals
+    fn duplicate(&mut self, ids: &[ShapeId]) {
+        let copies = self.scene.extract_circles(ids);
+        if self.prepare_to_add(copies.shape_count()) {
+            self.selected = self
+                .scene
+                .append(&copies, self.duplicate_offset())
+                .into_iter()
+                .collect();
+        }
+    }
+
+    /// Swaps in a loaded or generated scene, the old one can be brought back with undo
+    ///
+    /// The new circles get ids the old scene never used, so the selection and followed circle from before cant
+    /// point at one of them, now or after undoing back and forth
+    fn replace_scene(&mut self, mut scene: Scene) {
+        scene.renumber_ids_after(&self.scene);
+        self.history.push(std::mem::replace(&mut self.scene, scene));
+        self.selected.clear();
+        self.follow = None;
+        self.grabbed = None;
+        self.trails.clear();
+    }
+
+    /// Forgets anything about the scene from before an undo or redo, and drops the selected and followed
+    /// circles that arent in the restored scene
+    fn scene_restored(&mut self) {
+        self.grabbed = None;
+        self.scene_before_drag = None;
+        self.trails.clear();
+        self.selected.retain(|&id| self.scene.circle(id).is_some());
+        if self
+            .follow
+            .is_some_and(|id| self.scene.circle(id).is_none())
+        {
+            self.follow = None;
+        }
+    }
+
+    /// Replaces the scene with a freshly generated preset from the seed, unless it has more shapes than the limit allows
+    fn load_preset(&mut self, preset: Preset) {
+        let scene = preset.generate(self.seed, &self.physics.boundary);
+        // like regenerating, there is nothing to recycle when the whole scene is replaced
+        if scene.shape_count() > self.max_shapes {
+            self.shapes_refused = Some(scene.shape_count());
+            return;
+        }
+        self.shapes_refused = None;
+        self.replace_scene(scene);
+        let physics = preset.physics(&self.physics.boundary);
+        self.physics.gravity = physics.gravity;
+        self.fixed_attractor = physics.attractor;
+        self.spawner.reseed(self.seed);
+        self.physics_time = std::time::Duration::ZERO;
+    }
+
+    /// Adds a sprite for the image at `path` a world unit tall centered on `position`, the image is only loaded the first time
+    fn add_sprite(
+        &mut self,
+        render_state: &egui_wgpu::RenderState,
+        path: &str,
+        position: Vector2<f32>,
+    ) -> Result<(), image::ImageError> {
+        let (texture_id, size) = match self.sprite_textures.get(path) {
+            Some(&texture) => texture,
+            None => {
+                let (width, height) = image::image_dimensions(path)?;
+                let id = load_texture(render_state, path, AlphaMode::Straight)?;
+                self.sprite_textures
+                    .insert(path.to_string(), (id, [width, height]));
+                (id, [width, height])
+            }
+        };
+        self.push_sprite(texture_id, size, position);
+        Ok(())
+    }
+
+    /// Like [`App::add_sprite`] for an image file that was read already, which is loaded every time as it has no path to remember it by
+    fn add_sprite_from_memory(
+        &mut self,
+        render_state: &egui_wgpu::RenderState,
+        bytes: &[u8],
+        position: Vector2<f32>,
+    ) -> Result<(), image::ImageError> {
+        let image = image::load_from_memory(bytes)?.into_rgba8();
+        let texture_id = upload_texture(render_state, &image, AlphaMode::Straight)?;
+        self.push_sprite(texture_id, [image.width(), image.height()], position);
+        Ok(())
+    }
 
-            self.physics_time -= time_step;
+    fn push_sprite(&mut self, texture_id: u32, [width, height]: [u32; 2], position: Vector2<f32>) {
+        self.sprites.push(GpuSprite {
+            position,
+            size: cgmath::vec2(width as f32 / height as f32, 1.0),
+            texture_id,
+            tint: cgmath::vec4(1.0, 1.0, 1.0, 1.0),
+        });
+    }
+
+    /// Loads dropped `.json` files as the scene and adds dropped `.png` files as sprites starting at `position`
+    ///
+    /// Each scene replaces the last so the last one dropped stays, undo goes back through the others,
+    /// and each sprite is moved over from the one before so they dont all land on top of each other
+    fn load_dropped_files(
+        &mut self,
+        render_state: &egui_wgpu::RenderState,
+        files: &[egui::DroppedFile],
+        mut position: Vector2<f32>,
+    ) {
+        for file in files {
+            let name = file_name(file.path.as_deref(), &file.name);
+            let extension = name
+                .rsplit_once('.')
+                .map(|(_, extension)| extension.to_ascii_lowercase());
+            match extension.as_deref() {
+                Some("json") => {
+                    let scene = match (&file.path, &file.bytes) {
+                        (Some(path), _) => Scene::load_from_path(path),
+                        // anything that isnt utf8 wouldnt parse as json either
+                        (None, Some(bytes)) => Scene::from_json(&String::from_utf8_lossy(bytes)),
+                        (None, None) => continue,
+                    };
+                    match scene {
+                        Ok(scene) => {
+                            self.replace_scene(scene);
+                            self.scene_file_error = None;
+                        }
+                        Err(error) => self.scene_file_error = Some(format!("{name}: {error}")),
+                    }
+                }
+                Some("png") => {
+                    let result = match (&file.path, &file.bytes) {
+                        (Some(path), _) => {
+                            self.add_sprite(render_state, &path.to_string_lossy(), position)
+                        }
+                        (None, Some(bytes)) => {
+                            self.add_sprite_from_memory(render_state, bytes, position)
+                        }
+                        (None, None) => continue,
+                    };
+                    match result {
+                        Ok(()) => {
+                            position.x += self.sprites.last().unwrap().size.x;
+                            self.sprite_error = None;
+                        }
+                        Err(error) => self.sprite_error = Some(format!("{name}: {error}")),
+                    }
+                }
+                _ => {
+                    self.scene_file_error = Some(format!(
+                        "{name}: only .json scenes and .png images can be dropped"
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Shows the context menu if one is open, closing it once something is picked or the pointer clicks elsewhere
+    fn show_context_menu(&mut self, ctx: &egui::Context) {
+        let Some((position, target)) = self.context_menu else {
+            return;
+        };
+        let mut close = false;
+        let area = egui::Area::new("Context Menu")
+            .order(egui::Order::Foreground)
+            .fixed_pos(position)
+            .show(ctx, |ui| {
+                egui::Frame::menu(ui.style()).show(ui, |ui| match target {
+                    ContextMenuTarget::Circle(id) => {
+                        if self.scene.circle(id).is_none() {
+                            // removed by something else while the menu was open
+                            close = true;
+                            return;
+                        }
+                        if ui.button("Delete").clicked() {
+                            self.history.push(self.scene.clone());
+                            self.scene.remove_circles(&HashSet::from([id]));
+                            self.selected.remove(&id);
+                            if self.grabbed.is_some_and(|(grabbed, _)| grabbed == id) {
+                                self.grabbed = None;
+                            }
+                            close = true;
+                        }
+                        if ui.button("Duplicate").clicked() {
+                            self.duplicate(&[id]);
+                            close = true;
+                        }
+                        if ui.button("Follow").clicked() {
+                            self.follow = Some(id);
+                            self.camera_animation = None;
+                            close = true;
+                        }
+                        if ui.button("Edit...").clicked() {
+                            self.selected = HashSet::from([id]);
+                            self.info_window_open = true;
+                            self.inspector_open_requested = true;
+                            close = true;
+                        }
+                    }
+                    ContextMenuTarget::Empty(world_position) => {
+                        if ui.button("Add Circle Here").clicked() && self.prepare_to_add(1) {
+                            self.scene.add_circle(
+                                GpuCircle::new(self.snap(world_position))
+                                    .with_color(self.brush_color)
+                                    .with_radius(DEFAULT_BRUSH_RADIUS),
+                            );
+                            close = true;
+                        }
+                        if ui
+                            .add_enabled(self.follow.is_some(), egui::Button::new("Stop Following"))
+                            .clicked()
+                        {
+                            self.follow = None;
+                            close = true;
+                        }
+                    }
+                });
+            });
+        let escape = ctx.input(|input| input.key_pressed(egui::Key::Escape));
+        if close || escape || area.response.clicked_elsewhere() {
+            self.context_menu = None;
+        }
+    }
+
+    /// Holding F pulls the circles towards the cursor, the fixed attractor is used whenever it isnt held
+    fn update_attractor(&mut self, ctx: &egui::Context) {
+        // the cursor position is from last frame as the central panel hasnt been shown yet
+        let (attract, repel) = ctx.input(|input| {
+            let held = input.key_down(egui::Key::F) && !input.modifiers.command;
+            (held, input.modifiers.shift)
+        });
+        self.physics.attractor = self
+            .cursor_world_position
+            .filter(|_| attract && !ctx.wants_keyboard_input())
+            .map(|position| Attractor {
+                position,
+                strength: if repel {
+                    -self.attractor_strength
+                } else {
+                    self.attractor_strength
+                },
+            })
+            .or(self.fixed_attractor);
+    }
+
+    /// Panning, turning and nudging with the keyboard, and the shortcuts that arent on any button
+    fn keyboard_input(&mut self, ctx: &egui::Context, step: std::time::Duration) {
+        let arrow_held = ctx.input(|input| {
+            [
+                egui::Key::ArrowUp,
+                egui::Key::ArrowDown,
+                egui::Key::ArrowLeft,
+                egui::Key::ArrowRight,
+            ]
+            .into_iter()
+            .any(|key| input.key_down(key))
+        });
+        if !arrow_held {
+            if let Some(scene) = self.scene_before_nudge.take() {
+                self.history.push(scene);
+            }
+        }
+
+        if !ctx.wants_keyboard_input() {
+            let arrows_nudge = !self.selected.is_empty();
+            let (direction, turn, fast) = ctx.input(|input| {
+                let mut direction = Vector2::<f32>::zero();
+                let mut turn: f32 = 0.0;
+                // so shortcuts like ctrl+d dont also pan
+                if input.modifiers.command {
+                    return (direction, turn, false);
+                }
+                let arrow_down = |key| !arrows_nudge && input.key_down(key);
+                if input.key_down(egui::Key::W) || arrow_down(egui::Key::ArrowUp) {
+                    direction.y += 1.0;
+                }
+                if input.key_down(egui::Key::S) || arrow_down(egui::Key::ArrowDown) {
+                    direction.y -= 1.0;
+                }
+                if input.key_down(egui::Key::A) || arrow_down(egui::Key::ArrowLeft) {
+                    direction.x -= 1.0;
+                }
+                if input.key_down(egui::Key::D) || arrow_down(egui::Key::ArrowRight) {
+                    direction.x += 1.0;
+                }
+                if input.key_down(egui::Key::Q) {
+                    turn += 1.0;
+                }
+                if input.key_down(egui::Key::E) {
+                    turn -= 1.0;
+                }
+                (direction, turn, input.modifiers.shift)
+            });
+
+            if direction != Vector2::zero() {
+                self.camera_animation = None;
+                self.follow = None;
+                let mut speed = self.keyboard_pan_speed;
+                if fast {
+                    speed *= self.keyboard_pan_fast_multiplier;
+                }
+                // the view is 2 / zoom world units tall, so this keeps the on screen speed the same at any zoom
+                let direction = camera::rotate(direction.normalize(), self.camera.rotation);
+                self.camera.position +=
+                    direction * speed * 2.0 * step.as_secs_f32() / self.camera.zoom;
+            }
+
+            if turn != 0.0 {
+                self.camera_animation = None;
+                self.camera.rotation = (self.camera.rotation
+                    + turn * self.keyboard_turn_speed * step.as_secs_f32())
+                .rem_euclid(TAU);
+            }
+
+            if arrows_nudge {
+                // along the world axes rather than the screen so nudged circles stay on the grid
+                let (nudge, fast) = ctx.input(|input| {
+                    let mut nudge = Vector2::<f32>::zero();
+                    if input.modifiers.command {
+                        return (nudge, false);
+                    }
+                    // held keys repeat, so each repeat is another nudge
+                    for event in &input.events {
+                        let egui::Event::Key {
+                            key, pressed: true, ..
+                        } = event
+                        else {
+                            continue;
+                        };
+                        match key {
+                            egui::Key::ArrowUp => nudge.y += 1.0,
+                            egui::Key::ArrowDown => nudge.y -= 1.0,
+                            egui::Key::ArrowLeft => nudge.x -= 1.0,
+                            egui::Key::ArrowRight => nudge.x += 1.0,
+                            _ => {}
+                        }
+                    }
+                    (nudge, input.modifiers.shift)
+                });
+                if nudge != Vector2::zero() {
+                    if self.scene_before_nudge.is_none() {
+                        self.scene_before_nudge = Some(self.scene.clone());
+                    }
+                    let offset = nudge * self.nudge_step(fast);
+                    for &id in &self.selected {
+                        if let Some(circle) = self.scene.circle(id) {
+                            self.scene.move_circle(id, circle.position + offset);
+                        }
+                    }
+                }
+            }
+
+            if ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Space)) {
+                self.paused = !self.paused;
+            }
+
+            if ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Delete))
+                && !self.selected.is_empty()
+            {
+                self.history.push(self.scene.clone());
+                self.scene.remove_circles(&self.selected);
+                self.selected.clear();
+                self.grabbed = None;
+            }
+
+            if ctx.input_mut(|input| input.consume_key(egui::Modifiers::COMMAND, egui::Key::D))
+                && !self.selected.is_empty()
+            {
+                let ids: Vec<_> = self.selected.iter().copied().collect();
+                self.duplicate(&ids);
+            }
+
+            // egui turns ctrl+c and ctrl+v into these events rather than key presses
+            for event in ctx.input(|input| input.events.clone()) {
+                match event {
+                    egui::Event::Copy => {
+                        let copied = if self.selected.is_empty() {
+                            self.scene.to_json()
+                        } else {
+                            let ids: Vec<_> = self.selected.iter().copied().collect();
+                            self.scene.extract_circles(&ids).to_json()
+                        };
+                        if let Ok(json) = copied {
+                            ctx.output_mut(|output| output.copied_text = json);
+                        }
+                    }
+                    egui::Event::Paste(text) => {
+                        if self.apply_view(&text).is_ok() {
+                            self.view_error = None;
+                            continue;
+                        }
+                        // anything that isnt a scene or a view is ignored, it was probably just some other text
+                        let Ok(pasted) = Scene::from_json(&text) else {
+                            continue;
+                        };
+                        let Some((min, max)) = pasted.bounding_box() else {
+                            continue;
+                        };
+                        let target =
+                            self.snap(self.cursor_world_position.unwrap_or(self.camera.position));
+                        if !self.prepare_to_add(pasted.shape_count()) {
+                            continue;
+                        }
+                        self.selected = self
+                            .scene
+                            .append(&pasted, target - (min + max) * 0.5)
+                            .into_iter()
+                            .collect();
+                    }
+                    _ => {}
+                }
+            }
+
+            let (undo, redo) = ctx.input_mut(|input| {
+                let undo = input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+                let redo = input.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)
+                    || input.consume_key(
+                        egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                        egui::Key::Z,
+                    );
+                (undo, redo)
+            });
+            if undo && self.history.undo(&mut self.scene)
+                || redo && self.history.redo(&mut self.scene)
+            {
+                self.scene_restored();
+            }
+        }
+    }
+
+    /// Plays the camera animation or follows the followed circle
+    fn move_camera(&mut self, step: std::time::Duration) {
+        if let Some(animation) = &mut self.camera_animation {
+            if animation.update(&mut self.camera, step.as_secs_f32()) {
+                self.camera_animation = None;
+            }
+        }
+
+        if let Some(id) = self.follow {
+            match self.scene.interpolated_position(id, self.physics_alpha()) {
+                Some(position) => {
+                    let t = if self.follow_smoothing > 0.0 {
+                        1.0 - (-step.as_secs_f32() / self.follow_smoothing).exp()
+                    } else {
+                        1.0
+                    };
+                    self.camera.position = self.camera.position.lerp(position, t);
+                }
+                None => self.follow = None,
+            }
         }
+    }
 
+    fn menu_bar(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("Menu").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 self.info_window_open |= ui.button("Info").clicked();
+                ui.toggle_value(&mut self.shape_list_open, "Shapes");
+                self.scenes_menu(ui);
+                ui.separator();
+                ui.selectable_value(&mut self.tool, Tool::Select, "Select");
+                ui.selectable_value(&mut self.tool, Tool::DrawCircle, "Draw Circle");
+                ui.selectable_value(&mut self.tool, Tool::Measure, "Measure");
+                if self.tool != Tool::Measure {
+                    self.measure_start = None;
+                }
+                if ui
+                    .add_enabled(
+                        !self.measurements.is_empty() || self.measure_start.is_some(),
+                        egui::Button::new("Clear Measurements"),
+                    )
+                    .clicked()
+                {
+                    self.measurements.clear();
+                    self.measure_start = None;
+                }
+                ui.separator();
+                self.export_requested |= ui.button("Export PNG").clicked();
+                // 8192 is the smallest max texture size wgpu guarantees
+                ui.add(egui::DragValue::new(&mut self.export_width).clamp_range(1..=8192));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut self.export_height).clamp_range(1..=8192));
+                self.svg_export_requested |= ui.button("Export SVG").clicked();
+                ui.separator();
+                self.recording_ui(ui);
+                if let Some(error) = &self.export_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            });
+        });
+    }
+
+    fn scenes_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Scenes", |ui| {
+            for preset in Preset::ALL {
+                if ui.button(preset.name()).clicked() {
+                    self.load_preset(preset);
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Seed: ");
+                ui.add(egui::DragValue::new(&mut self.seed));
+            });
+        });
+    }
+
+    /// The frame rate the written frames play back at the speed they were simulated at
+    fn record_playback_frame_rate(&self) -> f32 {
+        self.record_frame_rate as f32 / self.record_every_nth.max(1) as f32
+    }
+
+    fn recording_ui(&mut self, ui: &mut egui::Ui) {
+        let mut recording = self.recorder.is_some();
+        if ui.toggle_value(&mut recording, "Record").changed() {
+            if recording {
+                match Recorder::start(RECORDING_DIRECTORY, self.record_every_nth) {
+                    Ok(recorder) => {
+                        self.recorder = Some(recorder);
+                        self.record_status = None;
+                        // so the first recorded step doesnt depend on the leftover real time
+                        self.physics_time = std::time::Duration::ZERO;
+                    }
+                    Err(error) => self.record_status = Some(error.to_string()),
+                }
+            } else if let Some(recorder) = self.recorder.take() {
+                self.record_status = Some(format!(
+                    "Wrote {} frames to {}, play them back at {:.2} fps",
+                    recorder.frames_written(),
+                    recorder.directory().display(),
+                    self.record_playback_frame_rate(),
+                ));
+            }
+        }
+        ui.add(
+            egui::DragValue::new(&mut self.record_frame_rate)
+                .clamp_range(1..=240)
+                .suffix(" fps"),
+        );
+        ui.label("Every");
+        ui.add(egui::DragValue::new(&mut self.record_every_nth).clamp_range(1..=100))
+            .on_hover_text(format!(
+                "Only every nth frame is written, so the frames play back at the simulated speed at {:.2} fps",
+                self.record_playback_frame_rate(),
+            ));
+        match &self.recorder {
+            Some(recorder) => {
+                ui.label(format!("{} frames", recorder.frames_written()));
+            }
+            None => {
+                if let Some(status) = &self.record_status {
+                    ui.label(status);
+                }
+            }
+        }
+    }
+
+    fn shape_list(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("Shapes").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter: ");
+                ui.text_edit_singleline(&mut self.shape_list_filter);
             });
+            ui.checkbox(&mut self.center_on_click, "Center On Click");
+            ui.separator();
+
+            let filter = self.shape_list_filter.to_lowercase();
+            let entries: Vec<ShapeListEntry> = shape_list_entries(&self.scene)
+                .into_iter()
+                .filter(|entry| {
+                    entry.name.to_lowercase().contains(&filter)
+                        || entry.kind.to_lowercase().contains(&filter)
+                })
+                .collect();
+            ui.label(format!("{} Shapes", entries.len()));
+            // only the visible rows are laid out, so big scenes dont slow the list down
+            let row_height = ui.spacing().interact_size.y;
+            egui::ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                    for entry in &entries[rows] {
+                        let selected = entry.id.is_some_and(|id| self.selected.contains(&id));
+                        let text = format!(
+                            "{}  {}  ({:.2}, {:.2})",
+                            entry.name, entry.kind, entry.position.x, entry.position.y,
+                        );
+                        if !ui.selectable_label(selected, text).clicked() {
+                            continue;
+                        }
+                        if let Some(id) = entry.id {
+                            if !ctx.input(|input| input.modifiers.shift) {
+                                self.selected.clear();
+                            }
+                            self.selected.insert(id);
+                        }
+                        if self.center_on_click {
+                            self.follow = None;
+                            self.camera_animation = Some(CameraAnimation::new(
+                                &self.camera,
+                                entry.position,
+                                self.camera.zoom,
+                                self.camera.rotation,
+                                CAMERA_ANIMATION_DURATION,
+                            ));
+                        }
+                    }
+                });
         });
+    }
 
+    fn info_window(&mut self, ctx: &egui::Context, frame: &eframe::Frame, dt: std::time::Duration) {
+        // a copy so the window contents can borrow all of self
+        let mut info_window_open = self.info_window_open;
         egui::Window::new("Info")
-            .open(&mut self.info_window_open)
+            .open(&mut info_window_open)
             .resizable(false)
             .show(ctx, |ui| {
-                ui.collapsing("Background Color", |ui| {
-                    egui::color_picker::color_picker_color32(
-                        ui,
-                        &mut self.background_color,
-                        egui::color_picker::Alpha::Opaque,
-                    );
-                });
-                ui.label(format!("FPS: {:.3}", 1.0 / dt.as_secs_f64()));
-                ui.label(format!("Frame Time: {:.3}ms", 1000.0 * dt.as_secs_f64()));
-                ui.horizontal(|ui| {
-                    ui.label("Physics Ticks: ");
-                    ui.add(egui::Slider::new(&mut self.physics_ticks, 1..=1000));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Time Scale: ");
-                    ui.add(egui::Slider::new(&mut self.time_scale, -20.0..=20.0));
-                });
+                self.colors_ui(ui);
+                self.scene_file_ui(ui);
+                self.camera_ui(ui, ctx);
+                self.settings_ui(ui, frame);
+                self.performance_ui(ui, frame, dt);
+                self.physics_ui(ui);
+                self.scene_generation_ui(ui);
+                self.simulation_ui(ui);
+                self.inspector_ui(ui, ctx);
+                self.spawner_ui(ui);
+                self.attractor_ui(ui);
+                self.display_ui(ui, frame);
+                self.controls_ui(ui);
             });
+        self.info_window_open = info_window_open;
+    }
 
-        egui::CentralPanel::default()
-            .frame(egui::Frame::none().fill(self.background_color))
-            .show(ctx, |ui| {
-                let (rect, response) =
-                    ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
-                let aspect = rect.width() / rect.height();
+    fn colors_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Background Color", |ui| {
+            egui::color_picker::color_picker_color32(
+                ui,
+                &mut self.background_color,
+                egui::color_picker::Alpha::Opaque,
+            );
+        });
+        ui.collapsing("Brush Color", |ui| {
+            color::color_edit(ui, &mut self.brush_color);
+        });
+    }
 
-                if response.dragged_by(egui::PointerButton::Secondary) {
-                    let delta = response.drag_delta();
-                    self.camera.position.x -=
-                        delta.x / self.camera.zoom / rect.width() * 2.0 * aspect;
-                    self.camera.position.y += delta.y / self.camera.zoom / rect.height() * 2.0;
+    /// Saving and loading the scene, importing circles and undo
+    fn scene_file_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                self.scene_file_error = self
+                    .scene
+                    .save_to_path(SCENE_PATH)
+                    .err()
+                    .map(|error| error.to_string());
+            }
+            if ui.button("Load").clicked() {
+                match Scene::load_from_path(SCENE_PATH) {
+                    Ok(scene) => {
+                        self.replace_scene(scene);
+                        self.scene_file_error = None;
+                    }
+                    Err(error) => self.scene_file_error = Some(error.to_string()),
                 }
+            }
+            let export_selected = ui
+                .button("Export Selected")
+                .on_hover_text(format!(
+                    "Saves only the selected circles and the springs between them to {SELECTION_PATH}, \
+                     where they are now, it can be loaded by dropping it onto the window"
+                ));
+            if export_selected.clicked() {
+                let selected: Vec<ShapeId> = self.selected.iter().copied().collect();
+                // nothing selected is still saved, as a valid empty scene
+                self.scene_file_error = self
+                    .scene
+                    .extract_circles(&selected)
+                    .save_to_path(SELECTION_PATH)
+                    .err()
+                    .map(|error| error.to_string());
+            }
+        });
+        if let Some(error) = &self.scene_file_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        ui.horizontal(|ui| {
+            ui.label("CSV (x,y,radius,r,g,b): ");
+            ui.text_edit_singleline(&mut self.csv_path);
+            if ui.button("Import CSV").clicked() {
+                let mut scene = self.scene.clone();
+                let result = std::fs::File::open(&self.csv_path)
+                    .map_err(SceneError::from)
+                    .and_then(|file| scene.import_csv(std::io::BufReader::new(file)));
+                self.csv_import_status = Some(match result {
+                    Ok(skipped) => {
+                        let imported = scene.circles.len() - self.scene.circles.len();
+                        if scene.make_room(0, self.max_shapes, self.edit_limit_policy) {
+                            self.history.push(std::mem::replace(&mut self.scene, scene));
+                            format!("Imported {imported} circles, skipped {skipped} malformed rows")
+                        } else {
+                            format!(
+                                "Importing {imported} circles would go over the limit of {} shapes",
+                                self.max_shapes
+                            )
+                        }
+                    }
+                    Err(error) => error.to_string(),
+                });
+            }
+        });
+        if let Some(status) = &self.csv_import_status {
+            ui.label(status);
+        }
+        ui.horizontal(|ui| {
+            let undo = ui.add_enabled(self.history.can_undo(), egui::Button::new("Undo"));
+            if undo.clicked() && self.history.undo(&mut self.scene) {
+                self.scene_restored();
+            }
+            let redo = ui.add_enabled(self.history.can_redo(), egui::Button::new("Redo"));
+            if redo.clicked() && self.history.redo(&mut self.scene) {
+                self.scene_restored();
+            }
+        });
+    }
 
-                if response.dragged_by(egui::PointerButton::Primary) {
-                    'drag: {
-                        let Some(interact_pointer_pos) = response.interact_pointer_pos() else {
-                            break 'drag;
-                        };
-                        let mouse_position =
-                            ((interact_pointer_pos - rect.left_top()) / rect.size() * 2.0
-                                - egui::vec2(1.0, 1.0))
-                                * egui::vec2(1.0, -1.0);
-                        let world_position = Vector2 {
-                            x: mouse_position.x * aspect / self.camera.zoom
-                                + self.camera.position.x,
-                            y: mouse_position.y / self.camera.zoom + self.camera.position.y,
-                        };
+    fn camera_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        if ui.button("Reset Camera").clicked() {
+            self.follow = None;
+            self.camera_animation = Some(CameraAnimation::new(
+                &self.camera,
+                Vector2::zero(),
+                DEFAULT_CAMERA_ZOOM,
+                0.0,
+                CAMERA_ANIMATION_DURATION,
+            ));
+        }
+        self.fit_all_requested |= ui.button("Fit All").clicked();
+        ui.horizontal(|ui| {
+            if ui.button("Copy View").clicked() {
+                self.view_text = self.camera.view_string();
+                ctx.output_mut(|output| output.copied_text = self.view_text.clone());
+            }
+            // egui cant read the clipboard on request, so the view is pasted into the field first,
+            // pasting one straight into the view with ctrl+v works too
+            ui.text_edit_singleline(&mut self.view_text);
+            if ui.button("Paste View").clicked() {
+                let text = self.view_text.clone();
+                self.view_error = self.apply_view(&text).err().map(|error| error.to_string());
+            }
+        });
+        if let Some(error) = &self.view_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        ui.horizontal(|ui| {
+            ui.label("Follow Smoothing (Seconds): ");
+            ui.add(egui::Slider::new(&mut self.follow_smoothing, 0.0..=1.0));
+        });
+    }
 
-                        // use the world position to find out where you clicked
-                        _ = world_position;
+    /// The rendering settings and the ones saved to the settings file
+    fn settings_ui(&mut self, ui: &mut egui::Ui, frame: &eframe::Frame) {
+        ui.horizontal(|ui| {
+            ui.label("MSAA Samples: ");
+            egui::ComboBox::from_id_source("MSAA Samples")
+                .selected_text(self.sample_count.to_string())
+                .show_ui(ui, |ui| {
+                    for count in supported_sample_counts(frame.wgpu_render_state().unwrap()) {
+                        ui.selectable_value(&mut self.sample_count, count, count.to_string());
                     }
-                } else {
-                    // not being dragged anymore
-                }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Circle Quality: ").on_hover_text(
+                "Performance draws the circles with hard edges, which is cheaper when there are lots of tiny circles, the GPU time shows the difference",
+            );
+            egui::ComboBox::from_id_source("Circle Quality")
+                .selected_text(self.circle_quality.name())
+                .show_ui(ui, |ui| {
+                    for quality in CircleQuality::ALL {
+                        ui.selectable_value(
+                            &mut self.circle_quality,
+                            quality,
+                            quality.name(),
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Frame Rate Limit: ");
+            egui::ComboBox::from_id_source("Frame Rate Limit")
+                .selected_text(frame_rate_limit_name(self.frame_rate_limit))
+                .show_ui(ui, |ui| {
+                    for limit in FRAME_RATE_LIMITS {
+                        ui.selectable_value(
+                            &mut self.frame_rate_limit,
+                            limit,
+                            frame_rate_limit_name(limit),
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Present Mode: ");
+            let previous_present_mode = self.settings.present_mode;
+            egui::ComboBox::from_id_source("Present Mode")
+                .selected_text(self.settings.present_mode.name())
+                .show_ui(ui, |ui| {
+                    for present_mode in PresentMode::ALL {
+                        ui.selectable_value(
+                            &mut self.settings.present_mode,
+                            present_mode,
+                            present_mode.name(),
+                        );
+                    }
+                });
+            if self.settings.present_mode != previous_present_mode {
+                self.settings_error = self
+                    .settings
+                    .save_to_path(SETTINGS_PATH)
+                    .err()
+                    .map(|error| error.to_string());
+            }
+        });
+        if self.settings.present_mode != self.launch_present_mode {
+            ui.label("The present mode applies on the next launch");
+        }
+        ui.horizontal(|ui| {
+            ui.label("Power Preference: ");
+            let previous_power_preference = self.settings.power_preference;
+            egui::ComboBox::from_id_source("Power Preference")
+                .selected_text(self.settings.power_preference.name())
+                .show_ui(ui, |ui| {
+                    for power_preference in PowerPreference::ALL {
+                        ui.selectable_value(
+                            &mut self.settings.power_preference,
+                            power_preference,
+                            power_preference.name(),
+                        );
+                    }
+                });
+            if self.settings.power_preference != previous_power_preference {
+                self.settings_error = self
+                    .settings
+                    .save_to_path(SETTINGS_PATH)
+                    .err()
+                    .map(|error| error.to_string());
+            }
+        });
+        if self.settings.power_preference != self.launch_power_preference {
+            ui.label("The power preference applies on the next launch");
+        }
+        if ui
+            .button("Save Settings")
+            .on_hover_text(
+                "Starts the next launch with the current tick rate, time scale, frame rate limit, background, camera and shape limit",
+            )
+            .clicked()
+        {
+            let [r, g, b, _] = self.background_color.to_array();
+            self.settings.physics_ticks = self.physics_ticks;
+            self.settings.time_scale = self.time_scale;
+            self.settings.background_color = [r, g, b];
+            self.settings.camera_position = self.camera.position;
+            self.settings.camera_zoom = self.camera.zoom;
+            self.settings.frame_rate_limit = self.frame_rate_limit;
+            self.settings.circle_quality = self.circle_quality;
+            self.settings.max_shapes = self.max_shapes;
+            self.settings.edit_limit_policy = self.edit_limit_policy;
+            self.settings.spawner_limit_policy = self.spawner.limit_policy;
+            self.settings_error = self
+                .settings
+                .save_to_path(SETTINGS_PATH)
+                .err()
+                .map(|error| error.to_string());
+        }
+        if let Some(error) = &self.settings_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        ui.collapsing("Instance Buffers", |ui| {
+            for (name, capacity) in instance_buffer_capacities(frame.wgpu_render_state().unwrap()) {
+                ui.label(format!("{name} Capacity: {capacity}"));
+            }
+        });
+    }
 
-                if response.hovered() {
-                    'hover: {
-                        let Some(hover_pos) = response.hover_pos() else {
-                            break 'hover;
-                        };
-                        let mouse_position =
-                            ((response.hover_pos().unwrap() - rect.left_top()) / rect.size() * 2.0
-                                - egui::vec2(1.0, 1.0))
-                                * egui::vec2(1.0, -1.0);
-                        let world_position = Vector2 {
-                            x: mouse_position.x * aspect / self.camera.zoom
-                                + self.camera.position.x,
-                            y: mouse_position.y / self.camera.zoom + self.camera.position.y,
-                        };
+    fn performance_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        frame: &eframe::Frame,
+        dt: std::time::Duration,
+    ) {
+        ui.label(format!("FPS: {:.3}", 1.0 / dt.as_secs_f64()));
+        ui.label(format!("Frame Time: {:.3}ms", 1000.0 * dt.as_secs_f64()));
+        frame_time_graph(ui, &self.frame_times);
+        match presentation_latency(frame.wgpu_render_state().unwrap()) {
+            Some(latency) => ui.label(format!(
+                "Latency (Approximate): {:.3}ms",
+                1000.0 * latency.as_secs_f64()
+            )),
+            None => ui.label("Latency (Approximate): measuring"),
+        }
+        .on_hover_text(
+            "From the start of a frame until it is about to be presented, timed on the cpu as the \
+             presentation itself cant be, compare it between present modes",
+        );
+        match gpu_time(frame.wgpu_render_state().unwrap()) {
+            GpuTime::Unsupported => ui.label("GPU Time: unsupported"),
+            GpuTime::Measuring => ui.label("GPU Time: measuring"),
+            GpuTime::Average(time) => {
+                ui.label(format!("GPU Time: {:.3}ms", 1000.0 * time.as_secs_f64()))
+            }
+        };
+    }
 
-                        // use the world position to know where you are hovering
-                    }
+    /// How the physics is stepped, the settings that change what it does are in [`App::simulation_ui`]
+    fn physics_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Physics Ticks: ");
+            ui.add(egui::Slider::new(&mut self.physics_ticks, 1..=1000));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Substeps: ");
+            ui.add(egui::Slider::new(&mut self.physics.substeps, 1..=16))
+                .on_hover_text(
+                    "Splits every physics step up so fast circles collide more reliably, at the cost of that many times the physics time",
+                );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Max Physics Steps Per Frame: ");
+            ui.add(egui::Slider::new(&mut self.max_physics_steps, 1..=100));
+        });
+        ui.label(format!(
+            "Physics Steps Last Frame: {}",
+            self.physics_steps_last_frame
+        ));
+        ui.label(format!(
+            "Physics Time Last Frame: {:.3}ms",
+            1000.0 * self.physics_time_last_frame.as_secs_f64()
+        ));
+        #[cfg(feature = "rapier")]
+        ui.horizontal(|ui| {
+            ui.label("Backend: ");
+            ui.selectable_value(&mut self.physics.backend, Backend::BuiltIn, "Built In");
+            ui.selectable_value(&mut self.physics.backend, Backend::Rapier, "Rapier");
+            if self.physics.backend == Backend::Rapier {
+                ui.label(format!("{} bodies", self.rapier_world.body_count()));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Broad Phase: ");
+            ui.selectable_value(
+                &mut self.physics.broad_phase,
+                BroadPhase::BruteForce,
+                "Brute Force",
+            );
+            ui.selectable_value(
+                &mut self.physics.broad_phase,
+                BroadPhase::SpatialHash,
+                "Spatial Hash",
+            );
+        });
+        ui.checkbox(&mut self.show_spatial_hash, "Show Spatial Hash Cells")
+            .on_hover_text(
+                "The cells the spatial hash broad phase would put the circles in right now, \
+                 the more circles in a cell the redder it is",
+            );
+    }
 
-                    ctx.input(|input| match input.scroll_delta.y.total_cmp(&0.0) {
-                        std::cmp::Ordering::Less => self.camera.zoom *= 0.9,
-                        std::cmp::Ordering::Greater => self.camera.zoom /= 0.9,
-                        _ => {}
+    /// Generating random circles into the scene and the limit on how many shapes it can have
+    fn scene_generation_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Seed: ");
+            ui.add(egui::DragValue::new(&mut self.seed));
+            ui.add(
+                egui::DragValue::new(&mut self.random_circle_count)
+                    .clamp_range(0..=10000)
+                    .suffix(" circles"),
+            );
+            let regenerate = ui.button("Regenerate").clicked();
+            // the whole scene is replaced so there is nothing to recycle, too many is always refused
+            if regenerate && self.random_circle_count as usize > self.max_shapes {
+                self.shapes_refused = Some(self.random_circle_count as usize);
+            } else if regenerate {
+                self.shapes_refused = None;
+                let Boundary { min, max, .. } = self.physics.boundary;
+                let mut scene = Scene::new();
+                scene.add_random_circles(
+                    &mut StdRng::seed_from_u64(self.seed),
+                    min,
+                    max,
+                    self.random_circle_count,
+                );
+                self.replace_scene(scene);
+                self.spawner.reseed(self.seed);
+                self.physics_time = std::time::Duration::ZERO;
+            }
+        });
+        if ui.button("Add 5000 Circles").clicked() && self.prepare_to_add(5000) {
+            let Boundary { min, max, .. } = self.physics.boundary;
+            self.scene.add_circle_grid(min, max, 5000);
+        }
+        ui.collapsing("Shape Limit", |ui| {
+            let shape_count = self.scene.shape_count();
+            let text = format!("Shapes: {shape_count} / {}", self.max_shapes);
+            if shape_count >= self.max_shapes {
+                ui.colored_label(egui::Color32::RED, format!("{text} (Full)"));
+            } else {
+                ui.label(text);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Max Shapes: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.max_shapes)
+                        .clamp_range(1..=1_000_000)
+                        .speed(10.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("When Editing: ");
+                egui::ComboBox::from_id_source("Edit Limit Policy")
+                    .selected_text(self.edit_limit_policy.name())
+                    .show_ui(ui, |ui| {
+                        for policy in LimitPolicy::ALL {
+                            ui.selectable_value(&mut self.edit_limit_policy, policy, policy.name());
+                        }
                     });
-                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("When Spawning: ");
+                egui::ComboBox::from_id_source("Spawner Limit Policy")
+                    .selected_text(self.spawner.limit_policy.name())
+                    .show_ui(ui, |ui| {
+                        for policy in LimitPolicy::ALL {
+                            ui.selectable_value(
+                                &mut self.spawner.limit_policy,
+                                policy,
+                                policy.name(),
+                            );
+                        }
+                    });
+            });
+            if let Some(count) = self.shapes_refused {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("Adding {count} shapes would go over the limit"),
+                );
+            }
+        });
+    }
 
-                ui.painter().add(Callback::new_paint_callback(
-                    rect,
-                    RenderCallback {
-                        camera: GpuCamera {
-                            position: self.camera.position,
-                            aspect,
-                            zoom: self.camera.zoom,
-                        },
-                        circles: vec![GpuCircle {
-                            position: cgmath::vec2(0.0, 0.0),
-                            color: cgmath::vec3(1.0, 0.0, 0.0),
-                            radius: 1.0,
-                        }],
-                        rectangles: vec![],
-                    },
+    fn simulation_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Time Scale: ");
+            ui.add(egui::Slider::new(&mut self.time_scale, -20.0..=20.0));
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button(if self.paused { "Resume" } else { "Pause" })
+                .on_hover_text("Space")
+                .clicked()
+            {
+                self.paused = !self.paused;
+            }
+            if ui
+                .add_enabled(self.paused, egui::Button::new("Step"))
+                .clicked()
+            {
+                self.step_requested = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Gravity: ");
+            ui.add(egui::DragValue::new(&mut self.physics.gravity.x).speed(0.1));
+            ui.add(egui::DragValue::new(&mut self.physics.gravity.y).speed(0.1));
+        });
+        ui.collapsing("Boundary", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Min: ");
+                ui.add(egui::DragValue::new(&mut self.physics.boundary.min.x).speed(0.1));
+                ui.add(egui::DragValue::new(&mut self.physics.boundary.min.y).speed(0.1));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Max: ");
+                ui.add(egui::DragValue::new(&mut self.physics.boundary.max.x).speed(0.1));
+                ui.add(egui::DragValue::new(&mut self.physics.boundary.max.y).speed(0.1));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Restitution: ");
+                ui.add(egui::Slider::new(
+                    &mut self.physics.boundary.restitution,
+                    0.0..=1.0,
                 ));
             });
-
-        ctx.request_repaint();
+            ui.checkbox(&mut self.draw_boundary, "Draw Boundary");
+        });
+        ui.checkbox(&mut self.throw_on_release, "Throw Circles On Release");
+        ui.collapsing("Trails", |ui| {
+            ui.checkbox(&mut self.trails.enabled, "Enabled");
+            ui.horizontal(|ui| {
+                ui.label("Length: ");
+                ui.add(egui::Slider::new(&mut self.trails.length, 1..=200));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Fade: ");
+                ui.add(egui::Slider::new(&mut self.trails.fade, 0.1..=10.0).logarithmic(true));
+            });
+        });
     }
-}
 
-fn main() {
-    eframe::run_native(
-        "eframe circles and rectangles",
-        NativeOptions {
-            vsync: false,
-            renderer: Renderer::Wgpu,
-            wgpu_options: WgpuConfiguration {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                present_mode: wgpu::PresentMode::AutoNoVsync,
-                ..Default::default()
-            },
+    /// Edits the selected circles, several at once all get the same value
+    fn inspector_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let inspector_open = std::mem::take(&mut self.inspector_open_requested);
+        // typing changes the name every key, it is recorded as one edit once the field loses focus
+        let mut editing_name = false;
+        egui::CollapsingHeader::new("Inspector")
+            .open(inspector_open.then_some(true))
+            .show(ui, |ui| {
+                let mut ids: Vec<ShapeId> = self
+                    .selected
+                    .iter()
+                    .copied()
+                    .filter(|&id| self.scene.circle(id).is_some())
+                    .collect();
+                // sorted so the values shown for a mixed selection dont jump around between frames
+                ids.sort_by_key(|&id| self.scene.circle_index(id));
+                if ids.is_empty() {
+                    ui.label("Select a circle to edit it");
+                    return;
+                }
+                let circles: Vec<&GpuCircle> =
+                    ids.iter().filter_map(|&id| self.scene.circle(id)).collect();
+                let first = circles[0].clone();
+                ui.label(format!("{} Selected", ids.len()));
+                ui.weak("The arrow keys nudge the selection, shift nudges further");
+                ui.weak("Delete removes the selected circles, Ctrl+D duplicates them");
+
+                let mixed_name = ids
+                    .iter()
+                    .any(|&id| self.scene.circle_name(id) != self.scene.circle_name(ids[0]));
+                let mut name = self
+                    .scene
+                    .circle_name(ids[0])
+                    .unwrap_or_default()
+                    .to_string();
+                let mut name_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Name: ");
+                    let response = ui.text_edit_singleline(&mut name);
+                    name_changed = response.changed();
+                    editing_name = response.has_focus();
+                    if mixed_name {
+                        ui.weak("(mixed)");
+                    }
+                });
+
+                // several circles are moved together, so the center of them is what is shown and edited
+                let center = circles
+                    .iter()
+                    .map(|circle| circle.position)
+                    .sum::<Vector2<f32>>()
+                    / circles.len() as f32;
+                let mut position = center;
+                let mut position_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label(if ids.len() > 1 {
+                        "Center: "
+                    } else {
+                        "Position: "
+                    });
+                    position_changed |= ui
+                        .add(egui::DragValue::new(&mut position.x).speed(0.01))
+                        .changed();
+                    position_changed |= ui
+                        .add(egui::DragValue::new(&mut position.y).speed(0.01))
+                        .changed();
+                });
+
+                let mixed_radius = circles.iter().any(|circle| circle.radius != first.radius);
+                let mut radius = first.radius;
+                let mut radius_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Radius: ");
+                    radius_changed = ui
+                        .add(
+                            egui::DragValue::new(&mut radius)
+                                .speed(0.01)
+                                .clamp_range(0.01..=f32::MAX),
+                        )
+                        .changed();
+                    if mixed_radius {
+                        ui.weak("(mixed)");
+                    }
+                });
+
+                let mixed_color = circles.iter().any(|circle| circle.color != first.color);
+                let mut color = first.color;
+                let mut color_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Color: ");
+                    color_changed = color::color_edit(ui, &mut color).changed();
+                    if mixed_color {
+                        ui.weak("(mixed)");
+                    }
+                });
+
+                let bodies: Vec<&PhysicsCircle> = ids
+                    .iter()
+                    .map(|&id| &self.scene.bodies[self.scene.circle_index(id).unwrap()])
+                    .collect();
+                let mixed_mass = bodies.iter().any(|body| body.mass != bodies[0].mass);
+                let mut mass = bodies[0].mass;
+                let mut mass_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Mass: ");
+                    mass_changed = ui
+                        .add(
+                            egui::DragValue::new(&mut mass)
+                                .speed(0.01)
+                                .clamp_range(0.001..=f32::MAX),
+                        )
+                        .changed();
+                    if mixed_mass {
+                        ui.weak("(mixed)");
+                    }
+                });
+
+                let mixed_restitution = bodies
+                    .iter()
+                    .any(|body| body.restitution != bodies[0].restitution);
+                let mut restitution = bodies[0].restitution;
+                let mut restitution_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Restitution: ");
+                    restitution_changed = ui
+                        .add(egui::Slider::new(&mut restitution, 0.0..=1.0))
+                        .changed();
+                    if mixed_restitution {
+                        ui.weak("(mixed)");
+                    }
+                });
+
+                let mixed_static = bodies
+                    .iter()
+                    .any(|body| body.is_static != bodies[0].is_static);
+                let mut is_static = bodies[0].is_static;
+                let mut static_changed = false;
+                ui.horizontal(|ui| {
+                    static_changed = ui.checkbox(&mut is_static, "Static").changed();
+                    if mixed_static {
+                        ui.weak("(mixed)");
+                    }
+                });
+
+                if name_changed
+                    || position_changed
+                    || radius_changed
+                    || color_changed
+                    || mass_changed
+                    || restitution_changed
+                    || static_changed
+                {
+                    // a drag changes the values every frame, it is recorded as one edit once it ends
+                    if self.scene_before_inspector_edit.is_none() {
+                        self.scene_before_inspector_edit = Some(self.scene.clone());
+                    }
+                    for &id in &ids {
+                        if name_changed {
+                            self.scene.set_circle_name(id, Some(name.clone()));
+                        }
+                        if position_changed {
+                            let circle_position = self.scene.circle(id).unwrap().position;
+                            self.scene
+                                .move_circle(id, circle_position + position - center);
+                        }
+                        let index = self.scene.circle_index(id).unwrap();
+                        if radius_changed {
+                            self.scene.circles[index].radius = radius;
+                        }
+                        if color_changed {
+                            self.scene.circles[index].color = color;
+                        }
+                        if mass_changed {
+                            self.scene.bodies[index].mass = mass;
+                        }
+                        if restitution_changed {
+                            self.scene.bodies[index].restitution = restitution;
+                        }
+                        if static_changed {
+                            self.scene.bodies[index].is_static = is_static;
+                        }
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Spring Stiffness: ");
+                    ui.add(egui::Slider::new(&mut self.spring_stiffness, 0.0..=1.0));
+                });
+                ui.horizontal(|ui| {
+                    // chained in draw order, each one as long as its circles are apart now
+                    let connect =
+                        ui.add_enabled(ids.len() > 1, egui::Button::new("Connect With Springs"));
+                    if connect.clicked() {
+                        self.history.push(self.scene.clone());
+                        for pair in ids.windows(2) {
+                            let a = self.scene.circle(pair[0]).unwrap().position;
+                            let b = self.scene.circle(pair[1]).unwrap().position;
+                            self.scene.add_spring(CircleSpring {
+                                a: pair[0],
+                                b: pair[1],
+                                rest_length: (b - a).magnitude(),
+                                stiffness: self.spring_stiffness,
+                            });
+                        }
+                    }
+                    if ui.button("Remove Springs").clicked() {
+                        self.history.push(self.scene.clone());
+                        self.scene.remove_springs(&ids.iter().copied().collect());
+                    }
+                });
+            });
+        if !ctx.is_using_pointer() && !editing_name {
+            if let Some(scene) = self.scene_before_inspector_edit.take() {
+                self.history.push(scene);
+            }
+        }
+    }
+
+    fn spawner_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Spawner", |ui| {
+            let spawner = &mut self.spawner;
+            ui.checkbox(&mut spawner.enabled, "Enabled");
+            ui.horizontal(|ui| {
+                ui.label("Position: ");
+                ui.add(egui::DragValue::new(&mut spawner.position.x).speed(0.1));
+                ui.add(egui::DragValue::new(&mut spawner.position.y).speed(0.1));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Velocity: ");
+                ui.add(egui::DragValue::new(&mut spawner.initial_velocity.x).speed(0.1));
+                ui.add(egui::DragValue::new(&mut spawner.initial_velocity.y).speed(0.1));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Spread: ");
+                ui.add(egui::Slider::new(&mut spawner.spread, 0.0..=TAU));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Rate (Per Second): ");
+                ui.add(egui::Slider::new(&mut spawner.rate, 0.0..=1000.0).logarithmic(true));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Lifetime (Seconds): ");
+                ui.add(egui::Slider::new(&mut spawner.lifetime, 0.1..=60.0).logarithmic(true));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Radius: ");
+                ui.add(egui::Slider::new(&mut spawner.radius, 0.01..=1.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Color: ");
+                color::color_edit(ui, &mut spawner.color);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Emissive (Glows With Bloom): ");
+                ui.add(egui::Slider::new(&mut spawner.emissive, 0.0..=10.0));
+            });
+            ui.label(format!("Circles: {}", self.scene.circles.len()));
+        });
+    }
+
+    fn attractor_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Attractor Strength (Hold F, Shift To Repel): ");
+            ui.add(egui::Slider::new(&mut self.attractor_strength, 0.0..=200.0));
+        });
+        if let Some(attractor) = self.fixed_attractor {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Fixed Attractor: ({:.3}, {:.3}) Strength {:.1}",
+                    attractor.position.x, attractor.position.y, attractor.strength
+                ));
+                if ui.button("Remove").clicked() {
+                    self.fixed_attractor = None;
+                }
+            });
+        }
+    }
+
+    /// What is drawn over and instead of the circles
+    fn display_ui(&mut self, ui: &mut egui::Ui, frame: &eframe::Frame) {
+        match self.cursor_world_position {
+            Some(position) => ui.label(format!("Cursor: ({:.3}, {:.3})", position.x, position.y)),
+            None => ui.label("Cursor: \u{2014}"),
+        };
+        ui.checkbox(&mut self.show_crosshair, "Show Crosshair");
+        ui.collapsing("Velocity Arrows", |ui| {
+            ui.checkbox(&mut self.show_velocities, "Show Velocities");
+            ui.horizontal(|ui| {
+                ui.label("Scale (Seconds): ");
+                ui.add(egui::Slider::new(&mut self.velocity_scale, 0.01..=10.0).logarithmic(true));
+            });
+            color::color_edit(ui, &mut self.velocity_color);
+        });
+        ui.collapsing("Color By Speed", |ui| {
+            ui.checkbox(&mut self.color_by_speed, "Color Circles By Speed")
+                .on_hover_text(
+                    "Only changes how the circles are drawn, from blue for slow to red for fast, \
+                     their own colors are kept",
+                );
+            ui.add_enabled_ui(self.color_by_speed, |ui| {
+                let (min, max) = &mut self.speed_color_range;
+                ui.horizontal(|ui| {
+                    ui.label("From: ");
+                    ui.add(
+                        egui::DragValue::new(min)
+                            .speed(0.1)
+                            .clamp_range(0.0..=*max)
+                            .suffix(" u/s"),
+                    );
+                    ui.label("to");
+                    ui.add(
+                        egui::DragValue::new(max)
+                            .speed(0.1)
+                            .clamp_range(*min..=f32::INFINITY)
+                            .suffix(" u/s"),
+                    );
+                });
+            });
+        });
+        ui.collapsing("Labels", |ui| {
+            ui.checkbox(&mut self.show_labels, "Show Names");
+            ui.checkbox(&mut self.label_ids, "Label Unnamed Circles With Their Id");
+            ui.horizontal(|ui| {
+                ui.label("Font Size: ");
+                ui.add(egui::Slider::new(&mut self.label_font_size, 4.0..=64.0));
+            });
+            ui.checkbox(&mut self.label_scale_with_zoom, "Scale With Zoom");
+            ui.add_enabled_ui(self.label_scale_with_zoom, |ui| {
+                let (min, max) = &mut self.label_font_size_range;
+                ui.horizontal(|ui| {
+                    ui.label("Between: ");
+                    ui.add(egui::DragValue::new(min).clamp_range(1.0..=*max));
+                    ui.label("and");
+                    ui.add(egui::DragValue::new(max).clamp_range(*min..=256.0));
+                });
+            });
+        });
+        ui.collapsing("Grid", |ui| {
+            ui.checkbox(&mut self.show_grid, "Show Grid");
+            ui.horizontal(|ui| {
+                ui.label("Spacing: ");
+                ui.add(
+                    egui::DragValue::new(&mut self.grid_spacing)
+                        .speed(0.1)
+                        .clamp_range(0.001..=f32::INFINITY),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Major Line Every: ");
+                ui.add(egui::DragValue::new(&mut self.grid_major_every).clamp_range(1..=100));
+            });
+            ui.add_enabled(
+                self.show_grid,
+                egui::Checkbox::new(&mut self.snap_to_grid, "Snap To Grid"),
+            );
+        });
+        ui.collapsing("Sprites", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Image: ");
+                ui.text_edit_singleline(&mut self.sprite_path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Add Sprite").clicked() {
+                    let path = self.sprite_path.clone();
+                    // in the middle of the view
+                    self.sprite_error = self
+                        .add_sprite(
+                            frame.wgpu_render_state().unwrap(),
+                            &path,
+                            self.camera.position,
+                        )
+                        .err()
+                        .map(|error| error.to_string());
+                }
+                if ui.button("Clear Sprites").clicked() {
+                    self.sprites.clear();
+                }
+            });
+            if let Some(error) = &self.sprite_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+        ui.collapsing("Minimap", |ui| {
+            ui.checkbox(&mut self.minimap.enabled, "Show Minimap");
+            ui.horizontal(|ui| {
+                ui.label("Size: ");
+                ui.add(egui::Slider::new(&mut self.minimap.size, 50.0..=500.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Corner: ");
+                egui::ComboBox::from_id_source("Minimap Corner")
+                    .selected_text(self.minimap.corner.name())
+                    .show_ui(ui, |ui| {
+                        for corner in Corner::ALL {
+                            ui.selectable_value(&mut self.minimap.corner, corner, corner.name());
+                        }
+                    });
+            });
+        });
+        // the performance quality has hard edges
+        ui.add_enabled_ui(self.circle_quality == CircleQuality::Quality, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Circle Edge Softness (Pixels): ");
+                ui.add(egui::Slider::new(&mut self.edge_softness, 0.1..=8.0).logarithmic(true));
+            });
+        });
+        ui.collapsing("Bloom", |ui| {
+            ui.checkbox(&mut self.bloom_enabled, "Enabled");
+            ui.horizontal(|ui| {
+                ui.label("Blur Radius: ");
+                ui.add(egui::Slider::new(&mut self.bloom_radius, 0.5..=16.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Intensity: ");
+                ui.add(egui::Slider::new(&mut self.bloom_intensity, 0.0..=5.0));
+            });
+        });
+        ui.collapsing("Density", |ui| {
+            ui.checkbox(&mut self.density_enabled, "Show Density Instead Of Circles");
+            ui.horizontal(|ui| {
+                ui.label("Color Ramp: ");
+                egui::ComboBox::from_id_source("Density Color Ramp")
+                    .selected_text(self.density_ramp.name())
+                    .show_ui(ui, |ui| {
+                        for ramp in DensityRamp::ALL {
+                            ui.selectable_value(&mut self.density_ramp, ramp, ramp.name());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Circles At The End Of The Ramp: ");
+                ui.add(egui::Slider::new(&mut self.max_density, 2.0..=100.0).logarithmic(true));
+            });
+        });
+    }
+
+    /// How the mouse and keyboard move the camera
+    fn controls_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Scroll Zoom Factor: ");
+            ui.add(egui::Slider::new(&mut self.scroll_zoom_factor, 0.5..=0.99));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Scroll Zoom Sensitivity: ");
+            ui.add(
+                egui::Slider::new(&mut self.scroll_zoom_sensitivity, 0.001..=0.1).logarithmic(true),
+            );
+        });
+        ui.checkbox(&mut self.scroll_pans, "Scroll To Pan (Trackpad)");
+        ui.horizontal(|ui| {
+            ui.label("Keyboard Pan Speed (WASD/Arrows): ")
+                .on_hover_text(
+                    "The arrow keys nudge the selection instead while something is selected",
+                );
+            ui.add(egui::Slider::new(&mut self.keyboard_pan_speed, 0.1..=5.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Shift Pan Multiplier: ");
+            ui.add(egui::Slider::new(
+                &mut self.keyboard_pan_fast_multiplier,
+                1.0..=10.0,
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Camera Rotation: ");
+            if ui
+                .add(egui::Slider::new(&mut self.camera.rotation, 0.0..=TAU))
+                .changed()
+            {
+                self.camera_animation = None;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Keyboard Turn Speed (Q/E): ");
+            ui.add(egui::Slider::new(&mut self.keyboard_turn_speed, 0.1..=10.0));
+        });
+    }
+
+    /// The scene itself, drawn to fill the central panel
+    fn scene_view(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, frame: &eframe::Frame) {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        // a minimized window can have no height at all
+        let aspect = rect.width() / rect.height();
+        let aspect = if aspect.is_finite() && aspect > 0.0 {
+            aspect
+        } else {
+            1.0
+        };
+
+        if std::mem::take(&mut self.fit_all_requested) {
+            if let Some((min, max)) = self.scene.bounding_box() {
+                self.follow = None;
+                let (position, zoom) = self.camera.framing(min, max, aspect, FIT_ALL_MARGIN);
+                self.camera_animation = Some(CameraAnimation::new(
+                    &self.camera,
+                    position,
+                    zoom,
+                    self.camera.rotation,
+                    CAMERA_ANIMATION_DURATION,
+                ));
+            }
+        }
+
+        let hovered_circle = self.pointer_input(ctx, rect, &response);
+
+        // after everything that moves the camera this frame and before anything is drawn with it
+        self.camera.make_finite();
+        let render_callback = RenderCallback {
+            shapes: self.shapes(rect, aspect, hovered_circle),
+            sample_count: self.sample_count,
+            target_size: [
+                (rect.width() * ctx.pixels_per_point()).round() as u32,
+                (rect.height() * ctx.pixels_per_point()).round() as u32,
+            ],
+            clear_color: {
+                let background_color = color::srgb_to_linear(self.background_color);
+                wgpu::Color {
+                    r: background_color.x as f64,
+                    g: background_color.y as f64,
+                    b: background_color.z as f64,
+                    a: 1.0,
+                }
+            },
+            // set at the very start of update
+            frame_start: self.last_frame_time,
+        };
+
+        self.export_view(frame, &render_callback, aspect);
+
+        ui.painter()
+            .add(Callback::new_paint_callback(rect, render_callback));
+
+        self.paint_overlays(&ui.painter_at(rect), rect);
+        self.dropped_files_input(ctx, frame, rect);
+    }
+
+    /// Panning, selecting, drawing and dragging with the pointer, and zooming with the scroll wheel, returns the
+    /// circle under the pointer
+    fn pointer_input(
+        &mut self,
+        ctx: &egui::Context,
+        rect: egui::Rect,
+        response: &egui::Response,
+    ) -> Option<ShapeId> {
+        if response.drag_started_by(egui::PointerButton::Secondary) {
+            self.pan_anchor = response
+                .interact_pointer_pos()
+                .map(|pointer_pos| self.camera.screen_to_world(pointer_pos, rect));
+        }
+        // only once it has moved too far to be a click, so right clicking for the context menu cant pan a little,
+        // the anchor is then put back under the pointer so none of the movement before that is lost
+        if response.dragged_by(egui::PointerButton::Secondary)
+            && ctx.input(|input| input.pointer.is_decidedly_dragging())
+        {
+            if let (Some(anchor), Some(pointer_pos)) =
+                (self.pan_anchor, response.interact_pointer_pos())
+            {
+                self.camera_animation = None;
+                self.follow = None;
+                self.camera.move_point_to(anchor, pointer_pos, rect);
+            }
+        }
+        if response.drag_released_by(egui::PointerButton::Secondary) {
+            self.pan_anchor = None;
+        }
+
+        if let Some(position) = self.minimap.show(ctx, rect, &self.scene, &self.camera) {
+            self.camera_animation = None;
+            self.follow = None;
+            self.camera.position = position;
+        }
+
+        self.show_context_menu(ctx);
+        // egui opens its own context menus as soon as the button is pressed, this waits for a click
+        // so that right dragging to pan doesnt open one
+        if response.secondary_clicked() {
+            if let Some(pointer_pos) = response.interact_pointer_pos() {
+                let world_position = self.camera.screen_to_world(pointer_pos, rect);
+                let target = match self.scene.circle_at(world_position) {
+                    Some(id) => ContextMenuTarget::Circle(id),
+                    None => ContextMenuTarget::Empty(world_position),
+                };
+                self.context_menu = Some((pointer_pos, target));
+            }
+        }
+
+        if self.tool == Tool::Select && response.clicked_by(egui::PointerButton::Primary) {
+            'click: {
+                let Some(interact_pointer_pos) = response.interact_pointer_pos() else {
+                    break 'click;
+                };
+                let world_position = self.camera.screen_to_world(interact_pointer_pos, rect);
+
+                let add_to_selection = ctx.input(|input| input.modifiers.shift);
+                if self.scene.circle_at(world_position).is_none() && !add_to_selection {
+                    self.selected.clear();
+                }
+            }
+        }
+
+        if self.tool == Tool::Measure && response.clicked_by(egui::PointerButton::Primary) {
+            if let Some(interact_pointer_pos) = response.interact_pointer_pos() {
+                let world_position =
+                    self.snap(self.camera.screen_to_world(interact_pointer_pos, rect));
+                match self.measure_start.take() {
+                    Some(start) => self.measurements.push((start, world_position)),
+                    None => self.measure_start = Some(world_position),
+                }
+            }
+        }
+
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            'grab: {
+                let Some(interact_pointer_pos) = response.interact_pointer_pos() else {
+                    break 'grab;
+                };
+                // measuring only uses clicks
+                if self.tool == Tool::Measure {
+                    break 'grab;
+                }
+                let world_position = self.camera.screen_to_world(interact_pointer_pos, rect);
+
+                if self.tool == Tool::DrawCircle {
+                    self.drawing_circle = Some(
+                        GpuCircle::new(self.snap(world_position))
+                            .with_color(self.brush_color)
+                            .with_radius(0.0),
+                    );
+                    break 'grab;
+                }
+
+                let add_to_selection = ctx.input(|input| input.modifiers.shift);
+                if let Some(id) = self.scene.circle_at(world_position) {
+                    if !add_to_selection {
+                        self.selected.clear();
+                    }
+                    self.selected.insert(id);
+                    let position = self.scene.circle(id).unwrap().position;
+                    self.grabbed = Some((id, position - world_position));
+                    self.scene_before_drag = Some(self.scene.clone());
+                } else {
+                    self.selection_box = Some((world_position, world_position));
+                }
+            }
+        }
+
+        if response.dragged_by(egui::PointerButton::Primary) {
+            'drag: {
+                let Some(interact_pointer_pos) = response.interact_pointer_pos() else {
+                    break 'drag;
+                };
+                let world_position = self.camera.screen_to_world(interact_pointer_pos, rect);
+
+                if let Some((id, offset)) = self.grabbed {
+                    // the position is set rather than moved by the pointer delta so
+                    // the physics steps this frame cant make it drift away from the pointer
+                    self.scene
+                        .move_circle(id, self.snap(world_position + offset));
+                } else if let Some((_, end)) = &mut self.selection_box {
+                    *end = world_position;
+                } else if let Some(circle) = &mut self.drawing_circle {
+                    circle.radius = (world_position - circle.position).magnitude();
+                }
+            }
+        }
+
+        if response.drag_released_by(egui::PointerButton::Primary) {
+            if let Some((start, end)) = self.selection_box.take() {
+                let min = cgmath::vec2(start.x.min(end.x), start.y.min(end.y));
+                let max = cgmath::vec2(start.x.max(end.x), start.y.max(end.y));
+                if !ctx.input(|input| input.modifiers.shift) {
+                    self.selected.clear();
+                }
+                self.selected.extend(self.scene.circles_in_rect(min, max));
+            }
+            if let Some(mut circle) = self.drawing_circle.take() {
+                if response.clicked_by(egui::PointerButton::Primary) {
+                    circle.radius = DEFAULT_BRUSH_RADIUS;
+                }
+                if self.prepare_to_add(1) {
+                    self.scene.add_circle(circle);
+                }
+            }
+            // a click without moving also starts and ends a drag, but it isnt an edit
+            if let Some(scene) = self.scene_before_drag.take() {
+                if !response.clicked_by(egui::PointerButton::Primary) {
+                    self.history.push(scene);
+                }
+            }
+            if let Some((id, _)) = self.grabbed.take() {
+                if self.throw_on_release {
+                    let velocity = ctx.input(|input| input.pointer.velocity());
+                    self.scene
+                        .set_circle_velocity(id, self.camera.screen_to_world_delta(velocity, rect));
+                }
+            }
+        }
+
+        let mut hovered_circle = None;
+        self.cursor_world_position = None;
+        if response.hovered() {
+            'hover: {
+                let Some(hover_pos) = response.hover_pos() else {
+                    break 'hover;
+                };
+                let world_position = self.camera.screen_to_world(hover_pos, rect);
+                self.cursor_world_position = Some(world_position);
+
+                if self.tool == Tool::Select {
+                    hovered_circle = self.scene.circle_at(world_position);
+                }
+
+                let (zoom_delta, scroll_delta, multi_touch) = ctx
+                    .input(|input| (input.zoom_delta(), input.scroll_delta, input.multi_touch()));
+
+                // pinching on a trackpad or touch screen, and ctrl+scroll, only show up
+                // in the zoom delta and not the scroll delta so nothing is applied twice
+                if zoom_delta != 1.0 {
+                    self.camera_animation = None;
+                    let focus = multi_touch.map_or(hover_pos, |touch| touch.start_pos);
+                    self.camera.zoom_at(zoom_delta, focus, rect);
+                }
+
+                if let Some(touch) = multi_touch {
+                    self.camera_animation = None;
+                    self.follow = None;
+                    self.camera.pan_by(touch.translation_delta, rect);
+                }
+
+                if self.scroll_pans {
+                    if scroll_delta != egui::Vec2::ZERO {
+                        self.camera_animation = None;
+                        self.follow = None;
+                        self.camera.pan_by(scroll_delta, rect);
+                    }
+                } else {
+                    // checked so that not scrolling cant move the camera at all through rounding in zoom_at
+                    if scroll_delta.y != 0.0 {
+                        self.camera_animation = None;
+                        // scrolling down gives a negative delta which zooms out
+                        let factor = self
+                            .scroll_zoom_factor
+                            .powf(-scroll_delta.y * self.scroll_zoom_sensitivity);
+                        self.camera.zoom_at(factor, hover_pos, rect);
+                    }
+                }
+            }
+        }
+        hovered_circle
+    }
+
+    /// The finished measurements, and the one being made following the cursor
+    fn measurements(&self) -> Vec<(Vector2<f32>, Vector2<f32>)> {
+        self.measurements
+            .iter()
+            .copied()
+            .chain(
+                self.measure_start.zip(
+                    self.cursor_world_position
+                        .map(|position| self.snap(position)),
+                ),
+            )
+            .collect()
+    }
+
+    /// The names or ids drawn above the circles, each with where it is anchored in the world
+    fn labels(&self) -> Vec<(Vector2<f32>, String)> {
+        if !self.show_labels && !self.label_ids {
+            return Vec::new();
+        }
+        // above each circle towards the top of the screen, so they dont cover the circle at any rotation
+        let up = camera::rotate(cgmath::vec2(0.0, 1.0), self.camera.rotation);
+        self.scene
+            .interpolated_circles(self.physics_alpha())
+            .iter()
+            .zip(self.scene.query_circles())
+            .zip(&self.scene.names)
+            .filter_map(|((circle, (id, _, _)), name)| {
+                let text = match name {
+                    Some(name) if self.show_labels => name.clone(),
+                    None if self.label_ids => format!("#{}", id.0),
+                    _ => return None,
+                };
+                Some((circle.position + up * circle.radius, text))
+            })
+            .collect()
+    }
+
+    /// Everything drawn in the view this frame, the scene along with the selection, measurements and other overlays
+    fn shapes(
+        &mut self,
+        rect: egui::Rect,
+        aspect: f32,
+        hovered_circle: Option<ShapeId>,
+    ) -> Arc<Shapes> {
+        // egui has dropped last frames callback by now, so its vectors can be filled again without allocating
+        let mut shapes = self
+            .frame_shapes
+            .take()
+            .and_then(|shapes| Arc::try_unwrap(shapes).ok())
+            .unwrap_or_else(|| Shapes::new(self.gpu_camera(aspect)));
+        self.fill_shapes(&mut shapes, rect, aspect, hovered_circle);
+        let shapes = Arc::new(shapes);
+        self.frame_shapes = Some(Arc::clone(&shapes));
+        shapes
+    }
+
+    /// Replaces everything in `shapes` with what [`App::shapes`] draws, reusing the vectors it already has
+    fn fill_shapes(
+        &self,
+        shapes: &mut Shapes,
+        rect: egui::Rect,
+        aspect: f32,
+        hovered_circle: Option<ShapeId>,
+    ) {
+        let circles = &mut shapes.circles;
+        circles.clear();
+        // in front of the circles so each trail is drawn behind its circle
+        if self.trails.enabled {
+            self.trails.extend_circles(&self.scene, circles);
+        }
+        let first_circle = circles.len();
+        self.scene
+            .extend_interpolated_circles(self.physics_alpha(), circles);
+        // taken before anything else is added so each circle is still at the index of its body
+        let velocities: Vec<(Vector2<f32>, Vector2<f32>)> = if self.show_velocities {
+            circles[first_circle..]
+                .iter()
+                .zip(&self.scene.bodies)
+                .map(|(circle, body)| (circle.position, body.velocity * self.velocity_scale))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if self.color_by_speed {
+            let (min, max) = self.speed_color_range;
+            for (circle, body) in circles[first_circle..].iter_mut().zip(&self.scene.bodies) {
+                let speed = body.velocity.magnitude();
+                circle.color = color::speed_ramp((speed - min) / (max - min).max(f32::EPSILON));
+            }
+        }
+        // static circles are outlined in the same gray as the boundary walls, unless they already
+        // have an outline, being selected replaces it
+        for (circle, body) in circles[first_circle..].iter_mut().zip(&self.scene.bodies) {
+            if body.is_static && circle.outline_width == 0.0 {
+                circle.outline_color = color::srgb_to_linear(egui::Color32::from_gray(128));
+                circle.outline_width = circle.radius * 0.15;
+            }
+        }
+        for &id in &self.selected {
+            if let Some(index) = self.scene.circle_index(id) {
+                let circle = &mut circles[first_circle + index];
+                circle.outline_color = cgmath::vec3(1.0, 1.0, 1.0) - circle.color;
+                circle.outline_width = circle.radius * 0.15;
+            }
+        }
+        // only the fill is brightened so a selected circle keeps the same outline
+        if let Some(index) = hovered_circle.and_then(|id| self.scene.circle_index(id)) {
+            let circle = &mut circles[first_circle + index];
+            let white = cgmath::vec3(1.0, 1.0, 1.0);
+            circle.color = circle.color.lerp(white, HOVER_BRIGHTEN);
+            circle.color2 = circle.color2.lerp(white, HOVER_BRIGHTEN);
+        }
+        circles.extend(self.drawing_circle.clone());
+
+        let rectangles = &mut shapes.rectangles;
+        rectangles.clear();
+        rectangles.extend_from_slice(&self.scene.rectangles);
+        if self.draw_boundary {
+            let thickness = 0.1;
+            let Boundary { min, max, .. } = self.physics.boundary;
+            let center = (min + max) * 0.5;
+            let size = max - min;
+            let color = color::srgb_to_linear(egui::Color32::from_gray(128));
+            // the walls sit just outside of the boundary so they dont cover any circles
+            for (position, size) in [
+                (
+                    cgmath::vec2(center.x, min.y - thickness * 0.5),
+                    cgmath::vec2(size.x + thickness * 2.0, thickness),
+                ),
+                (
+                    cgmath::vec2(center.x, max.y + thickness * 0.5),
+                    cgmath::vec2(size.x + thickness * 2.0, thickness),
+                ),
+                (
+                    cgmath::vec2(min.x - thickness * 0.5, center.y),
+                    cgmath::vec2(thickness, size.y),
+                ),
+                (
+                    cgmath::vec2(max.x + thickness * 0.5, center.y),
+                    cgmath::vec2(thickness, size.y),
+                ),
+            ] {
+                rectangles.push(
+                    GpuRectangle::new(position)
+                        .with_color(color)
+                        .with_size(size),
+                );
+            }
+        }
+
+        let lines = &mut shapes.lines;
+        lines.clear();
+        lines.extend_from_slice(&self.scene.lines);
+        for spring in self.scene.springs() {
+            let alpha = self.physics_alpha();
+            lines.push(GpuLine {
+                start: self.scene.interpolated_position(spring.a, alpha).unwrap(),
+                end: self.scene.interpolated_position(spring.b, alpha).unwrap(),
+                width: 0.03,
+                color: color::srgb_to_linear(egui::Color32::from_gray(200)),
+                dash_length: 0.0,
+                gap_length: 0.0,
+            });
+        }
+        if let Some((start, end)) = self.selection_box {
+            let color = color::srgb_to_linear(egui::Color32::from_rgb(77, 153, 255));
+            rectangles.push(
+                GpuRectangle::new((start + end) * 0.5)
+                    .with_color(color)
+                    .with_size(cgmath::vec2(
+                        (end.x - start.x).abs(),
+                        (end.y - start.y).abs(),
+                    ))
+                    .with_alpha(0.25),
+            );
+            // one pixel wide whatever the zoom, dashed so it stands out from the spatial hash cells
+            let width = 2.0 / (self.camera.zoom * rect.height());
+            let corners = [
+                start,
+                cgmath::vec2(end.x, start.y),
+                end,
+                cgmath::vec2(start.x, end.y),
+            ];
+            for i in 0..corners.len() {
+                lines.push(GpuLine {
+                    start: corners[i],
+                    end: corners[(i + 1) % corners.len()],
+                    width,
+                    color,
+                    dash_length: 6.0,
+                    gap_length: 4.0,
+                });
+            }
+        }
+        let measurements = self.measurements();
+        // one pixel in world units at the current zoom
+        let pixel = 2.0 / (self.camera.zoom * rect.height());
+        let color = color::srgb_to_linear(MEASUREMENT_COLOR);
+        for &(start, end) in &measurements {
+            lines.push(GpuLine {
+                start,
+                end,
+                width: pixel * 2.0,
+                color,
+                dash_length: 0.0,
+                gap_length: 0.0,
+            });
+            for position in [start, end] {
+                circles.push(
+                    GpuCircle::new(position)
+                        .with_color(color)
+                        .with_radius(pixel * 4.0),
+                );
+            }
+        }
+        if self.show_spatial_hash {
+            // made from the bodies the same way a step makes it, only for drawing
+            let spatial_hash = SpatialHash::new(&self.scene.bodies);
+            let most = spatial_hash
+                .occupied_cells()
+                .map(|(_, count)| count)
+                .max()
+                .unwrap_or(1);
+            let (few, many) = (cgmath::vec3(0.0, 0.3, 1.0), cgmath::vec3(1.0, 0.1, 0.0));
+            for (cell, count) in spatial_hash.occupied_cells() {
+                let (min, max) = spatial_hash.cell_bounds(cell);
+                // a single circle is as blue as it gets, the fullest cell is fully red
+                let t = if most > 1 {
+                    (count - 1) as f32 / (most - 1) as f32
+                } else {
+                    0.0
+                };
+                let color = few.lerp(many, t);
+                rectangles.push(
+                    GpuRectangle::new((min + max) * 0.5)
+                        .with_size(max - min)
+                        .with_color(color)
+                        .with_alpha(0.3),
+                );
+                let corners = [
+                    min,
+                    cgmath::vec2(max.x, min.y),
+                    max,
+                    cgmath::vec2(min.x, max.y),
+                ];
+                for i in 0..corners.len() {
+                    lines.push(GpuLine {
+                        start: corners[i],
+                        end: corners[(i + 1) % corners.len()],
+                        width: pixel,
+                        color,
+                        dash_length: 0.0,
+                        gap_length: 0.0,
+                    });
+                }
+            }
+        }
+
+        // after the circles in the draw order so they are always on top, the head is a triangle
+        // that keeps the same size on screen and shrinks for arrows too short to fit it
+        let polygons = &mut shapes.polygons;
+        polygons.clear();
+        polygons.extend_from_slice(&self.scene.polygons);
+        for (start, velocity) in velocities {
+            let length = velocity.magnitude();
+            if length <= pixel {
+                continue;
+            }
+            let direction = velocity / length;
+            let side = cgmath::vec2(-direction.y, direction.x);
+            let head_length = (pixel * 10.0).min(length * 0.5);
+            let tip = start + velocity;
+            let base = tip - direction * head_length;
+            lines.push(GpuLine {
+                start,
+                end: base,
+                width: pixel * 2.0,
+                color: self.velocity_color,
+                dash_length: 0.0,
+                gap_length: 0.0,
+            });
+            polygons.push(
+                GpuPolygon::new(&[
+                    tip,
+                    base + side * head_length * 0.5,
+                    base - side * head_length * 0.5,
+                ])
+                .with_color(self.velocity_color),
+            );
+        }
+        if let Some(position) = self.cursor_world_position.filter(|_| self.show_crosshair) {
+            let arm = pixel * CROSSHAIR_SIZE;
+            for direction in [cgmath::vec2(1.0, 0.0), cgmath::vec2(0.0, 1.0)] {
+                lines.push(GpuLine {
+                    start: position - direction * arm,
+                    end: position + direction * arm,
+                    width: pixel,
+                    color: cgmath::vec3(1.0, 1.0, 1.0),
+                    dash_length: 0.0,
+                    gap_length: 0.0,
+                });
+            }
+        }
+
+        shapes.camera = self.gpu_camera(aspect);
+        shapes.grid = self.show_grid.then(|| GpuGrid {
+            spacing: self.grid_spacing,
+            major_every: self.grid_major_every,
+            minor_color: color::srgb_to_linear(egui::Color32::from_gray(51)),
+            major_color: color::srgb_to_linear(egui::Color32::from_gray(89)),
+        });
+        shapes.sprites.clear();
+        shapes.sprites.extend_from_slice(&self.sprites);
+        shapes.ellipses.clear();
+        shapes.ellipses.extend_from_slice(&self.scene.ellipses);
+        shapes.capsules.clear();
+        shapes.capsules.extend_from_slice(&self.scene.capsules);
+        shapes.rings.clear();
+        shapes.rings.extend_from_slice(&self.scene.rings);
+        shapes.bloom = self.bloom_enabled.then_some(GpuBloom {
+            radius: self.bloom_radius,
+            intensity: self.bloom_intensity,
+        });
+        shapes.density = self.density_enabled.then_some(GpuDensity {
+            max_density: self.max_density,
+            ramp: self.density_ramp as u32,
+        });
+        shapes.edge_softness = self.edge_softness;
+        shapes.circle_quality = self.circle_quality;
+    }
+
+    /// The camera the shapes are drawn with, `aspect` being the width over the height of the view
+    fn gpu_camera(&self, aspect: f32) -> GpuCamera {
+        GpuCamera {
+            position: self.camera.position,
+            aspect,
+            zoom: self.camera.zoom,
+            rotation: self.camera.rotation,
+        }
+    }
+    fn export_view(
+        &mut self,
+        frame: &eframe::Frame,
+        render_callback: &RenderCallback,
+        aspect: f32,
+    ) {
+        if std::mem::take(&mut self.svg_export_requested) {
+            let svg = svg::scene_to_svg(
+                &self.scene,
+                &self.camera,
+                aspect,
+                color::srgb_to_linear(self.background_color),
+            );
+            self.export_error = std::fs::write(SVG_PATH, svg)
+                .map_err(|error| error.to_string())
+                .err();
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            let result = recorder.record(|| {
+                render_export(
+                    frame,
+                    render_callback,
+                    self.export_width,
+                    self.export_height,
+                )
+            });
+            if let Err(error) = result {
+                let recorder = self.recorder.take().unwrap();
+                self.record_status = Some(format!(
+                    "Recording stopped after {} frames: {error}",
+                    recorder.frames_written(),
+                ));
+            }
+        }
+
+        if std::mem::take(&mut self.export_requested) {
+            self.export_error = export_png(
+                frame,
+                render_callback,
+                self.export_width,
+                self.export_height,
+            )
+            .err();
+        }
+    }
+
+    fn paint_overlays(&self, painter: &egui::Painter, rect: egui::Rect) {
+        // the distances are drawn by egui over the shapes as the renderer has no text
+        for (start, end) in self.measurements() {
+            let galley = painter.layout_no_wrap(
+                format!("{:.3}", (end - start).magnitude()),
+                egui::FontId::proportional(14.0),
+                MEASUREMENT_COLOR,
+            );
+            let midpoint = self.camera.world_to_screen((start + end) * 0.5, rect);
+            let label_rect = egui::Align2::CENTER_BOTTOM
+                .anchor_rect(egui::Rect::from_min_size(midpoint, galley.size()));
+            painter.rect_filled(
+                label_rect.expand(2.0),
+                2.0,
+                egui::Color32::from_black_alpha(160),
+            );
+            painter.galley(label_rect.min, galley);
+        }
+
+        // the same for the labels, projected each frame so they stay crisp at any zoom
+        let font_size = if self.label_scale_with_zoom {
+            let (min, max) = self.label_font_size_range;
+            (self.label_font_size * self.camera.zoom / DEFAULT_CAMERA_ZOOM).clamp(min, max)
+        } else {
+            self.label_font_size
+        };
+        for (position, text) in self.labels() {
+            let anchor = self.camera.world_to_screen(position, rect);
+            // most labels in a big scene are off screen, so they are skipped before being laid out
+            if !rect.expand(font_size * 8.0).contains(anchor) {
+                continue;
+            }
+            let galley = painter.layout_no_wrap(
+                text,
+                egui::FontId::proportional(font_size),
+                egui::Color32::WHITE,
+            );
+            let label_rect = egui::Align2::CENTER_BOTTOM
+                .anchor_rect(egui::Rect::from_min_size(anchor, galley.size()));
+            if !rect.intersects(label_rect) {
+                continue;
+            }
+            painter.rect_filled(
+                label_rect.expand(2.0),
+                2.0,
+                egui::Color32::from_black_alpha(160),
+            );
+            painter.galley(label_rect.min, galley);
+        }
+
+        // shown in the view too, as the spawner keeps quietly refusing or recycling while its full
+        if self.scene.shape_count() >= self.max_shapes {
+            painter.text(
+                rect.center_top() + egui::vec2(0.0, 8.0),
+                egui::Align2::CENTER_TOP,
+                format!("Shape Limit Reached ({})", self.max_shapes),
+                egui::FontId::proportional(16.0),
+                egui::Color32::RED,
+            );
+        }
+    }
+
+    /// Shows which files are about to be dropped, and loads them once they are
+    fn dropped_files_input(
+        &mut self,
+        ctx: &egui::Context,
+        frame: &eframe::Frame,
+        rect: egui::Rect,
+    ) {
+        let (hovered_files, dropped_files, pointer_pos) = ctx.input(|input| {
+            (
+                input.raw.hovered_files.clone(),
+                input.raw.dropped_files.clone(),
+                input.pointer.latest_pos(),
+            )
+        });
+        if !hovered_files.is_empty() {
+            let names: Vec<String> = hovered_files
+                .iter()
+                .map(|file| file_name(file.path.as_deref(), &file.mime))
+                .collect();
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("Hovered Files"),
+            ));
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                format!("Drop to load\n{}", names.join("\n")),
+                egui::FontId::proportional(20.0),
+                egui::Color32::WHITE,
+            );
+        }
+        if !dropped_files.is_empty() {
+            // most platforms dont move the pointer while dragging files, so this can be where it left the window
+            let position = pointer_pos
+                .filter(|&pos| rect.contains(pos))
+                .map_or(self.camera.position, |pos| {
+                    self.camera.screen_to_world(pos, rect)
+                });
+            self.load_dropped_files(
+                frame.wgpu_render_state().unwrap(),
+                &dropped_files,
+                self.snap(position),
+            );
+        }
+    }
+}
+
+fn frame_rate_limit_name(limit: Option<u32>) -> String {
+    match limit {
+        Some(limit) => format!("{limit} FPS"),
+        None => "Unlimited".to_string(),
+    }
+}
+
+/// A row of the shape list
+struct ShapeListEntry {
+    /// Only circles can be selected
+    id: Option<ShapeId>,
+    name: String,
+    kind: &'static str,
+    position: Vector2<f32>,
+}
+
+/// Every shape in the scene in draw order, the ones without a name are named by their type and index
+fn shape_list_entries(scene: &Scene) -> Vec<ShapeListEntry> {
+    let mut entries = Vec::new();
+    let mut add = |id, name: Option<&str>, kind, index: usize, position| {
+        entries.push(ShapeListEntry {
+            id,
+            name: name.map_or_else(|| format!("#{index}"), str::to_string),
+            kind,
+            position,
+        });
+    };
+    for (index, (id, position, _)) in scene.query_circles().enumerate() {
+        add(
+            Some(id),
+            scene.names[index].as_deref(),
+            "Circle",
+            index,
+            position,
+        );
+    }
+    for (index, rectangle) in scene.rectangles.iter().enumerate() {
+        add(None, None, "Rectangle", index, rectangle.position);
+    }
+    for (index, ellipse) in scene.ellipses.iter().enumerate() {
+        add(None, None, "Ellipse", index, ellipse.position);
+    }
+    for (index, capsule) in scene.capsules.iter().enumerate() {
+        add(
+            None,
+            None,
+            "Capsule",
+            index,
+            (capsule.start + capsule.end) * 0.5,
+        );
+    }
+    for (index, ring) in scene.rings.iter().enumerate() {
+        add(None, None, "Ring", index, ring.position);
+    }
+    for (index, polygon) in scene.polygons.iter().enumerate() {
+        let vertices = polygon.vertices();
+        let center = vertices.iter().sum::<Vector2<f32>>() / vertices.len().max(1) as f32;
+        add(None, None, "Polygon", index, center);
+    }
+    for (index, line) in scene.lines.iter().enumerate() {
+        add(None, None, "Line", index, (line.start + line.end) * 0.5);
+    }
+    entries
+}
+
+/// The last part of `path`, or `fallback` for files that dont have one like those dropped on the web
+fn file_name(path: Option<&std::path::Path>, fallback: &str) -> String {
+    path.and_then(|path| path.file_name()).map_or_else(
+        || fallback.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Draws the frame times as a line with a reference line at `TARGET_FRAME_TIME`
+fn frame_time_graph(ui: &mut egui::Ui, frame_times: &VecDeque<std::time::Duration>) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(240.0, 60.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+    // leave some room above the slowest frame so stutters dont touch the top
+    let max_time = frame_times
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or_default()
+        .max(TARGET_FRAME_TIME)
+        .as_secs_f32()
+        * 1.2;
+    let to_screen = |index: usize, time: std::time::Duration| {
+        egui::pos2(
+            egui::lerp(
+                rect.x_range(),
+                index as f32 / (FRAME_TIME_SAMPLES - 1) as f32,
+            ),
+            egui::lerp(rect.bottom()..=rect.top(), time.as_secs_f32() / max_time),
+        )
+    };
+
+    let target_y = to_screen(0, TARGET_FRAME_TIME).y;
+    painter.hline(
+        rect.x_range(),
+        target_y,
+        egui::Stroke::new(1.0, egui::Color32::DARK_GREEN),
+    );
+    painter.text(
+        egui::pos2(rect.left() + 2.0, target_y),
+        egui::Align2::LEFT_BOTTOM,
+        format!("{:.1}ms", 1000.0 * TARGET_FRAME_TIME.as_secs_f64()),
+        egui::FontId::monospace(10.0),
+        egui::Color32::DARK_GREEN,
+    );
+
+    // the newest frame is always on the right edge
+    let start = FRAME_TIME_SAMPLES - frame_times.len();
+    let points = frame_times
+        .iter()
+        .enumerate()
+        .map(|(index, &time)| to_screen(start + index, time))
+        .collect::<Vec<_>>();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.0, ui.visuals().text_color()),
+    ));
+
+    response.on_hover_text(format!(
+        "Slowest Frame: {:.3}ms",
+        1000.0
+            * frame_times
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or_default()
+                .as_secs_f64()
+    ));
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
+        let time = web_time::Instant::now();
+        let dt = time.duration_since(self.last_frame_time.unwrap_or(time));
+        if self.last_frame_time.is_some() {
+            if self.frame_times.len() == FRAME_TIME_SAMPLES {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(dt);
+        }
+        self.last_frame_time = Some(time);
+        // recordings use a fixed step so they play back at a known rate however slow writing the frames is
+        let step = match self.recorder {
+            Some(_) => std::time::Duration::from_secs(1) / self.record_frame_rate,
+            None => dt,
+        };
+
+        self.update_attractor(ctx);
+        self.physics_steps_last_frame = self.advance_physics(step);
+
+        self.keyboard_input(ctx, step);
+        self.move_camera(step);
+
+        self.menu_bar(ctx);
+        if self.shape_list_open {
+            self.shape_list(ctx);
+        }
+        self.info_window(ctx, frame, dt);
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(self.background_color))
+            .show(ctx, |ui| {
+                self.scene_view(ui, ctx, frame);
+            });
+
+        match self.frame_rate_limit {
+            // the time this frame took counts towards the wait, so slower frames dont slow down the rate
+            Some(limit) => ctx.request_repaint_after(
+                (std::time::Duration::from_secs(1) / limit).saturating_sub(time.elapsed()),
+            ),
+            None => ctx.request_repaint(),
+        }
+    }
+}
+
+fn export_png(
+    frame: &eframe::Frame,
+    render_callback: &RenderCallback,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    render_export(frame, render_callback, width, height)?
+        .save(SCREENSHOT_PATH)
+        .map_err(|error| error.to_string())
+}
+
+/// Renders the same view as the screen at the export size, when the export has a different aspect the camera is
+/// fitted to it so nothing is stretched and nothing that was on screen is cut off
+fn render_export(
+    frame: &eframe::Frame,
+    render_callback: &RenderCallback,
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage, String> {
+    let render_state = frame.wgpu_render_state().unwrap();
+    let callback = RenderCallback {
+        shapes: Arc::new(Shapes {
+            camera: render_callback
+                .shapes
+                .camera
+                .fitted_to_aspect(width as f32 / height as f32),
+            ..Shapes::clone(&render_callback.shapes)
+        }),
+        sample_count: render_callback.sample_count,
+        target_size: [width, height],
+        clear_color: render_callback.clear_color,
+        frame_start: None,
+    };
+
+    render_to_image(render_state, &callback, callback.clear_color, width, height)
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    let settings = Settings::load_from_path_or_default(SETTINGS_PATH);
+    eframe::run_native(
+        "eframe circles and rectangles",
+        eframe::NativeOptions {
+            vsync: false,
+            renderer: eframe::Renderer::Wgpu,
+            wgpu_options: WgpuConfiguration {
+                power_preference: settings.power_preference.to_wgpu(),
+                device_descriptor: Arc::new(device_descriptor),
+                present_mode: settings.present_mode.to_wgpu(),
+                ..Default::default()
+            },
             ..Default::default()
         },
-        Box::new(|cc| Box::new(App::new(cc))),
+        Box::new(|cc| Box::new(App::new(cc, settings))),
     )
     .unwrap();
 }
+
+// the shapes are read from storage buffers which webgl doesnt have, so this needs a browser with webgpu
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(async {
+        eframe::WebRunner::new()
+            .start(
+                "the_canvas_id",
+                eframe::WebOptions {
+                    wgpu_options: WgpuConfiguration {
+                        power_preference: wgpu::PowerPreference::HighPerformance,
+                        device_descriptor: Arc::new(device_descriptor),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                // theres no file to read the settings from on the web
+                Box::new(|cc| Box::new(App::new(cc, Settings::default()))),
+            )
+            .await
+            .unwrap();
+    });
+}
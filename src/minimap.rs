@@ -0,0 +1,165 @@
+use crate::{
+    camera::{rotate, Camera},
+    color,
+    scene::Scene,
+};
+use cgmath::Vector2;
+use eframe::egui;
+
+/// How much bigger than the scene the minimap shows, so shapes at the edge arent cut off
+const MINIMAP_MARGIN: f32 = 1.1;
+/// Shapes smaller than this many points would disappear, so they are drawn at least this big
+const MIN_SHAPE_SIZE: f32 = 0.75;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "Top Left",
+            Corner::TopRight => "Top Right",
+            Corner::BottomLeft => "Bottom Left",
+            Corner::BottomRight => "Bottom Right",
+        }
+    }
+
+    fn align(self) -> egui::Align2 {
+        match self {
+            Corner::TopLeft => egui::Align2::LEFT_TOP,
+            Corner::TopRight => egui::Align2::RIGHT_TOP,
+            Corner::BottomLeft => egui::Align2::LEFT_BOTTOM,
+            Corner::BottomRight => egui::Align2::RIGHT_BOTTOM,
+        }
+    }
+}
+
+/// A small overview of the whole scene drawn with egui in a corner of the view, with the part the camera sees outlined
+///
+/// It is drawn with the egui painter rather than the renderer, as the renderer only has buffers for one view a frame
+pub struct Minimap {
+    pub enabled: bool,
+    /// The length of the longer side in points
+    pub size: f32,
+    pub corner: Corner,
+}
+
+impl Minimap {
+    /// Draws the minimap over `view_rect`, which is where `camera` is drawing the scene,
+    /// returning the world position that was clicked or dragged to
+    pub fn show(
+        &self,
+        ctx: &egui::Context,
+        view_rect: egui::Rect,
+        scene: &Scene,
+        camera: &Camera,
+    ) -> Option<Vector2<f32>> {
+        if !self.enabled {
+            return None;
+        }
+        let (min, max) = scene.bounding_box()?;
+        let center = (min + max) * 0.5;
+        let half_size = (max - min) * 0.5 * MINIMAP_MARGIN;
+        // a single point or a line still needs some area to fit in
+        let half_size = Vector2 {
+            x: half_size.x.max(half_size.y * 0.1).max(0.5),
+            y: half_size.y.max(half_size.x * 0.1).max(0.5),
+        };
+        let size = if half_size.x > half_size.y {
+            egui::vec2(self.size, self.size * half_size.y / half_size.x)
+        } else {
+            egui::vec2(self.size * half_size.x / half_size.y, self.size)
+        };
+        let points_per_unit = size.x / (half_size.x * 2.0);
+
+        let margin = egui::vec2(8.0, 8.0);
+        let align = self.corner.align();
+        let rect = align.align_size_within_rect(size, view_rect.shrink2(margin));
+
+        let area = egui::Area::new("Minimap")
+            .order(egui::Order::Foreground)
+            .fixed_pos(rect.min)
+            .show(ctx, |ui| {
+                let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+                let to_screen = |world: Vector2<f32>| {
+                    rect.center()
+                        + egui::vec2(world.x - center.x, center.y - world.y) * points_per_unit
+                };
+
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(192));
+                for circle in scene.iter_circles() {
+                    let [r, g, b, _] = color::linear_to_srgb(circle.color).to_array();
+                    painter.circle_filled(
+                        to_screen(circle.position),
+                        (circle.radius * points_per_unit).max(MIN_SHAPE_SIZE),
+                        egui::Color32::from_rgba_unmultiplied(
+                            r,
+                            g,
+                            b,
+                            (circle.alpha.clamp(0.0, 1.0) * 255.0) as u8,
+                        ),
+                    );
+                }
+                for rectangle in &scene.rectangles {
+                    let half_size = rectangle.size * 0.5;
+                    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(
+                        |(x, y): (f32, f32)| {
+                            let corner = Vector2 {
+                                x: x * half_size.x,
+                                y: y * half_size.y,
+                            };
+                            to_screen(rectangle.position + rotate(corner, rectangle.rotation))
+                        },
+                    );
+                    let [r, g, b, _] = color::linear_to_srgb(rectangle.color).to_array();
+                    painter.add(egui::Shape::convex_polygon(
+                        corners.to_vec(),
+                        egui::Color32::from_rgba_unmultiplied(
+                            r,
+                            g,
+                            b,
+                            (rectangle.alpha.clamp(0.0, 1.0) * 255.0) as u8,
+                        ),
+                        egui::Stroke::NONE,
+                    ));
+                }
+
+                // the corners of what the camera sees, which is turned with the camera
+                let view_corners = [
+                    view_rect.left_top(),
+                    view_rect.right_top(),
+                    view_rect.right_bottom(),
+                    view_rect.left_bottom(),
+                ]
+                .map(|corner| to_screen(camera.screen_to_world(corner, view_rect)));
+                painter.add(egui::Shape::closed_line(
+                    view_corners.to_vec(),
+                    egui::Stroke::new(1.0, egui::Color32::WHITE),
+                ));
+                painter.rect_stroke(rect, 2.0, ui.visuals().window_stroke());
+
+                let pointer_pos = response
+                    .interact_pointer_pos()
+                    .filter(|_| response.clicked() || response.dragged())?;
+                let offset = (pointer_pos - rect.center()) / points_per_unit;
+                Some(Vector2 {
+                    x: center.x + offset.x,
+                    y: center.y - offset.y,
+                })
+            });
+        area.inner
+    }
+}
@@ -0,0 +1,863 @@
+use cgmath::{InnerSpace, Vector2};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+
+#[cfg(feature = "rapier")]
+pub mod rapier;
+
+/// Identifies a circle for as long as it exists, unlike its index which shifts when circles before it are removed
+///
+/// The scene hands them out and the number means nothing else, it is only here so the physics doesnt need the scene.
+/// Ids arent saved, a loaded scene hands out new ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShapeId(pub u64);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PhysicsCircle {
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    pub radius: f32,
+    /// Always positive, scenes saved before circles had a mass get [`PhysicsCircle::default_mass`] when loaded
+    #[serde(default)]
+    pub mass: f32,
+    /// How much of the speed towards another circle is kept after bouncing off it, `1.0` is perfectly elastic,
+    /// the lower of the two circles restitutions is used
+    #[serde(default = "default_restitution")]
+    pub restitution: f32,
+    /// Static circles never move by themselves, and collisions and springs treat them as infinitely heavy,
+    /// their mass is only kept for when they are made dynamic again
+    #[serde(default)]
+    pub is_static: bool,
+}
+
+fn default_restitution() -> f32 {
+    1.0
+}
+
+impl PhysicsCircle {
+    /// A perfectly elastic circle with [`PhysicsCircle::default_mass`]
+    pub fn new(position: Vector2<f32>, velocity: Vector2<f32>, radius: f32) -> PhysicsCircle {
+        PhysicsCircle {
+            position,
+            velocity,
+            radius,
+            mass: PhysicsCircle::default_mass(radius),
+            restitution: default_restitution(),
+            is_static: false,
+        }
+    }
+
+    /// Zero for static circles, so nothing can move them
+    pub fn inverse_mass(&self) -> f32 {
+        if self.is_static {
+            0.0
+        } else {
+            1.0 / self.mass.max(f32::EPSILON)
+        }
+    }
+
+    /// The area without the pi, so bigger circles push smaller ones around
+    pub fn default_mass(radius: f32) -> f32 {
+        (radius * radius).max(f32::EPSILON)
+    }
+}
+
+/// Pulls two circles towards being `rest_length` apart, `a` and `b` are indices into the circles being stepped
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Spring {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    /// How much of the stretch or squash is undone each substep from `0.0` to `1.0`, where `1.0` is a rigid rod
+    ///
+    /// The circles are moved directly rather than being pushed with a force, so any value in that range is stable
+    /// however stiff it is, though a long chain still needs a few substeps to stop it from sagging
+    pub stiffness: f32,
+}
+
+/// An axis aligned box that the circles are kept inside of
+pub struct Boundary {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+    /// How much of the velocity is kept after bouncing off a wall, `1.0` is perfectly elastic
+    pub restitution: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BroadPhase {
+    /// Tests every pair of circles, fine for small scenes
+    BruteForce,
+    /// Only tests circles that are in the same or neighbouring cells of a [`SpatialHash`]
+    SpatialHash,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// [`step`], which can also run backwards
+    BuiltIn,
+    /// [`rapier::RapierWorld::step`], which ignores the springs
+    #[cfg(feature = "rapier")]
+    Rapier,
+}
+
+/// Pulls every circle towards a point with an inverse square force, or pushes them away if `strength` is negative
+#[derive(Clone, Copy)]
+pub struct Attractor {
+    pub position: Vector2<f32>,
+    /// The acceleration at a distance of 1
+    pub strength: f32,
+}
+
+impl Attractor {
+    /// Circles closer than this are accelerated as if they were this far away, so the force cant blow up
+    pub const MIN_DISTANCE: f32 = 0.5;
+
+    pub fn acceleration(&self, position: Vector2<f32>) -> Vector2<f32> {
+        let offset = self.position - position;
+        let distance = offset.magnitude();
+        if distance == 0.0 {
+            return Vector2 { x: 0.0, y: 0.0 };
+        }
+        let clamped = distance.max(Self::MIN_DISTANCE);
+        offset / distance * self.strength / (clamped * clamped)
+    }
+}
+
+pub struct PhysicsSettings {
+    pub gravity: Vector2<f32>,
+    pub boundary: Boundary,
+    /// Which step the circles are advanced with, the broad phase only applies to the built in one
+    pub backend: Backend,
+    pub broad_phase: BroadPhase,
+    pub attractor: Option<Attractor>,
+    /// How many smaller steps each call to [`step`] is split into, so fast circles move less between
+    /// collision checks and are less likely to pass through each other or push out of the boundary
+    ///
+    /// Each substep costs about as much as a whole step did, so the physics takes `substeps` times as
+    /// long, but unlike raising the tick rate it doesnt change how often the fixed steps run
+    pub substeps: u32,
+}
+
+/// Buckets circles into a grid of cells at least as big as the biggest circle,
+/// so any two overlapping circles are always in the same or neighbouring cells
+///
+/// The cells are kept sorted so the pairs always come out in the same order, resolving them in a different
+/// order each run would make the same scene play out differently
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: BTreeMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(circles: &[PhysicsCircle]) -> SpatialHash {
+        let max_radius = circles
+            .iter()
+            .map(|circle| circle.radius)
+            .fold(0.0, f32::max);
+        let mut spatial_hash = SpatialHash {
+            cell_size: (max_radius * 2.0).max(f32::EPSILON),
+            cells: BTreeMap::new(),
+        };
+        for (index, circle) in circles.iter().enumerate() {
+            let cell = spatial_hash.cell_of(circle.position);
+            spatial_hash.cells.entry(cell).or_default().push(index);
+        }
+        spatial_hash
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// The corner with the lowest coordinates and the one with the highest
+    pub fn cell_bounds(&self, (x, y): (i32, i32)) -> (Vector2<f32>, Vector2<f32>) {
+        let min = Vector2 {
+            x: x as f32 * self.cell_size,
+            y: y as f32 * self.cell_size,
+        };
+        (
+            min,
+            min + Vector2 {
+                x: self.cell_size,
+                y: self.cell_size,
+            },
+        )
+    }
+
+    /// Every cell that has a circle in it and how many circles it has, sorted by column and then row
+    pub fn occupied_cells(&self) -> impl Iterator<Item = ((i32, i32), usize)> + '_ {
+        self.cells
+            .iter()
+            .map(|(&cell, indices)| (cell, indices.len()))
+    }
+
+    pub fn cell_of(&self, position: Vector2<f32>) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Calls `f` once for every pair of circles that might be overlapping
+    pub fn for_each_candidate_pair(&self, mut f: impl FnMut(usize, usize)) {
+        // only half of the neighbours are checked from each cell so every pair of cells is only visited once
+        const NEIGHBOURS: [(i32, i32); 4] = [(1, 0), (-1, 1), (0, 1), (1, 1)];
+
+        for (&(x, y), indices) in &self.cells {
+            for (i, &a) in indices.iter().enumerate() {
+                for &b in &indices[i + 1..] {
+                    f(a, b);
+                }
+            }
+
+            for (dx, dy) in NEIGHBOURS {
+                let Some(neighbour_indices) = self.cells.get(&(x + dx, y + dy)) else {
+                    continue;
+                };
+                for &a in indices {
+                    for &b in neighbour_indices {
+                        f(a, b);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The id, center and radius of each circle a query looks at
+///
+/// The queries only need where the circles are, so they work on the drawn circles as well as the physics bodies
+pub type QueryCircle = (ShapeId, Vector2<f32>, f32);
+
+/// Every circle containing `point`, including ones it is exactly on the edge of, in the order they were given
+pub fn point_query(
+    circles: impl IntoIterator<Item = QueryCircle>,
+    point: Vector2<f32>,
+) -> Vec<ShapeId> {
+    circles
+        .into_iter()
+        .filter(|&(_, position, radius)| (position - point).magnitude2() <= radius * radius)
+        .map(|(id, _, _)| id)
+        .collect()
+}
+
+/// The first circle a ray from `origin` going in `direction` hits and how far along the ray it is,
+/// the distance is in world units whatever the length of `direction` is
+///
+/// A circle the origin is inside of is hit at a distance of `0.0`, and a zero direction never hits anything
+pub fn raycast(
+    circles: impl IntoIterator<Item = QueryCircle>,
+    origin: Vector2<f32>,
+    direction: Vector2<f32>,
+) -> Option<(ShapeId, f32)> {
+    if direction.magnitude2() == 0.0 {
+        return None;
+    }
+    let direction = direction.normalize();
+
+    circles
+        .into_iter()
+        .filter_map(|(id, position, radius)| {
+            // solving |origin + direction * t - position| = radius for the smaller t
+            let offset = origin - position;
+            let b = offset.dot(direction);
+            let c = offset.magnitude2() - radius * radius;
+            // outside of the circle and pointing away from it
+            if c > 0.0 && b > 0.0 {
+                return None;
+            }
+            let discriminant = b * b - c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            Some((id, (-b - discriminant.sqrt()).max(0.0)))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Adds `dt` to the time `accumulated` over earlier frames and works out how many fixed steps of `time_step`
+/// that covers, returning them along with the time that is left over for the next frame
+///
+/// At most `max_steps` are returned, any time beyond that is dropped so a long hitch doesnt cause more and more
+/// steps every frame, so the time left over is always less than one step
+pub fn fixed_steps(
+    accumulated: Duration,
+    dt: Duration,
+    time_step: Duration,
+    max_steps: u32,
+) -> (u32, Duration) {
+    if time_step.is_zero() {
+        return (0, Duration::ZERO);
+    }
+    let accumulated = (accumulated + dt).min(time_step * max_steps);
+    let steps = (accumulated.as_nanos() / time_step.as_nanos()) as u32;
+    (steps, accumulated - time_step * steps)
+}
+
+/// Advances the simulation by `dt`, integrating positions, pulling the springs back towards their rest length
+/// and then resolving any overlapping circles, split into [`PhysicsSettings::substeps`] steps
+///
+/// A negative `dt` runs the simulation backwards
+pub fn step(
+    circles: &mut [PhysicsCircle],
+    springs: &[Spring],
+    settings: &PhysicsSettings,
+    dt: f32,
+) {
+    let substeps = settings.substeps.max(1);
+    for _ in 0..substeps {
+        substep(circles, springs, settings, dt / substeps as f32);
+    }
+}
+
+fn substep(circles: &mut [PhysicsCircle], springs: &[Spring], settings: &PhysicsSettings, dt: f32) {
+    for circle in circles.iter_mut() {
+        if circle.is_static {
+            circle.velocity = Vector2 { x: 0.0, y: 0.0 };
+            continue;
+        }
+        circle.velocity += settings.gravity * dt;
+        if let Some(attractor) = &settings.attractor {
+            circle.velocity += attractor.acceleration(circle.position) * dt;
+        }
+        move_within_boundary(circle, &settings.boundary, dt);
+    }
+
+    for spring in springs {
+        let (a, b) = pair_mut(circles, spring.a, spring.b);
+        resolve_spring(a, b, spring, dt);
+    }
+
+    // a circle resting on another gains about this much speed towards it from gravity each step, bouncing that
+    // back off would keep it hopping forever so slower collisions dont bounce
+    let resting_speed = settings.gravity.magnitude() * dt.abs() * 2.0;
+    match settings.broad_phase {
+        BroadPhase::BruteForce => {
+            for i in 0..circles.len() {
+                let (left, right) = circles.split_at_mut(i + 1);
+                let a = &mut left[i];
+                for b in right {
+                    resolve_collision(a, b, resting_speed, dt);
+                }
+            }
+        }
+        BroadPhase::SpatialHash => {
+            let spatial_hash = SpatialHash::new(circles);
+            spatial_hash.for_each_candidate_pair(|a, b| {
+                let (a, b) = pair_mut(circles, a, b);
+                resolve_collision(a, b, resting_speed, dt);
+            });
+        }
+    }
+
+    // static circles can be put anywhere, even outside of the boundary
+    for circle in circles.iter_mut().filter(|circle| !circle.is_static) {
+        resolve_boundary(circle, &settings.boundary, dt);
+    }
+}
+
+/// When a circle moving from `start` by `displacement` first touches a wall of the boundary, as a fraction of
+/// the displacement from `0.0` to `1.0`, along with the normal of that wall pointing into the boundary
+///
+/// Only walls being crossed from the inside count, a circle already past a wall is left for the boundary to push back
+pub fn boundary_time_of_impact(
+    start: Vector2<f32>,
+    displacement: Vector2<f32>,
+    radius: f32,
+    boundary: &Boundary,
+) -> Option<(f32, Vector2<f32>)> {
+    // where the center is when the circle is touching each wall
+    let min = boundary.min
+        + Vector2 {
+            x: radius,
+            y: radius,
+        };
+    let max = boundary.max
+        - Vector2 {
+            x: radius,
+            y: radius,
+        };
+    let walls = [
+        (displacement.x < 0.0 && start.x >= min.x).then(|| {
+            let time = (min.x - start.x) / displacement.x;
+            (time, Vector2 { x: 1.0, y: 0.0 })
+        }),
+        (displacement.x > 0.0 && start.x <= max.x).then(|| {
+            let time = (max.x - start.x) / displacement.x;
+            (time, Vector2 { x: -1.0, y: 0.0 })
+        }),
+        (displacement.y < 0.0 && start.y >= min.y).then(|| {
+            let time = (min.y - start.y) / displacement.y;
+            (time, Vector2 { x: 0.0, y: 1.0 })
+        }),
+        (displacement.y > 0.0 && start.y <= max.y).then(|| {
+            let time = (max.y - start.y) / displacement.y;
+            (time, Vector2 { x: 0.0, y: -1.0 })
+        }),
+    ];
+    walls
+        .into_iter()
+        .flatten()
+        .filter(|&(time, _)| time <= 1.0)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+}
+
+/// Moves the circle by its velocity, stopping at any wall it would hit on the way and bouncing off with the
+/// rest of the movement, so however fast it is going it cant end up on the other side of a wall
+fn move_within_boundary(circle: &mut PhysicsCircle, boundary: &Boundary, dt: f32) {
+    // enough for a corner, anything more is left for resolve_boundary
+    const MAX_BOUNCES: usize = 4;
+
+    let mut displacement = circle.velocity * dt;
+    for _ in 0..MAX_BOUNCES {
+        let Some((time, normal)) =
+            boundary_time_of_impact(circle.position, displacement, circle.radius, boundary)
+        else {
+            break;
+        };
+        circle.position += displacement * time;
+        // the rest of the movement is reflected as well as the velocity
+        let reflect = |v: Vector2<f32>| v - normal * (v.dot(normal) * (1.0 + boundary.restitution));
+        circle.velocity = reflect(circle.velocity);
+        displacement = reflect(displacement * (1.0 - time));
+    }
+    circle.position += displacement;
+}
+
+fn pair_mut<T>(slice: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = slice.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = slice.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+fn resolve_boundary(circle: &mut PhysicsCircle, boundary: &Boundary, dt: f32) {
+    let direction = dt.signum();
+
+    if circle.position.x - circle.radius < boundary.min.x {
+        circle.position.x = boundary.min.x + circle.radius;
+        if circle.velocity.x * direction < 0.0 {
+            circle.velocity.x *= -boundary.restitution;
+        }
+    }
+    if circle.position.x + circle.radius > boundary.max.x {
+        circle.position.x = boundary.max.x - circle.radius;
+        if circle.velocity.x * direction > 0.0 {
+            circle.velocity.x *= -boundary.restitution;
+        }
+    }
+    if circle.position.y - circle.radius < boundary.min.y {
+        circle.position.y = boundary.min.y + circle.radius;
+        if circle.velocity.y * direction < 0.0 {
+            circle.velocity.y *= -boundary.restitution;
+        }
+    }
+    if circle.position.y + circle.radius > boundary.max.y {
+        circle.position.y = boundary.max.y - circle.radius;
+        if circle.velocity.y * direction > 0.0 {
+            circle.velocity.y *= -boundary.restitution;
+        }
+    }
+}
+
+fn resolve_spring(a: &mut PhysicsCircle, b: &mut PhysicsCircle, spring: &Spring, dt: f32) {
+    let offset = b.position - a.position;
+    let distance = offset.magnitude();
+    let a_inverse_mass = a.inverse_mass();
+    let b_inverse_mass = b.inverse_mass();
+    let total_inverse_mass = a_inverse_mass + b_inverse_mass;
+    if distance <= 0.0 || dt == 0.0 || total_inverse_mass == 0.0 {
+        return;
+    }
+
+    let normal = offset / distance;
+
+    // the lighter circle moves further, like pushing circles apart
+    let correction = normal * ((distance - spring.rest_length) * spring.stiffness.clamp(0.0, 1.0));
+    let a_movement = correction * (a_inverse_mass / total_inverse_mass);
+    let b_movement = -correction * (b_inverse_mass / total_inverse_mass);
+    a.position += a_movement;
+    b.position += b_movement;
+    // the velocities change by the same movement over the step, otherwise they would keep going and stretch it again
+    a.velocity += a_movement / dt;
+    b.velocity += b_movement / dt;
+}
+
+/// Collisions slower than `resting_speed` dont bounce at all
+fn resolve_collision(a: &mut PhysicsCircle, b: &mut PhysicsCircle, resting_speed: f32, dt: f32) {
+    let offset = b.position - a.position;
+    let distance = offset.magnitude();
+    let overlap = a.radius + b.radius - distance;
+    let a_inverse_mass = a.inverse_mass();
+    let b_inverse_mass = b.inverse_mass();
+    let total_inverse_mass = a_inverse_mass + b_inverse_mass;
+    if overlap <= 0.0 || distance <= 0.0 || total_inverse_mass == 0.0 {
+        return;
+    }
+
+    let normal = offset / distance;
+
+    // push them apart so they are just touching, the lighter circle moves further
+    a.position -= normal * (overlap * a_inverse_mass / total_inverse_mass);
+    b.position += normal * (overlap * b_inverse_mass / total_inverse_mass);
+
+    // only bounce if they are moving towards each other, flipped when time is running backwards
+    let relative_velocity = (b.velocity - a.velocity).dot(normal);
+    if relative_velocity * dt.signum() >= 0.0 {
+        return;
+    }
+
+    // the impulse along the normal that leaves them separating at `restitution` times the speed they met at
+    let restitution = if relative_velocity.abs() < resting_speed {
+        0.0
+    } else {
+        a.restitution.min(b.restitution)
+    };
+    let impulse = (1.0 + restitution) * relative_velocity / total_inverse_mass;
+    a.velocity += normal * (impulse * a_inverse_mass);
+    b.velocity -= normal * (impulse * b_inverse_mass);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// No gravity and walls far enough away to never be hit
+    fn settings() -> PhysicsSettings {
+        PhysicsSettings {
+            gravity: Vector2 { x: 0.0, y: 0.0 },
+            boundary: Boundary {
+                min: Vector2 {
+                    x: -100.0,
+                    y: -100.0,
+                },
+                max: Vector2 { x: 100.0, y: 100.0 },
+                restitution: 1.0,
+            },
+            backend: Backend::BuiltIn,
+            broad_phase: BroadPhase::BruteForce,
+            attractor: None,
+            substeps: 1,
+        }
+    }
+
+    fn assert_near(a: Vector2<f32>, b: Vector2<f32>) {
+        assert!((a - b).magnitude() < 1e-4, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn head_on_circles_swap_velocities() {
+        for broad_phase in [BroadPhase::BruteForce, BroadPhase::SpatialHash] {
+            let settings = PhysicsSettings {
+                broad_phase,
+                ..settings()
+            };
+            let mut circles = [
+                PhysicsCircle::new(Vector2 { x: -1.0, y: 0.0 }, Vector2 { x: 1.0, y: 0.0 }, 0.5),
+                PhysicsCircle::new(Vector2 { x: 1.0, y: 0.0 }, Vector2 { x: -1.0, y: 0.0 }, 0.5),
+            ];
+            for _ in 0..120 {
+                step(&mut circles, &[], &settings, 1.0 / 60.0);
+            }
+            assert_near(circles[0].velocity, Vector2 { x: -1.0, y: 0.0 });
+            assert_near(circles[1].velocity, Vector2 { x: 1.0, y: 0.0 });
+            // and they bounced back off each other instead of passing through
+            assert!(circles[0].position.x < circles[1].position.x);
+        }
+    }
+
+    #[test]
+    fn fixed_steps_are_capped_after_a_hitch() {
+        let time_step = Duration::from_secs(1) / 60;
+        // a whole minute stuck dragging the window
+        let (steps, left_over) =
+            fixed_steps(Duration::ZERO, Duration::from_secs(60), time_step, 10);
+        assert_eq!(steps, 10);
+        assert!(left_over < time_step);
+
+        // and the frame after it is back to normal as nothing was saved up
+        let (steps, left_over) = fixed_steps(left_over, time_step, time_step, 10);
+        assert!(steps <= 1);
+        assert!(left_over < time_step);
+    }
+
+    #[test]
+    fn fixed_steps_keep_the_left_over_time() {
+        let time_step = Duration::from_millis(10);
+        let (steps, left_over) = fixed_steps(
+            Duration::from_millis(4),
+            Duration::from_millis(21),
+            time_step,
+            10,
+        );
+        assert_eq!(steps, 2);
+        assert_eq!(left_over, Duration::from_millis(5));
+    }
+
+    fn query_circles() -> [QueryCircle; 3] {
+        [
+            (ShapeId(0), Vector2 { x: 0.0, y: 0.0 }, 1.0),
+            (ShapeId(1), Vector2 { x: 3.0, y: 0.0 }, 1.0),
+            (ShapeId(2), Vector2 { x: 0.5, y: 0.0 }, 0.5),
+        ]
+    }
+
+    #[test]
+    fn point_query_finds_every_containing_circle() {
+        let circles = query_circles();
+        assert_eq!(
+            point_query(circles, Vector2 { x: 0.9, y: 0.0 }),
+            [ShapeId(0), ShapeId(2)]
+        );
+        // exactly on the edge of both
+        assert_eq!(
+            point_query(circles, Vector2 { x: 1.0, y: 0.0 }),
+            [ShapeId(0), ShapeId(2)]
+        );
+        assert_eq!(
+            point_query(circles, Vector2 { x: 2.0, y: 0.0 }),
+            [ShapeId(1)]
+        );
+        assert!(point_query(circles, Vector2 { x: 0.0, y: 1.5 }).is_empty());
+    }
+
+    #[test]
+    fn raycast_hits_the_nearest_circle() {
+        let circles = query_circles();
+        // the direction isnt normalized, the distance is still in world units
+        assert_eq!(
+            raycast(
+                circles,
+                Vector2 { x: -5.0, y: 0.0 },
+                Vector2 { x: 2.0, y: 0.0 }
+            ),
+            Some((ShapeId(0), 4.0))
+        );
+        assert_eq!(
+            raycast(
+                circles,
+                Vector2 { x: 3.0, y: -5.0 },
+                Vector2 { x: 0.0, y: 1.0 }
+            ),
+            Some((ShapeId(1), 4.0))
+        );
+        // starting inside a circle
+        assert_eq!(
+            raycast(
+                circles,
+                Vector2 { x: 3.5, y: 0.0 },
+                Vector2 { x: 1.0, y: 0.0 }
+            ),
+            Some((ShapeId(1), 0.0))
+        );
+        // pointing away, passing by, and not pointing anywhere
+        assert_eq!(
+            raycast(
+                circles,
+                Vector2 { x: 5.0, y: 0.0 },
+                Vector2 { x: 1.0, y: 0.0 }
+            ),
+            None
+        );
+        assert_eq!(
+            raycast(
+                circles,
+                Vector2 { x: -5.0, y: 2.0 },
+                Vector2 { x: 1.0, y: 0.0 }
+            ),
+            None
+        );
+        assert_eq!(
+            raycast(
+                circles,
+                Vector2 { x: 0.0, y: 0.0 },
+                Vector2 { x: 0.0, y: 0.0 }
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn substeps_stop_tunneling_through_a_resting_circle() {
+        // a circle that moves ten times its own size each step, at a resting circle just as big
+        let run = |substeps| {
+            let settings = PhysicsSettings {
+                substeps,
+                ..settings()
+            };
+            let mut circles = [
+                PhysicsCircle::new(
+                    Vector2 { x: -0.5, y: 0.0 },
+                    Vector2 { x: 60.0, y: 0.0 },
+                    0.05,
+                ),
+                PhysicsCircle::new(Vector2 { x: 0.0, y: 0.0 }, Vector2 { x: 0.0, y: 0.0 }, 0.05),
+            ];
+            step(&mut circles, &[], &settings, 1.0 / 60.0);
+            circles
+        };
+
+        let [moving, resting] = run(1);
+        assert!(
+            moving.position.x > resting.position.x,
+            "expected a single step to tunnel"
+        );
+        assert_eq!(resting.velocity.x, 0.0);
+
+        let [moving, resting] = run(16);
+        assert!(
+            moving.position.x < resting.position.x,
+            "expected substeps to collide"
+        );
+        assert_near(resting.velocity, Vector2 { x: 60.0, y: 0.0 });
+    }
+
+    fn small_boundary() -> Boundary {
+        Boundary {
+            min: Vector2 { x: -10.0, y: -10.0 },
+            max: Vector2 { x: 10.0, y: 10.0 },
+            restitution: 1.0,
+        }
+    }
+
+    #[test]
+    fn boundary_time_of_impact_finds_the_first_wall() {
+        let boundary = small_boundary();
+        // the center touches the right wall at x = 9, 9 / 20 of the way along
+        assert_eq!(
+            boundary_time_of_impact(
+                Vector2 { x: 0.0, y: 0.0 },
+                Vector2 { x: 20.0, y: 0.0 },
+                1.0,
+                &boundary
+            ),
+            Some((0.45, Vector2 { x: -1.0, y: 0.0 }))
+        );
+        // towards the top right corner, the top is reached first
+        assert_eq!(
+            boundary_time_of_impact(
+                Vector2 { x: 0.0, y: 4.0 },
+                Vector2 { x: 10.0, y: 10.0 },
+                1.0,
+                &boundary
+            ),
+            Some((0.5, Vector2 { x: 0.0, y: -1.0 }))
+        );
+        // not far enough to reach anything
+        assert_eq!(
+            boundary_time_of_impact(
+                Vector2 { x: 0.0, y: 0.0 },
+                Vector2 { x: 5.0, y: 0.0 },
+                1.0,
+                &boundary
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn fast_circles_dont_tunnel_through_walls() {
+        let settings = PhysicsSettings {
+            boundary: small_boundary(),
+            ..settings()
+        };
+        // would move 50 units in one step, far past both walls
+        let mut circles = [PhysicsCircle::new(
+            Vector2 { x: 5.0, y: 0.0 },
+            Vector2 { x: 3000.0, y: 0.0 },
+            0.5,
+        )];
+        step(&mut circles, &[], &settings, 1.0 / 60.0);
+        let circle = &circles[0];
+        assert!(circle.position.x.abs() <= 9.5, "{:?}", circle.position);
+        // 4.5 to the right wall, 19 across to the left one and 19 back, and the last 7.5 heading left again
+        assert!(circle.velocity.x < 0.0);
+        assert!(
+            (circle.position.x - 2.0).abs() < 1e-3,
+            "{:?}",
+            circle.position
+        );
+    }
+
+    #[test]
+    fn heavy_circle_knocks_a_light_one_away() {
+        let mut heavy =
+            PhysicsCircle::new(Vector2 { x: -1.0, y: 0.0 }, Vector2 { x: 1.0, y: 0.0 }, 0.5);
+        heavy.mass = 100.0;
+        let mut light =
+            PhysicsCircle::new(Vector2 { x: 0.5, y: 0.0 }, Vector2 { x: 0.0, y: 0.0 }, 0.5);
+        light.mass = 1.0;
+        let mut circles = [heavy, light];
+        for _ in 0..60 {
+            step(&mut circles, &[], &settings(), 1.0 / 60.0);
+        }
+        let [heavy, light] = &circles;
+
+        // for a perfectly elastic hit the light one leaves at 2 * 100 / 101 and the heavy one keeps 99 / 101
+        assert!(
+            (light.velocity.x - 200.0 / 101.0).abs() < 1e-3,
+            "{:?}",
+            light.velocity
+        );
+        assert!(
+            (heavy.velocity.x - 99.0 / 101.0).abs() < 1e-3,
+            "{:?}",
+            heavy.velocity
+        );
+        // and nothing was lost
+        let momentum = heavy.mass * heavy.velocity.x + light.mass * light.velocity.x;
+        assert!((momentum - 100.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn the_lower_restitution_is_used() {
+        let mut a =
+            PhysicsCircle::new(Vector2 { x: -1.0, y: 0.0 }, Vector2 { x: 1.0, y: 0.0 }, 0.5);
+        a.restitution = 0.5;
+        let b = PhysicsCircle::new(Vector2 { x: 1.0, y: 0.0 }, Vector2 { x: -1.0, y: 0.0 }, 0.5);
+        let mut circles = [a, b];
+        for _ in 0..120 {
+            step(&mut circles, &[], &settings(), 1.0 / 60.0);
+        }
+        // the same mass so they split it evenly, separating at half the speed they met at
+        assert_near(circles[0].velocity, Vector2 { x: -0.5, y: 0.0 });
+        assert_near(circles[1].velocity, Vector2 { x: 0.5, y: 0.0 });
+    }
+
+    #[test]
+    fn spatial_hash_steps_the_same_every_time() {
+        // a crowded grid so lots of circles are touching and the order pairs are resolved in matters
+        let circles: Vec<PhysicsCircle> = (0..400)
+            .map(|i| {
+                let (x, y) = ((i % 20) as f32, (i / 20) as f32);
+                PhysicsCircle::new(
+                    Vector2 { x, y } * 0.9,
+                    Vector2 {
+                        x: (i * 7 % 11) as f32 - 5.0,
+                        y: (i * 13 % 7) as f32 - 3.0,
+                    },
+                    0.5,
+                )
+            })
+            .collect();
+        let settings = PhysicsSettings {
+            broad_phase: BroadPhase::SpatialHash,
+            ..settings()
+        };
+        let run = || {
+            let mut circles = circles.clone();
+            for _ in 0..60 {
+                step(&mut circles, &[], &settings, 1.0 / 60.0);
+            }
+            circles
+                .iter()
+                .map(|circle| (circle.position.x.to_bits(), circle.position.y.to_bits()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+}
@@ -0,0 +1,223 @@
+use super::{Boundary, PhysicsCircle, PhysicsSettings, ShapeId};
+use cgmath::Vector2;
+use rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// How thick the walls around the boundary are, so fast circles dont make it through in one step
+const WALL_THICKNESS: f32 = 10.0;
+
+/// Steps the circles with rapier instead of [`super::step`], each circle is a dynamic or fixed body with a ball collider
+///
+/// The circles stay the source of truth, before each step bodies are added, moved and removed to match them
+/// by their [`ShapeId`], so only circles that were changed outside of physics lose their contact state
+#[derive(Default)]
+pub struct RapierWorld {
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    /// The body and ball collider for every circle that was there last step
+    handles: HashMap<ShapeId, (RigidBodyHandle, ColliderHandle)>,
+    walls: Vec<ColliderHandle>,
+    /// The boundary the walls were made for, they are only remade when it changes
+    wall_boundary: Option<(Vector2<f32>, Vector2<f32>, f32)>,
+    integration_parameters: IntegrationParameters,
+    pipeline: PhysicsPipeline,
+    islands: IslandManager,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+}
+
+impl RapierWorld {
+    pub fn new() -> RapierWorld {
+        RapierWorld::default()
+    }
+
+    /// How many circles currently have a body
+    pub fn body_count(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Advances the circles by `dt`, `ids[i]` is the id of `circles[i]`
+    ///
+    /// Rapier cant run backwards, so nothing happens when `dt` isnt positive
+    pub fn step(
+        &mut self,
+        ids: &[ShapeId],
+        circles: &mut [PhysicsCircle],
+        settings: &PhysicsSettings,
+        dt: f32,
+    ) {
+        assert_eq!(ids.len(), circles.len());
+        if dt <= 0.0 {
+            return;
+        }
+
+        self.update_walls(&settings.boundary);
+        self.remove_missing(ids);
+
+        for (&id, circle) in ids.iter().zip(circles.iter()) {
+            // like in the built in step static circles dont keep whatever velocity they had before
+            let mut velocity = circle.velocity;
+            if circle.is_static {
+                velocity = Vector2 { x: 0.0, y: 0.0 };
+            } else if let Some(attractor) = &settings.attractor {
+                velocity += attractor.acceleration(circle.position) * dt;
+            }
+
+            let Some(&(body, collider)) = self.handles.get(&id) else {
+                let body = self.bodies.insert(
+                    RigidBodyBuilder::new(body_type(circle))
+                        .translation(vector![circle.position.x, circle.position.y])
+                        .linvel(vector![velocity.x, velocity.y])
+                        .can_sleep(false),
+                );
+                let collider = self.colliders.insert_with_parent(
+                    ball_collider(circle),
+                    body,
+                    &mut self.bodies,
+                );
+                self.handles.insert(id, (body, collider));
+                continue;
+            };
+
+            // both of these do nothing if the value is the same as what the last step wrote back
+            let body = &mut self.bodies[body];
+            if body.body_type() != body_type(circle) {
+                body.set_body_type(body_type(circle), true);
+            }
+            body.set_translation(vector![circle.position.x, circle.position.y], true);
+            body.set_linvel(vector![velocity.x, velocity.y], true);
+
+            let collider = &mut self.colliders[collider];
+            if collider.shape().as_ball().map(|ball| ball.radius) != Some(circle.radius) {
+                collider.set_shape(SharedShape::ball(circle.radius));
+            }
+            if collider.mass() != circle.mass {
+                collider.set_mass(circle.mass);
+            }
+            collider.set_restitution(circle.restitution);
+        }
+
+        let substeps = settings.substeps.max(1);
+        self.integration_parameters.dt = dt / substeps as f32;
+        for _ in 0..substeps {
+            self.pipeline.step(
+                &vector![settings.gravity.x, settings.gravity.y],
+                &self.integration_parameters,
+                &mut self.islands,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.bodies,
+                &mut self.colliders,
+                &mut self.impulse_joints,
+                &mut self.multibody_joints,
+                &mut self.ccd_solver,
+                None,
+                &(),
+                &(),
+            );
+        }
+
+        for (id, circle) in ids.iter().zip(circles.iter_mut()) {
+            let body = &self.bodies[self.handles[id].0];
+            circle.position = Vector2 {
+                x: body.translation().x,
+                y: body.translation().y,
+            };
+            circle.velocity = Vector2 {
+                x: body.linvel().x,
+                y: body.linvel().y,
+            };
+        }
+    }
+
+    /// Removes the bodies of circles that arent in `ids` anymore
+    fn remove_missing(&mut self, ids: &[ShapeId]) {
+        let ids: HashSet<ShapeId> = ids.iter().copied().collect();
+        let bodies = &mut self.bodies;
+        let islands = &mut self.islands;
+        let colliders = &mut self.colliders;
+        let impulse_joints = &mut self.impulse_joints;
+        let multibody_joints = &mut self.multibody_joints;
+        self.handles.retain(|id, &mut (body, _)| {
+            if ids.contains(id) {
+                return true;
+            }
+            bodies.remove(
+                body,
+                islands,
+                colliders,
+                impulse_joints,
+                multibody_joints,
+                true,
+            );
+            false
+        });
+    }
+
+    /// Puts a fixed box along each side of the boundary, bouncing with its restitution
+    fn update_walls(&mut self, boundary: &Boundary) {
+        let key = (boundary.min, boundary.max, boundary.restitution);
+        if self.wall_boundary == Some(key) {
+            return;
+        }
+        self.wall_boundary = Some(key);
+
+        for wall in self.walls.drain(..) {
+            self.colliders
+                .remove(wall, &mut self.islands, &mut self.bodies, true);
+        }
+
+        let center = (boundary.min + boundary.max) * 0.5;
+        let half_size = (boundary.max - boundary.min) * 0.5;
+        let half_thickness = WALL_THICKNESS * 0.5;
+        // the walls are long enough to overlap in the corners
+        let walls = [
+            (
+                vector![boundary.min.x - half_thickness, center.y],
+                vector![half_thickness, half_size.y + WALL_THICKNESS],
+            ),
+            (
+                vector![boundary.max.x + half_thickness, center.y],
+                vector![half_thickness, half_size.y + WALL_THICKNESS],
+            ),
+            (
+                vector![center.x, boundary.min.y - half_thickness],
+                vector![half_size.x + WALL_THICKNESS, half_thickness],
+            ),
+            (
+                vector![center.x, boundary.max.y + half_thickness],
+                vector![half_size.x + WALL_THICKNESS, half_thickness],
+            ),
+        ];
+        for (position, half_extents) in walls {
+            let wall = ColliderBuilder::cuboid(half_extents.x, half_extents.y)
+                .translation(position)
+                .friction(0.0)
+                .restitution(boundary.restitution)
+                // the same rule as the circles, so a circle bounces with the lower of its own and the walls
+                .restitution_combine_rule(CoefficientCombineRule::Min);
+            self.walls.push(self.colliders.insert(wall));
+        }
+    }
+}
+
+fn body_type(circle: &PhysicsCircle) -> RigidBodyType {
+    if circle.is_static {
+        RigidBodyType::Fixed
+    } else {
+        RigidBodyType::Dynamic
+    }
+}
+
+/// Bounces with the lower of the two restitutions and without friction like in the built in step
+fn ball_collider(circle: &PhysicsCircle) -> Collider {
+    ColliderBuilder::ball(circle.radius)
+        .friction(0.0)
+        .restitution(circle.restitution)
+        .restitution_combine_rule(CoefficientCombineRule::Min)
+        .mass(circle.mass)
+        .build()
+}
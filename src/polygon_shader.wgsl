@@ -0,0 +1,137 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) polygon_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) polygon_index: u32,
+    @location(1) world_position: vec2<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+const MAX_POLYGON_VERTICES: u32 = 8u;
+
+struct Polygon {
+    vertices: array<vec2<f32>, MAX_POLYGON_VERTICES>,
+    vertex_count: u32,
+    color: vec3<f32>,
+};
+
+@group(1)
+@binding(0)
+var<storage, read> polygons: array<Polygon>;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.polygon_index = input.polygon_index;
+
+    let uv = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u),
+        f32((input.vertex_index >> 1u) & 1u),
+    );
+
+    // the vertices are indexed straight out of the buffer as arrays in a local copy can only be indexed by constants
+    let vertices = &polygons[input.polygon_index].vertices;
+
+    // the quad is the bounding box of the polygon
+    var min_position = (*vertices)[0];
+    var max_position = (*vertices)[0];
+    for (var i = 1u; i < min(polygons[input.polygon_index].vertex_count, MAX_POLYGON_VERTICES); i += 1u) {
+        min_position = min(min_position, (*vertices)[i]);
+        max_position = max(max_position, (*vertices)[i]);
+    }
+
+    output.world_position = mix(min_position, max_position, uv);
+    output.clip_position = world_to_clip(output.world_position);
+
+    return output;
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let polygon = polygons[input.polygon_index];
+    let vertices = &polygons[input.polygon_index].vertices;
+
+    // the same edge width as the circles get, worked out before any discard as derivatives need every pixel
+    let pixel_size = length(dpdx(input.world_position));
+    let edge_width = pixel_size * max(output.edge_softness, 0.01);
+
+    let count = min(polygon.vertex_count, MAX_POLYGON_VERTICES);
+    if count < 3u {
+        discard;
+    }
+
+    // the distance to the nearest edge, and whether the point is inside by counting the edges a ray from it crosses
+    let point = input.world_position;
+    var distance_squared = 1e30;
+    var inside = false;
+    var previous = (*vertices)[count - 1u];
+    for (var i = 0u; i < count; i += 1u) {
+        let current = (*vertices)[i];
+        let edge = previous - current;
+        let from_current = point - current;
+        let t = clamp(dot(from_current, edge) / max(dot(edge, edge), 0.000001), 0.0, 1.0);
+        let from_edge = from_current - edge * t;
+        distance_squared = min(distance_squared, dot(from_edge, from_edge));
+
+        if (current.y > point.y) != (previous.y > point.y) {
+            let crossing_x = current.x + (point.y - current.y) / (previous.y - current.y) * (previous.x - current.x);
+            if point.x < crossing_x {
+                inside = !inside;
+            }
+        }
+        previous = current;
+    }
+    let distance = select(1.0, -1.0, inside) * sqrt(distance_squared);
+
+    let alpha = clamp(0.5 - distance / edge_width, 0.0, 1.0);
+    if alpha <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(encode_output(polygon.color) * alpha, alpha);
+}
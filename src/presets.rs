@@ -0,0 +1,251 @@
+use crate::{
+    physics::{Attractor, Boundary},
+    rendering::GpuCircle,
+    scene::{CircleSpring, Scene},
+};
+use cgmath::{prelude::*, Vector2, Vector3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::f32::consts::TAU;
+
+/// A demo scene to start from, each one is a pure function of the seed and the boundary it is made to fit in
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Grid,
+    Galaxy,
+    BouncingBalls,
+    Cradle,
+}
+
+/// The physics a preset is made for, applied along with its scene when it is loaded
+pub struct PresetPhysics {
+    pub gravity: Vector2<f32>,
+    /// An attractor that stays in place, unlike the one held under the cursor
+    pub attractor: Option<Attractor>,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::Grid,
+        Preset::Galaxy,
+        Preset::BouncingBalls,
+        Preset::Cradle,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Grid => "Grid",
+            Preset::Galaxy => "Galaxy Spiral",
+            Preset::BouncingBalls => "Bouncing Balls",
+            Preset::Cradle => "Newtons Cradle",
+        }
+    }
+
+    pub fn generate(self, seed: u64, boundary: &Boundary) -> Scene {
+        let (min, max) = (boundary.min, boundary.max);
+        match self {
+            Preset::Grid => grid(seed, min, max),
+            Preset::Galaxy => galaxy(seed, min, max),
+            Preset::BouncingBalls => bouncing_balls(seed, min, max),
+            Preset::Cradle => cradle(seed, min, max),
+        }
+    }
+
+    pub fn physics(self, boundary: &Boundary) -> PresetPhysics {
+        match self {
+            Preset::Galaxy => PresetPhysics {
+                gravity: Vector2 { x: 0.0, y: 0.0 },
+                attractor: Some(galaxy_attractor(boundary.min, boundary.max)),
+            },
+            Preset::Grid | Preset::BouncingBalls | Preset::Cradle => PresetPhysics {
+                gravity: crate::DEFAULT_GRAVITY,
+                attractor: None,
+            },
+        }
+    }
+}
+
+fn random_color(rng: &mut impl Rng) -> Vector3<f32> {
+    Vector3 {
+        x: rng.gen_range(0.0..=1.0),
+        y: rng.gen_range(0.0..=1.0),
+        z: rng.gen_range(0.0..=1.0),
+    }
+}
+
+/// A grid of small circles filling the box, colored with a gradient between two random colors
+pub fn grid(seed: u64, min: Vector2<f32>, max: Vector2<f32>) -> Scene {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (from, to) = (random_color(&mut rng), random_color(&mut rng));
+
+    let mut scene = Scene::new();
+    scene.add_circle_grid(min, max, 400);
+    let size = max - min;
+    for circle in &mut scene.circles {
+        let t =
+            (circle.position.x - min.x) / size.x * 0.5 + (circle.position.y - min.y) / size.y * 0.5;
+        circle.color = from.lerp(to, t);
+    }
+    scene
+}
+
+/// The attractor at the core of [`galaxy`] that keeps the arms in orbit
+pub fn galaxy_attractor(min: Vector2<f32>, max: Vector2<f32>) -> Attractor {
+    Attractor {
+        position: (min + max) * 0.5,
+        strength: 9.0,
+    }
+}
+
+/// Tiny circles along a few spiral arms around a heavy static core, each one orbiting the [`galaxy_attractor`]
+pub fn galaxy(seed: u64, min: Vector2<f32>, max: Vector2<f32>) -> Scene {
+    const ARMS: u32 = 3;
+    const CIRCLES_PER_ARM: u32 = 250;
+    // how many times each arm goes around from the core to the edge
+    const TURNS: f32 = 0.75;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let attractor = galaxy_attractor(min, max);
+    let center = attractor.position;
+    let max_distance = (max - min).x.min((max - min).y) * 0.45;
+    let core_color = Vector3 {
+        x: 1.0,
+        y: 0.9,
+        z: 0.6,
+    };
+    let edge_color = Vector3 {
+        x: 0.2,
+        y: 0.4,
+        z: 1.0,
+    };
+
+    let mut scene = Scene::new();
+    let core_radius = max_distance * 0.08;
+    scene.add_circle(
+        GpuCircle::new(center)
+            .with_color(core_color)
+            .with_radius(core_radius),
+    );
+    scene.bodies.last_mut().unwrap().is_static = true;
+
+    let start_angle = rng.gen_range(0.0..TAU);
+    for arm in 0..ARMS {
+        let arm_angle = start_angle + arm as f32 / ARMS as f32 * TAU;
+        for _ in 0..CIRCLES_PER_ARM {
+            // more circles closer in, like a real galaxy
+            let t: f32 = rng.gen_range(0.0f32..=1.0).powf(1.5);
+            let distance = core_radius * 1.5 + t * (max_distance - core_radius * 1.5);
+            let angle = arm_angle + t * TURNS * TAU + rng.gen_range(-0.3..=0.3) * (1.0 - t * 0.5);
+            let direction = Vector2 {
+                x: angle.cos(),
+                y: angle.sin(),
+            };
+            let position = center + direction * distance;
+            // just fast enough for a circular orbit, and counter clockwise so the arms trail behind
+            let speed = (attractor.acceleration(position).magnitude() * distance).sqrt();
+            scene.add_circle_with_velocity(
+                GpuCircle::new(position)
+                    .with_color(core_color.lerp(edge_color, t))
+                    .with_radius(rng.gen_range(0.02..=0.05)),
+                Vector2 {
+                    x: -direction.y,
+                    y: direction.x,
+                } * speed,
+            );
+        }
+    }
+    scene
+}
+
+/// Perfectly elastic balls of random sizes flying around the box, placed one per cell so none of them start overlapping
+pub fn bouncing_balls(seed: u64, min: Vector2<f32>, max: Vector2<f32>) -> Scene {
+    const COLUMNS: u32 = 10;
+    const ROWS: u32 = 5;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let cell = Vector2 {
+        x: (max.x - min.x) / COLUMNS as f32,
+        y: (max.y - min.y) / ROWS as f32,
+    };
+    let max_radius = cell.x.min(cell.y) * 0.4;
+
+    let mut scene = Scene::new();
+    for row in 0..ROWS {
+        for column in 0..COLUMNS {
+            let radius = rng.gen_range(max_radius * 0.4..=max_radius);
+            // anywhere in the cell the circle still fits
+            let slack = cell * 0.5 - Vector2::new(radius, radius);
+            let position = Vector2 {
+                x: min.x + (column as f32 + 0.5) * cell.x + rng.gen_range(-slack.x..=slack.x),
+                y: min.y + (row as f32 + 0.5) * cell.y + rng.gen_range(-slack.y..=slack.y),
+            };
+            let angle = rng.gen_range(0.0..TAU);
+            let speed = rng.gen_range(2.0..=6.0);
+            scene.add_circle_with_velocity(
+                GpuCircle::new(position)
+                    .with_color(random_color(&mut rng))
+                    .with_radius(radius),
+                Vector2 {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                } * speed,
+            );
+            scene.bodies.last_mut().unwrap().restitution = 1.0;
+        }
+    }
+    scene
+}
+
+/// A row of touching balls each hanging from a static anchor by a rigid spring, with the first one or two pulled
+/// out to the side ready to swing in, needs gravity to do anything
+pub fn cradle(seed: u64, min: Vector2<f32>, max: Vector2<f32>) -> Scene {
+    const BALLS: u32 = 5;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let height = max.y - min.y;
+    let radius = ((max.x - min.x) / (BALLS as f32 * 4.0)).min(height * 0.1);
+    let length = height * 0.6;
+    let top = max.y - radius;
+    let center_x = (min.x + max.x) * 0.5;
+    let pulled = rng.gen_range(1..=2);
+    let pulled_angle: f32 = rng.gen_range(30.0f32..=60.0).to_radians();
+    let ball_color = Vector3 {
+        x: 0.7,
+        y: 0.7,
+        z: 0.75,
+    };
+
+    let mut scene = Scene::new();
+    for i in 0..BALLS {
+        // the tiniest gap between neighbours, so they dont start out pushing each other apart
+        let anchor_position = Vector2 {
+            x: center_x + (i as f32 - (BALLS - 1) as f32 * 0.5) * radius * 2.001,
+            y: top,
+        };
+        let anchor = scene.add_circle(
+            GpuCircle::new(anchor_position)
+                .with_color_rgb(0.2, 0.2, 0.2)
+                .with_radius(radius * 0.2),
+        );
+        scene.bodies.last_mut().unwrap().is_static = true;
+
+        let angle = if i < pulled { -pulled_angle } else { 0.0 };
+        let position = anchor_position
+            + Vector2 {
+                x: angle.sin(),
+                y: -angle.cos(),
+            } * length;
+        let ball = scene.add_circle(
+            GpuCircle::new(position)
+                .with_color(ball_color)
+                .with_radius(radius),
+        );
+        scene.bodies.last_mut().unwrap().restitution = 1.0;
+        scene.add_spring(CircleSpring {
+            a: anchor,
+            b: ball,
+            rest_length: length,
+            stiffness: 1.0,
+        });
+    }
+    scene
+}
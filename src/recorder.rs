@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+/// Writes every `every_nth` frame of a recording into a directory as `frame_00000.png`, `frame_00001.png` and so on,
+/// the numbers have no gaps so the sequence can be given straight to a video encoder
+pub struct Recorder {
+    directory: PathBuf,
+    every_nth: u32,
+    /// Frames seen since the recording started, including the ones that were skipped
+    frames_seen: u32,
+    frames_written: u32,
+}
+
+impl Recorder {
+    /// Creates the directory if it isnt there yet, frames already in it are overwritten
+    pub fn start(directory: impl AsRef<Path>, every_nth: u32) -> std::io::Result<Recorder> {
+        std::fs::create_dir_all(&directory)?;
+        Ok(Recorder {
+            directory: directory.as_ref().to_path_buf(),
+            every_nth: every_nth.max(1),
+            frames_seen: 0,
+            frames_written: 0,
+        })
+    }
+
+    /// Counts a frame, calling `render` and saving what it returns when the frame is one that gets written
+    ///
+    /// Each frame is saved before this returns so nothing is left to flush when the recording stops
+    pub fn record(
+        &mut self,
+        render: impl FnOnce() -> Result<image::RgbaImage, String>,
+    ) -> Result<(), String> {
+        let write = self.frames_seen.is_multiple_of(self.every_nth);
+        self.frames_seen += 1;
+        if !write {
+            return Ok(());
+        }
+
+        let path = self
+            .directory
+            .join(format!("frame_{:05}.png", self.frames_written));
+        render()?.save(path).map_err(|error| error.to_string())?;
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    pub fn frames_written(&self) -> u32 {
+        self.frames_written
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
@@ -5,7 +5,7 @@ struct VertexInput {
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) rectangle_index: u32,
+    @location(0) @interpolate(flat) rectangle_index: u32,
     @location(1) uv: vec2<f32>,
 };
 
@@ -13,16 +13,60 @@ struct Camera {
     position: vec2<f32>,
     aspect: f32,
     zoom: f32,
+    rotation: f32,
 };
 
 @group(0)
 @binding(0)
 var<uniform> camera: Camera;
 
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
 struct Rectangle {
     position: vec2<f32>,
     color: vec3<f32>,
     size: vec2<f32>,
+    rotation: f32,
+    alpha: f32,
+    corner_radius: f32,
+    color2: vec3<f32>,
+    gradient_angle: f32,
+    use_gradient: u32,
+    layer: i32,
+    outline_color: vec3<f32>,
+    outline_width: f32,
+    dash_length: f32,
+    gap_length: f32,
 };
 
 @group(1)
@@ -39,14 +83,92 @@ fn vertex(input: VertexInput) -> VertexOutput {
         f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
     );
 
-    let world_position = output.uv * rectangles[input.rectangle_index].size * 0.5 + rectangles[input.rectangle_index].position;
+    let rectangle = rectangles[input.rectangle_index];
+
+    // rotate the corner around the center before the camera transform so the aspect correction doesnt skew it
+    // size is the full size, so halve it to get the offset of the corner from the center
+    let local_position = output.uv * rectangle.size * 0.5;
+    let c = cos(rectangle.rotation);
+    let s = sin(rectangle.rotation);
+    let rotated_position = vec2<f32>(
+        local_position.x * c - local_position.y * s,
+        local_position.x * s + local_position.y * c,
+    );
+
+    let world_position = rotated_position + rectangle.position;
 
-    output.clip_position = vec4<f32>((world_position - camera.position) * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+    output.clip_position = world_to_clip(world_position);
 
     return output;
 }
 
+// how far around the edge of the rounded box `p` is, counterclockwise from the middle of the right side
+fn perimeter_distance(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    // the distance in the top right quarter, which the other quarters mirror
+    let a = abs(p);
+    let inner = half_size - radius;
+    let q = a - inner;
+    var d: f32;
+    if q.x > 0.0 && q.y > 0.0 {
+        d = inner.y + radius * atan2(q.y, q.x);
+    } else if q.x >= q.y {
+        d = min(a.y, inner.y);
+    } else {
+        d = inner.y + radius * 1.5707964 + inner.x - min(a.x, inner.x);
+    }
+
+    let quarter = inner.x + inner.y + radius * 1.5707964;
+    if p.x >= 0.0 && p.y >= 0.0 {
+        return d;
+    } else if p.y >= 0.0 {
+        return quarter * 2.0 - d;
+    } else if p.x < 0.0 {
+        return quarter * 2.0 + d;
+    }
+    return quarter * 4.0 - d;
+}
+
 @fragment
 fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(rectangles[input.rectangle_index].color, 1.0);
+    let rectangle = rectangles[input.rectangle_index];
+
+    let half_size = rectangle.size * 0.5;
+    let local_position = input.uv * half_size;
+    // the local position is linear across the quad so its derivative is exactly the world size of a pixel
+    let pixel_size = length(dpdx(local_position));
+
+    // signed distance to the edge of the rounded box, negative inside
+    let radius = clamp(rectangle.corner_radius, 0.0, min(half_size.x, half_size.y));
+    let q = abs(local_position) - half_size + radius;
+    let distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+
+    // sharp rectangles are left exactly as they were, without anti-aliased edges
+    let coverage = select(1.0, clamp(0.5 - distance / pixel_size, 0.0, 1.0), radius > 0.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    // scaled by how far the corners reach along the direction so the gradient goes from 0 to 1 across the rectangle
+    let direction = vec2<f32>(cos(rectangle.gradient_angle), sin(rectangle.gradient_angle));
+    let extent = dot(abs(direction), half_size);
+    let t = clamp(dot(local_position, direction) / extent * 0.5 + 0.5, 0.0, 1.0);
+    let fill_color = select(rectangle.color, mix(rectangle.color, rectangle.color2, t), rectangle.use_gradient != 0u);
+
+    // the outline is drawn inside of the edge like the circle outlines, and the fill shows through its gaps
+    var outline = 0.0;
+    if rectangle.outline_width > 0.0 {
+        outline = clamp(0.5 + (distance + rectangle.outline_width) / pixel_size, 0.0, 1.0);
+        // the dashes are in pixels so they are the same size on screen at any zoom
+        if rectangle.dash_length > 0.0 {
+            let along = perimeter_distance(local_position, half_size, radius) / pixel_size;
+            let period = rectangle.dash_length + max(rectangle.gap_length, 0.0);
+            if along - floor(along / period) * period >= rectangle.dash_length {
+                outline = 0.0;
+            }
+        }
+    }
+    let color = mix(fill_color, rectangle.outline_color, outline);
+
+    let alpha = rectangle.alpha * coverage;
+    return vec4<f32>(encode_output(color) * alpha, alpha);
 }
@@ -1,22 +1,237 @@
-use cgmath::{Vector2, Vector3};
+use crate::color;
+use cgmath::{Vector2, Vector3, Vector4};
 use eframe::{
+    egui,
     egui_wgpu::{self, CallbackTrait},
     wgpu::{self, include_wgsl},
 };
 use encase::{ShaderSize, ShaderType, StorageBuffer, UniformBuffer};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    fmt,
+    num::NonZeroU64,
+    ops::Range,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-#[derive(ShaderType)]
+/// How many measurements the gpu time is averaged over
+const GPU_TIME_SAMPLES: usize = 30;
+
+#[derive(Clone, Copy, ShaderType)]
 pub struct GpuCamera {
     pub position: Vector2<f32>,
     pub aspect: f32,
     pub zoom: f32,
+    /// How far the view is turned counterclockwise, in radians
+    pub rotation: f32,
+}
+
+impl GpuCamera {
+    /// The same camera for a view with a different aspect, zoomed out just enough that everything this camera
+    /// shows is still shown, the wider or taller view only adds more of the world around it
+    pub fn fitted_to_aspect(self, aspect: f32) -> GpuCamera {
+        GpuCamera {
+            aspect,
+            // the height shown is 2 / zoom and the width 2 * aspect / zoom, so only a narrower view has to
+            // zoom out to fit the same width
+            zoom: self.zoom * (aspect / self.aspect).min(1.0),
+            ..self
+        }
+    }
 }
 
+/// How the shaders have to write their colors for the target format, and how soft the edges they draw are
 #[derive(ShaderType)]
+struct GpuOutput {
+    /// 0 if the shape format encodes linear colors to srgb itself
+    encode_srgb: u32,
+    /// See [`Shapes::edge_softness`]
+    edge_softness: f32,
+}
+
+/// A world space grid drawn behind every shape, the lines are always 1 pixel wide whatever the zoom
+#[derive(Clone, ShaderType)]
+pub struct GpuGrid {
+    /// Distance between the minor lines in world units
+    pub spacing: f32,
+    /// Every `major_every`th line is a major line
+    pub major_every: u32,
+    pub minor_color: Vector3<f32>,
+    pub major_color: Vector3<f32>,
+}
+
+/// How the circles are shown as a heatmap of how many of them cover each pixel, instead of being drawn
+#[derive(Clone, ShaderType)]
+pub struct GpuDensity {
+    /// How many overlapping circles reach the end of the ramp, a single circle is at the start of it
+    pub max_density: f32,
+    /// A [`DensityRamp`] as a u32
+    pub ramp: u32,
+}
+
+/// The colors the density goes through, from the fewest circles to the most
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DensityRamp {
+    /// Blue through yellow to white
+    #[default]
+    Heat,
+    Viridis,
+    Grayscale,
+}
+
+impl DensityRamp {
+    pub const ALL: [DensityRamp; 3] = [
+        DensityRamp::Heat,
+        DensityRamp::Viridis,
+        DensityRamp::Grayscale,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DensityRamp::Heat => "Heat",
+            DensityRamp::Viridis => "Viridis",
+            DensityRamp::Grayscale => "Grayscale",
+        }
+    }
+}
+
+/// How the glow of the emissive circles is spread out and added on top of the shapes
+#[derive(Clone, ShaderType)]
+pub struct GpuBloom {
+    /// Standard deviation of the blur in pixels of the bloom target, which is half the size of the target
+    pub radius: f32,
+    /// What the blurred glow is multiplied by before its added
+    pub intensity: f32,
+}
+
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
 pub struct GpuCircle {
     pub position: Vector2<f32>,
     pub color: Vector3<f32>,
     pub radius: f32,
+    pub outline_color: Vector3<f32>,
+    /// Width of the outline in world units, drawn inside of the radius
+    pub outline_width: f32,
+    /// Opacity of the whole circle, overlapping circles blend in the order they are in the scene
+    #[serde(default = "default_alpha")]
+    pub alpha: f32,
+    /// The second color of the gradient, only used when `use_gradient` isnt 0
+    #[serde(default = "default_color2")]
+    pub color2: Vector3<f32>,
+    /// Direction the gradient goes from `color` to `color2` in, in radians
+    #[serde(default)]
+    pub gradient_angle: f32,
+    /// 0 for a solid fill of `color`, anything else to fill with a linear gradient, its a u32 as shaders cant share bools
+    #[serde(default)]
+    pub use_gradient: u32,
+    /// Circles and rectangles on lower layers are drawn first, circles on the same layer keep their order
+    ///
+    /// Within a layer the circles are drawn before the rectangles
+    #[serde(default)]
+    pub layer: i32,
+    /// How brightly the circle glows when bloom is on, 0 for no glow
+    ///
+    /// The glow is the circles color times this, so anything above 1 is brighter than the circle itself
+    #[serde(default)]
+    pub emissive: f32,
+}
+
+fn default_alpha() -> f32 {
+    1.0
+}
+
+fn default_color2() -> Vector3<f32> {
+    Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    }
+}
+
+/// A white unit circle at the origin
+impl Default for GpuCircle {
+    fn default() -> Self {
+        Self {
+            position: Vector2 { x: 0.0, y: 0.0 },
+            color: Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            radius: 1.0,
+            outline_color: Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            outline_width: 0.0,
+            alpha: 1.0,
+            color2: default_color2(),
+            gradient_angle: 0.0,
+            use_gradient: 0,
+            layer: 0,
+            emissive: 0.0,
+        }
+    }
+}
+
+impl GpuCircle {
+    pub fn new(position: Vector2<f32>) -> GpuCircle {
+        GpuCircle {
+            position,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_color(self, color: Vector3<f32>) -> GpuCircle {
+        GpuCircle { color, ..self }
+    }
+
+    pub fn with_color_rgb(self, r: f32, g: f32, b: f32) -> GpuCircle {
+        self.with_color(Vector3 { x: r, y: g, z: b })
+    }
+
+    pub fn with_color32(self, color: egui::Color32) -> GpuCircle {
+        self.with_color(color::srgb_to_linear(color))
+    }
+
+    pub fn with_radius(self, radius: f32) -> GpuCircle {
+        GpuCircle { radius, ..self }
+    }
+
+    pub fn with_outline(self, outline_color: Vector3<f32>, outline_width: f32) -> GpuCircle {
+        GpuCircle {
+            outline_color,
+            outline_width,
+            ..self
+        }
+    }
+
+    pub fn with_alpha(self, alpha: f32) -> GpuCircle {
+        GpuCircle { alpha, ..self }
+    }
+
+    pub fn with_layer(self, layer: i32) -> GpuCircle {
+        GpuCircle { layer, ..self }
+    }
+
+    pub fn with_emissive(self, emissive: f32) -> GpuCircle {
+        GpuCircle { emissive, ..self }
+    }
+
+    /// Fills the circle with a gradient from its color to `color2`
+    pub fn with_gradient(self, color2: Vector3<f32>, gradient_angle: f32) -> GpuCircle {
+        GpuCircle {
+            color2,
+            gradient_angle,
+            use_gradient: 1,
+            ..self
+        }
+    }
 }
 
 #[derive(ShaderType)]
@@ -25,11 +240,145 @@ struct GpuCircles<'a> {
     circles: &'a [GpuCircle],
 }
 
-#[derive(ShaderType)]
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
 pub struct GpuRectangle {
     pub position: Vector2<f32>,
     pub color: Vector3<f32>,
+    /// The full width and height of the rectangle, not the half extents, it is centered on `position`
     pub size: Vector2<f32>,
+    /// Rotation around the rectangle's center, in radians
+    pub rotation: f32,
+    /// Opacity, rectangles blend over the circles and the rectangles before them
+    #[serde(default = "default_alpha")]
+    pub alpha: f32,
+    /// Radius of the rounded corners in world units, no more than half of the smaller side is used
+    #[serde(default)]
+    pub corner_radius: f32,
+    /// The second color of the gradient, only used when `use_gradient` isnt 0
+    #[serde(default = "default_color2")]
+    pub color2: Vector3<f32>,
+    /// Direction the gradient goes from `color` to `color2` in, in radians, relative to the rectangle's rotation
+    #[serde(default)]
+    pub gradient_angle: f32,
+    /// 0 for a solid fill of `color`, anything else to fill with a linear gradient, its a u32 as shaders cant share bools
+    #[serde(default)]
+    pub use_gradient: u32,
+    /// Circles and rectangles on lower layers are drawn first, rectangles on the same layer keep their order
+    ///
+    /// Within a layer the rectangles are drawn after the circles
+    #[serde(default)]
+    pub layer: i32,
+    #[serde(default = "default_color2")]
+    pub outline_color: Vector3<f32>,
+    /// Width of the outline in world units, drawn inside of the edge, 0 for no outline
+    #[serde(default)]
+    pub outline_width: f32,
+    /// Length of each dash of the outline in pixels, 0 for a solid outline
+    #[serde(default)]
+    pub dash_length: f32,
+    /// Length of the gaps between the dashes in pixels, the fill shows through them
+    #[serde(default)]
+    pub gap_length: f32,
+}
+
+impl Default for GpuRectangle {
+    fn default() -> Self {
+        Self {
+            position: Vector2 { x: 0.0, y: 0.0 },
+            color: Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            size: Vector2 { x: 1.0, y: 1.0 },
+            rotation: 0.0,
+            alpha: 1.0,
+            corner_radius: 0.0,
+            color2: default_color2(),
+            gradient_angle: 0.0,
+            use_gradient: 0,
+            layer: 0,
+            outline_color: Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            outline_width: 0.0,
+            dash_length: 0.0,
+            gap_length: 0.0,
+        }
+    }
+}
+
+impl GpuRectangle {
+    pub fn new(position: Vector2<f32>) -> GpuRectangle {
+        GpuRectangle {
+            position,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_color(self, color: Vector3<f32>) -> GpuRectangle {
+        GpuRectangle { color, ..self }
+    }
+
+    pub fn with_color_rgb(self, r: f32, g: f32, b: f32) -> GpuRectangle {
+        self.with_color(Vector3 { x: r, y: g, z: b })
+    }
+
+    pub fn with_color32(self, color: egui::Color32) -> GpuRectangle {
+        self.with_color(color::srgb_to_linear(color))
+    }
+
+    pub fn with_size(self, size: Vector2<f32>) -> GpuRectangle {
+        GpuRectangle { size, ..self }
+    }
+
+    pub fn with_rotation(self, rotation: f32) -> GpuRectangle {
+        GpuRectangle { rotation, ..self }
+    }
+
+    pub fn with_alpha(self, alpha: f32) -> GpuRectangle {
+        GpuRectangle { alpha, ..self }
+    }
+
+    pub fn with_corner_radius(self, corner_radius: f32) -> GpuRectangle {
+        GpuRectangle {
+            corner_radius,
+            ..self
+        }
+    }
+
+    pub fn with_layer(self, layer: i32) -> GpuRectangle {
+        GpuRectangle { layer, ..self }
+    }
+
+    pub fn with_outline(self, outline_color: Vector3<f32>, outline_width: f32) -> GpuRectangle {
+        GpuRectangle {
+            outline_color,
+            outline_width,
+            ..self
+        }
+    }
+
+    /// Dashes the outline, both lengths are in pixels
+    pub fn with_dashes(self, dash_length: f32, gap_length: f32) -> GpuRectangle {
+        GpuRectangle {
+            dash_length,
+            gap_length,
+            ..self
+        }
+    }
+
+    /// Fills the rectangle with a gradient from its color to `color2`
+    pub fn with_gradient(self, color2: Vector3<f32>, gradient_angle: f32) -> GpuRectangle {
+        GpuRectangle {
+            color2,
+            gradient_angle,
+            use_gradient: 1,
+            ..self
+        }
+    }
 }
 
 #[derive(ShaderType)]
@@ -38,174 +387,361 @@ struct GpuRectangles<'a> {
     rectangles: &'a [GpuRectangle],
 }
 
-struct RenderState {
-    camera_buffer: wgpu::Buffer,
-    camera_bind_group: wgpu::BindGroup,
-    circle_render_pipeline: wgpu::RenderPipeline,
-    circle_buffer_size: wgpu::BufferAddress,
-    circle_buffer: wgpu::Buffer,
-    circle_bind_group_layout: wgpu::BindGroupLayout,
-    circle_bind_group: wgpu::BindGroup,
-    rectangle_render_pipeline: wgpu::RenderPipeline,
-    rectangle_buffer_size: wgpu::BufferAddress,
-    rectangle_buffer: wgpu::Buffer,
-    rectangle_bind_group_layout: wgpu::BindGroupLayout,
-    rectangle_bind_group: wgpu::BindGroup,
+/// An image from [`load_texture`] drawn on an axis aligned quad
+#[derive(Clone, ShaderType)]
+pub struct GpuSprite {
+    pub position: Vector2<f32>,
+    /// The full width and height in world units
+    pub size: Vector2<f32>,
+    /// Sprites whose texture isnt loaded arent drawn
+    pub texture_id: u32,
+    /// Linear color and alpha the sampled color is multiplied by, white for the image as it is
+    pub tint: Vector4<f32>,
 }
 
-pub fn create_render_state(cc: &eframe::CreationContext) {
-    let egui_wgpu::RenderState {
-        ref device,
-        target_format,
-        ref renderer,
-        ..
-    } = *cc.wgpu_render_state.as_ref().unwrap();
+#[derive(ShaderType)]
+struct GpuSprites<'a> {
+    #[size(runtime)]
+    sprites: &'a [GpuSprite],
+}
 
-    let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Camera Buffer"),
-        size: GpuCamera::SHADER_SIZE.get(),
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
-        mapped_at_creation: false,
-    });
+/// How the circles are shaded, the emissive glow is always drawn the same way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircleQuality {
+    /// Smooth anti-aliased edges `edge_softness` pixels wide
+    #[default]
+    Quality,
+    /// Hard edges without any derivatives, which is cheaper per pixel and looks the same for circles only
+    /// a few pixels across, circles smaller than a pixel only show up where they cover a pixel center
+    Performance,
+}
 
-    let camera_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Camera Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: Some(GpuCamera::min_size()),
-                },
-                count: None,
-            }],
-        });
+impl CircleQuality {
+    pub const ALL: [CircleQuality; 2] = [CircleQuality::Quality, CircleQuality::Performance];
 
-    let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Camera Bind Group"),
-        layout: &camera_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: camera_buffer.as_entire_binding(),
-        }],
-    });
-
-    let circle_buffer_size = GpuCircles::min_size().get();
-    let circle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Circle Buffer"),
-        size: circle_buffer_size,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
-        mapped_at_creation: false,
-    });
+    pub fn name(self) -> &'static str {
+        match self {
+            CircleQuality::Quality => "Quality",
+            CircleQuality::Performance => "Performance",
+        }
+    }
+}
 
-    let circle_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Circle Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: Some(GpuCircles::min_size()),
-                },
-                count: None,
-            }],
-        });
+/// Whether the color channels of an image have already been multiplied by its alpha
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// What png files and most other images store
+    Straight,
+    Premultiplied,
+}
 
-    let circle_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Circle Bind Group"),
-        layout: &circle_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: circle_buffer.as_entire_binding(),
-        }],
-    });
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
+pub struct GpuEllipse {
+    pub position: Vector2<f32>,
+    pub color: Vector3<f32>,
+    /// The radius along the x and y axis
+    pub radii: Vector2<f32>,
+}
 
-    let circle_shader = device.create_shader_module(include_wgsl!("./circle_shader.wgsl"));
+#[derive(ShaderType)]
+struct GpuEllipses<'a> {
+    #[size(runtime)]
+    ellipses: &'a [GpuEllipse],
+}
 
-    let circle_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Circle Pipeline Layout"),
-        bind_group_layouts: &[&camera_bind_group_layout, &circle_bind_group_layout],
-        push_constant_ranges: &[],
-    });
-
-    let circle_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Circle Render Pipeline"),
-        layout: Some(&circle_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &circle_shader,
-            entry_point: "vertex",
-            buffers: &[],
-        },
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleStrip,
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Cw,
-            cull_mode: None,
-            unclipped_depth: false,
-            polygon_mode: wgpu::PolygonMode::Fill,
-            conservative: false,
-        },
-        depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
-        fragment: Some(wgpu::FragmentState {
-            module: &circle_shader,
-            entry_point: "pixel",
-            targets: &[Some(target_format.into())],
-        }),
-        multiview: None,
-    });
-
-    let rectangle_buffer_size = GpuRectangles::min_size().get();
-    let rectangle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Rectangle Buffer"),
-        size: rectangle_buffer_size,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
-        mapped_at_creation: false,
-    });
+/// A rectangle with round ends, every point within `radius` of the segment from `start` to `end`
+///
+/// With `start` and `end` in the same place it is a circle
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
+pub struct GpuCapsule {
+    pub start: Vector2<f32>,
+    pub end: Vector2<f32>,
+    pub radius: f32,
+    pub color: Vector3<f32>,
+}
+
+#[derive(ShaderType)]
+struct GpuCapsules<'a> {
+    #[size(runtime)]
+    capsules: &'a [GpuCapsule],
+}
+
+/// A circle with a hole cut out of the middle and only the part between two angles kept,
+/// for arcs, pies and progress indicators
+///
+/// With no inner radius and the whole way around it is the same as a filled circle
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
+pub struct GpuRing {
+    pub position: Vector2<f32>,
+    /// `0.0` for a pie with no hole
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    /// The ring goes counterclockwise from `start_angle` to `end_angle`, in radians from the x axis,
+    /// anything at least a full turn apart is a whole ring
+    pub start_angle: f32,
+    pub end_angle: f32,
+    pub color: Vector3<f32>,
+}
+
+#[derive(ShaderType)]
+struct GpuRings<'a> {
+    #[size(runtime)]
+    rings: &'a [GpuRing],
+}
+
+/// The most vertices a [`GpuPolygon`] can have, the shader has the same limit
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+/// A filled convex polygon of up to [`MAX_POLYGON_VERTICES`] vertices, in either winding order
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
+pub struct GpuPolygon {
+    /// Only the first `vertex_count` are used
+    pub vertices: [Vector2<f32>; MAX_POLYGON_VERTICES],
+    pub vertex_count: u32,
+    pub color: Vector3<f32>,
+}
+
+impl GpuPolygon {
+    /// Panics if there are less than 3 or more than [`MAX_POLYGON_VERTICES`] vertices
+    pub fn new(vertices: &[Vector2<f32>]) -> GpuPolygon {
+        assert!(
+            (3..=MAX_POLYGON_VERTICES).contains(&vertices.len()),
+            "a polygon needs 3 to {MAX_POLYGON_VERTICES} vertices, got {}",
+            vertices.len()
+        );
+        let mut polygon = GpuPolygon {
+            vertices: [Vector2 { x: 0.0, y: 0.0 }; MAX_POLYGON_VERTICES],
+            vertex_count: vertices.len() as u32,
+            color: Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        };
+        polygon.vertices[..vertices.len()].copy_from_slice(vertices);
+        polygon
+    }
+
+    /// A polygon with `sides` equal sides whose corners are `radius` from `center`, with a corner pointing up
+    ///
+    /// `sides` is clamped to between 3 and [`MAX_POLYGON_VERTICES`]
+    pub fn regular(center: Vector2<f32>, radius: f32, sides: u32) -> GpuPolygon {
+        let sides = sides.clamp(3, MAX_POLYGON_VERTICES as u32);
+        let vertices: Vec<_> = (0..sides)
+            .map(|i| {
+                let angle =
+                    std::f32::consts::FRAC_PI_2 + std::f32::consts::TAU * i as f32 / sides as f32;
+                center
+                    + Vector2 {
+                        x: angle.cos(),
+                        y: angle.sin(),
+                    } * radius
+            })
+            .collect();
+        GpuPolygon::new(&vertices)
+    }
+
+    pub fn with_color(self, color: Vector3<f32>) -> GpuPolygon {
+        GpuPolygon { color, ..self }
+    }
+
+    pub fn vertices(&self) -> &[Vector2<f32>] {
+        &self.vertices[..(self.vertex_count as usize).min(MAX_POLYGON_VERTICES)]
+    }
+}
 
-    let rectangle_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Rectangle Bind Group Layout"),
+#[derive(ShaderType)]
+struct GpuPolygons<'a> {
+    #[size(runtime)]
+    polygons: &'a [GpuPolygon],
+}
+
+#[derive(Clone, ShaderType, Serialize, Deserialize)]
+pub struct GpuLine {
+    pub start: Vector2<f32>,
+    pub end: Vector2<f32>,
+    /// Width in world units, so it scales with the zoom
+    pub width: f32,
+    pub color: Vector3<f32>,
+    /// Length of each dash in pixels, so unlike the width it stays the same on screen, 0 for a solid line
+    #[serde(default)]
+    pub dash_length: f32,
+    /// Length of the gaps between the dashes in pixels
+    #[serde(default)]
+    pub gap_length: f32,
+}
+
+#[derive(ShaderType)]
+struct GpuLines<'a> {
+    #[size(runtime)]
+    lines: &'a [GpuLine],
+}
+
+/// A storage buffer holding every instance of one shape type, along with its bind group
+struct InstanceBuffer {
+    name: &'static str,
+    /// The size of a single instance
+    stride: wgpu::BufferAddress,
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl InstanceBuffer {
+    fn new(device: &wgpu::Device, name: &'static str, stride: NonZeroU64) -> InstanceBuffer {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{name} Bind Group Layout")),
             entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
                 visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Storage { read_only: true },
                     has_dynamic_offset: false,
-                    min_binding_size: Some(GpuRectangles::min_size()),
+                    min_binding_size: Some(stride),
                 },
                 count: None,
             }],
         });
+        let (buffer, bind_group) =
+            Self::create_buffer(device, name, &bind_group_layout, stride.get(), 1);
+        InstanceBuffer {
+            name,
+            stride: stride.get(),
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
 
-    let rectangle_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Rectangle Bind Group"),
-        layout: &rectangle_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: rectangle_buffer.as_entire_binding(),
-        }],
-    });
+    fn create_buffer(
+        device: &wgpu::Device,
+        name: &str,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        stride: wgpu::BufferAddress,
+        capacity: wgpu::BufferAddress,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{name} Buffer (capacity {capacity})")),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{name} Bind Group")),
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        (buffer, bind_group)
+    }
 
-    let rectangle_shader = device.create_shader_module(include_wgsl!("./rectangle_shader.wgsl"));
+    /// How many instances fit in the buffer before it has to be recreated
+    fn capacity(&self) -> wgpu::BufferAddress {
+        self.buffer.size() / self.stride
+    }
 
-    let rectangle_pipeline_layout =
-        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Rectangle Pipeline Layout"),
-            bind_group_layouts: &[&camera_bind_group_layout, &rectangle_bind_group_layout],
-            push_constant_ranges: &[],
-        });
+    /// Uploads the encoded instances, the buffer grows to the next power of two
+    /// when they dont fit and never shrinks, so counts going up and down dont keep reallocating
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) {
+        let size = data.len() as wgpu::BufferAddress;
+        if size > self.buffer.size() {
+            let capacity = size.div_ceil(self.stride).next_power_of_two();
+            (self.buffer, self.bind_group) = Self::create_buffer(
+                device,
+                self.name,
+                &self.bind_group_layout,
+                self.stride,
+                capacity,
+            );
+        }
+        queue.write_buffer(&self.buffer, 0, data);
+    }
+}
+
+/// A shape's render pipeline along with what it was made from, so it can be remade for a different sample count
+struct ShapePipeline {
+    name: &'static str,
+    layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    fragment_entry_point: &'static str,
+    /// `None` for shapes with hard edges that dont need blending
+    blend: Option<wgpu::BlendState>,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ShapePipeline {
+    fn new(
+        device: &wgpu::Device,
+        name: &'static str,
+        layout: wgpu::PipelineLayout,
+        shader: wgpu::ShaderModule,
+        target_format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> ShapePipeline {
+        Self::with_entry_point(device, name, layout, shader, "pixel", target_format, blend)
+    }
 
-    let rectangle_render_pipeline =
+    /// Like [`ShapePipeline::new`], making the pipeline layout from `bind_group_layouts` for shapes that dont share it
+    fn with_layouts(
+        device: &wgpu::Device,
+        name: &'static str,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        shader: wgpu::ShaderModule,
+        target_format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> ShapePipeline {
+        let layout = pipeline_layout(device, name, bind_group_layouts);
+        Self::new(device, name, layout, shader, target_format, blend)
+    }
+
+    /// Like [`ShapePipeline::new`] for a shader with a fragment entry point other than `pixel`
+    fn with_entry_point(
+        device: &wgpu::Device,
+        name: &'static str,
+        layout: wgpu::PipelineLayout,
+        shader: wgpu::ShaderModule,
+        fragment_entry_point: &'static str,
+        target_format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> ShapePipeline {
+        let pipeline = Self::create_pipeline(
+            device,
+            name,
+            &layout,
+            &shader,
+            fragment_entry_point,
+            target_format,
+            blend,
+            1,
+        );
+        ShapePipeline {
+            name,
+            layout,
+            shader,
+            fragment_entry_point,
+            blend,
+            pipeline,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_pipeline(
+        device: &wgpu::Device,
+        name: &str,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        fragment_entry_point: &str,
+        target_format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Rectangle Render Pipeline"),
-            layout: Some(&rectangle_pipeline_layout),
+            label: Some(&format!("{name} Render Pipeline")),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
-                module: &rectangle_shader,
+                module: shader,
                 entry_point: "vertex",
                 buffers: &[],
             },
@@ -219,117 +755,1880 @@ pub fn create_render_state(cc: &eframe::CreationContext) {
                 conservative: false,
             },
             depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             fragment: Some(wgpu::FragmentState {
-                module: &rectangle_shader,
-                entry_point: "pixel",
-                targets: &[Some(target_format.into())],
+                module: shader,
+                entry_point: fragment_entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
             }),
             multiview: None,
-        });
+        })
+    }
 
-    renderer.write().callback_resources.insert(RenderState {
-        camera_buffer,
-        camera_bind_group,
-        circle_render_pipeline,
-        circle_buffer_size,
-        circle_buffer,
-        circle_bind_group_layout,
-        circle_bind_group,
-        rectangle_render_pipeline,
-        rectangle_buffer_size,
-        rectangle_buffer,
-        rectangle_bind_group_layout,
-        rectangle_bind_group,
-    });
+    fn set_sample_count(
+        &mut self,
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) {
+        self.pipeline = Self::create_pipeline(
+            device,
+            self.name,
+            &self.layout,
+            &self.shader,
+            self.fragment_entry_point,
+            target_format,
+            self.blend,
+            sample_count,
+        );
+    }
 }
 
-pub struct RenderCallback {
-    pub camera: GpuCamera,
-    pub circles: Vec<GpuCircle>,
-    pub rectangles: Vec<GpuRectangle>,
+/// The texture in the shape format the shapes are drawn into when they cant be drawn straight into the egui
+/// render pass, which is then drawn over it
+///
+/// With msaa they are drawn into the multisampled texture which resolves into it
+struct ShapeTarget {
+    width: u32,
+    height: u32,
+    sample_count: u32,
+    /// `None` without msaa
+    multisampled_view: Option<wgpu::TextureView>,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
 }
 
-impl CallbackTrait for RenderCallback {
-    fn prepare(
-        &self,
+impl ShapeTarget {
+    fn new(
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        _egui_encoder: &mut wgpu::CommandEncoder,
-        callback_resources: &mut eframe::egui_wgpu::CallbackResources,
-    ) -> Vec<wgpu::CommandBuffer> {
-        let render_state: &mut RenderState = callback_resources.get_mut().unwrap();
-
-        {
-            let mut camera_buffer = UniformBuffer::new([0u8; GpuCamera::SHADER_SIZE.get() as _]);
-            camera_buffer.write(&self.camera).unwrap();
-            queue.write_buffer(&render_state.camera_buffer, 0, &camera_buffer.into_inner());
+        shape_format: wgpu::TextureFormat,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> ShapeTarget {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let multisampled_view = (sample_count > 1).then(|| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Multisampled Texture"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: shape_format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shape Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: shape_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blit Bind Group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        ShapeTarget {
+            width,
+            height,
+            sample_count,
+            multisampled_view,
+            view,
+            bind_group,
         }
+    }
+}
 
-        {
-            let mut circle_buffer = StorageBuffer::new(vec![]);
-            circle_buffer
-                .write(&GpuCircles {
-                    circles: &self.circles,
+/// The two half size textures the glow is drawn into and blurred back and forth between,
+/// the finished blur always ends up back in the first one
+struct BloomTarget {
+    width: u32,
+    height: u32,
+    views: [wgpu::TextureView; 2],
+    /// Each samples the texture of the view with the same index
+    bind_groups: [wgpu::BindGroup; 2],
+}
+
+impl BloomTarget {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        bloom_buffer: &wgpu::Buffer,
+        output_buffer: &wgpu::Buffer,
+        width: u32,
+        height: u32,
+    ) -> BloomTarget {
+        let views = ["Bloom Texture", "Bloom Blur Texture"].map(|label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
                 })
-                .unwrap();
-            let circle_buffer = circle_buffer.into_inner();
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        let bind_groups = [&views[0], &views[1]].map(|view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bloom Bind Group"),
+                layout: bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: bloom_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: output_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+        BloomTarget {
+            width,
+            height,
+            views,
+            bind_groups,
+        }
+    }
+}
 
-            if circle_buffer.len() as wgpu::BufferAddress > render_state.circle_buffer_size {
-                render_state.circle_buffer_size = circle_buffer.len() as _;
+/// The full size texture the coverage of every circle is added up in for the density view
+struct DensityTarget {
+    width: u32,
+    height: u32,
+    view: wgpu::TextureView,
+    /// Samples the texture of `view`
+    bind_group: wgpu::BindGroup,
+}
 
-                render_state.circle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("Circle Buffer"),
-                    size: render_state.circle_buffer_size,
-                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
-                    mapped_at_creation: false,
-                });
+impl DensityTarget {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        density_buffer: &wgpu::Buffer,
+        output_buffer: &wgpu::Buffer,
+        width: u32,
+        height: u32,
+    ) -> DensityTarget {
+        let view = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Density Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Density Bind Group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: density_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: output_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        DensityTarget {
+            width,
+            height,
+            view,
+            bind_group,
+        }
+    }
+}
 
-                render_state.circle_bind_group =
-                    device.create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: Some("Circle Bind Group"),
-                        layout: &render_state.circle_bind_group_layout,
-                        entries: &[wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: render_state.circle_buffer.as_entire_binding(),
-                        }],
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TimerState {
+    /// Ready for timestamps to be written this frame
+    Idle,
+    /// Timestamps were written around the draws this frame
+    Written,
+    /// The timestamps were resolved and copied into the readback buffer
+    Copied,
+    /// Waiting for the readback buffer to be mapped
+    Mapping,
+}
+
+/// Measures how long the shapes take to draw on the gpu with timestamp queries
+///
+/// Reading the timestamps back is spread over a few frames so nothing ever waits on the gpu
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Whether timestamps can be written inside of the egui render pass, without this only msaa can be timed
+    inside_passes: bool,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    state: TimerState,
+    /// Set by the map callback, which isnt called until the device is polled
+    map_result: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+    samples: VecDeque<Duration>,
+}
+
+impl GpuTimer {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<GpuTimer> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let size = 2 * std::mem::size_of::<u64>() as wgpu::BufferAddress;
+        Some(GpuTimer {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Timestamp Query Set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Readback Buffer"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            inside_passes: device
+                .features()
+                .contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES),
+            period: queue.get_timestamp_period(),
+            state: TimerState::Idle,
+            map_result: Arc::new(Mutex::new(None)),
+            samples: VecDeque::new(),
+        })
+    }
+
+    /// Moves the readback of the last timestamps along by one step, returns true if new timestamps
+    /// should be written this frame
+    fn begin_frame(&mut self, encoder: &mut wgpu::CommandEncoder) -> bool {
+        match self.state {
+            TimerState::Idle => {
+                self.state = TimerState::Written;
+                return true;
+            }
+            TimerState::Written => {
+                encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+                encoder.copy_buffer_to_buffer(
+                    &self.resolve_buffer,
+                    0,
+                    &self.readback_buffer,
+                    0,
+                    self.resolve_buffer.size(),
+                );
+                self.state = TimerState::Copied;
+            }
+            // the buffer can only be mapped once the copy into it has been submitted, which is after prepare
+            TimerState::Copied => {
+                let map_result = self.map_result.clone();
+                self.readback_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        *map_result.lock().unwrap() = Some(result);
                     });
+                self.state = TimerState::Mapping;
             }
+            TimerState::Mapping => {
+                let Some(result) = self.map_result.lock().unwrap().take() else {
+                    return false;
+                };
+                if result.is_ok() {
+                    let timestamps: Vec<u64> = self
+                        .readback_buffer
+                        .slice(..)
+                        .get_mapped_range()
+                        .chunks_exact(8)
+                        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                        .collect();
+                    self.readback_buffer.unmap();
+                    let ticks = timestamps[1].saturating_sub(timestamps[0]);
+                    self.samples.push_back(Duration::from_nanos(
+                        (ticks as f64 * self.period as f64) as u64,
+                    ));
+                    while self.samples.len() > GPU_TIME_SAMPLES {
+                        self.samples.pop_front();
+                    }
+                }
+                self.state = TimerState::Idle;
+            }
+        }
+        false
+    }
+}
+
+fn uniform_buffer(device: &wgpu::Device, label: &str, size: NonZeroU64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: size.get(),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        mapped_at_creation: false,
+    })
+}
+
+fn uniform_layout_entry(
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    min_size: NonZeroU64,
+) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: Some(min_size),
+        },
+        count: None,
+    }
+}
 
-            queue.write_buffer(&render_state.circle_buffer, 0, &circle_buffer);
+/// A layout with a uniform buffer at each binding in order, each seen by the given stages
+fn uniform_bind_group_layout(
+    device: &wgpu::Device,
+    label: &str,
+    uniforms: &[(wgpu::ShaderStages, NonZeroU64)],
+) -> wgpu::BindGroupLayout {
+    let entries: Vec<_> = (0..)
+        .zip(uniforms)
+        .map(|(binding, &(visibility, min_size))| {
+            uniform_layout_entry(binding, visibility, min_size)
+        })
+        .collect();
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &entries,
+    })
+}
+
+/// A layout with a filterable texture at binding 0 and its sampler at binding 1, followed by a
+/// uniform buffer for the fragment shader at each binding after that
+fn texture_bind_group_layout(
+    device: &wgpu::Device,
+    label: &str,
+    uniform_sizes: &[NonZeroU64],
+) -> wgpu::BindGroupLayout {
+    let mut entries = vec![
+        wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ];
+    entries.extend((2..).zip(uniform_sizes).map(|(binding, &min_size)| {
+        uniform_layout_entry(binding, wgpu::ShaderStages::FRAGMENT, min_size)
+    }));
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &entries,
+    })
+}
+
+/// A bind group for a [`uniform_bind_group_layout`] with each buffer at the binding of its index
+fn uniform_bind_group(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::BindGroupLayout,
+    buffers: &[&wgpu::Buffer],
+) -> wgpu::BindGroup {
+    let entries: Vec<_> = (0..)
+        .zip(buffers)
+        .map(|(binding, buffer)| wgpu::BindGroupEntry {
+            binding,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &entries,
+    })
+}
+
+fn pipeline_layout(
+    device: &wgpu::Device,
+    name: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> wgpu::PipelineLayout {
+    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{name} Pipeline Layout")),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    })
+}
+
+/// `format` if the device can filter it and blend into it, otherwise `fallback`
+fn blendable_format(
+    adapter: &wgpu::Adapter,
+    format: wgpu::TextureFormat,
+    fallback: wgpu::TextureFormat,
+) -> wgpu::TextureFormat {
+    let features = adapter.get_texture_format_features(format);
+    if features
+        .allowed_usages
+        .contains(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+        && features.flags.contains(
+            wgpu::TextureFormatFeatureFlags::FILTERABLE
+                | wgpu::TextureFormatFeatureFlags::BLENDABLE,
+        )
+    {
+        format
+    } else {
+        fallback
+    }
+}
+
+/// Every shape type has one storage buffer holding all of its instances, and is drawn with a single
+/// instanced draw of a 4 vertex quad whose corners are expanded in the vertex shader, so the number of
+/// draw calls doesnt depend on the number of shapes
+///
+/// The buffers are kept between frames and only recreated when the shapes no longer fit
+pub struct Renderer {
+    target_format: wgpu::TextureFormat,
+    /// The srgb version of the target format, which every shape is drawn in
+    ///
+    /// Blending happens on whatever is stored in the texture, so with the non srgb formats egui prefers the
+    /// shaders would have to write encoded colors and overlapping translucent shapes would blend in srgb space,
+    /// which makes the midtones come out too dark. A texture in this format decodes to linear for blending and
+    /// encodes the result when writing instead, and the blit into the target encodes it again when the target
+    /// itself doesnt, so blending is always done on linear colors
+    shape_format: wgpu::TextureFormat,
+    /// The sample counts the device can render the shape format with, always including 1
+    supported_sample_counts: Vec<u32>,
+    /// The sample count the shape pipelines were made for
+    sample_count: u32,
+    /// `None` while the shapes are drawn straight into the egui render pass
+    shape_target: Option<ShapeTarget>,
+    /// `None` if the device doesnt have timestamp queries
+    timer: Option<GpuTimer>,
+    /// The last few times from the start of a frame until it was prepared to be presented, see [`presentation_latency`]
+    latency_samples: VecDeque<Duration>,
+    blit_render_pipeline: wgpu::RenderPipeline,
+    blit_bind_group_layout: wgpu::BindGroupLayout,
+    blit_sampler: wgpu::Sampler,
+    /// Rgba16Float when the device can blend into it so the glow can go above 1, otherwise Rgba8Unorm
+    bloom_format: wgpu::TextureFormat,
+    /// `None` while bloom is off
+    bloom_target: Option<BloomTarget>,
+    bloom_bind_group_layout: wgpu::BindGroupLayout,
+    bloom_sampler: wgpu::Sampler,
+    bloom_buffer: wgpu::Buffer,
+    emissive_render_pipeline: wgpu::RenderPipeline,
+    blur_horizontal_render_pipeline: wgpu::RenderPipeline,
+    blur_vertical_render_pipeline: wgpu::RenderPipeline,
+    bloom_composite_render_pipeline: wgpu::RenderPipeline,
+    density_format: wgpu::TextureFormat,
+    /// `None` while the density view is off
+    density_target: Option<DensityTarget>,
+    density_bind_group_layout: wgpu::BindGroupLayout,
+    density_sampler: wgpu::Sampler,
+    density_buffer: wgpu::Buffer,
+    /// Adds the coverage of each circle into the density target
+    density_accumulate_render_pipeline: wgpu::RenderPipeline,
+    /// Draws the density through the color ramp in place of the circles
+    density_render_pipeline: ShapePipeline,
+    camera_buffer: wgpu::Buffer,
+    output_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    grid_render_pipeline: ShapePipeline,
+    grid_buffer: wgpu::Buffer,
+    grid_bind_group: wgpu::BindGroup,
+    circle_render_pipeline: ShapePipeline,
+    /// Draws the circles for [`CircleQuality::Performance`]
+    fast_circle_render_pipeline: ShapePipeline,
+    circle_instances: InstanceBuffer,
+    rectangle_render_pipeline: ShapePipeline,
+    rectangle_instances: InstanceBuffer,
+    sprite_render_pipeline: ShapePipeline,
+    sprite_instances: InstanceBuffer,
+    /// The runs of consecutive sprites sharing a texture in the last uploaded sprites, in the order they were given
+    sprite_batches: Vec<(u32, Range<u32>)>,
+    /// The circles and rectangles on each layer in the last uploaded shapes, lowest layer first
+    layer_batches: Vec<(Range<u32>, Range<u32>)>,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    texture_sampler: wgpu::Sampler,
+    /// The bind group of every texture loaded with [`Renderer::load_texture`], by id
+    textures: HashMap<u32, wgpu::BindGroup>,
+    next_texture_id: u32,
+    ellipse_render_pipeline: ShapePipeline,
+    ellipse_instances: InstanceBuffer,
+    capsule_render_pipeline: ShapePipeline,
+    capsule_instances: InstanceBuffer,
+    ring_render_pipeline: ShapePipeline,
+    ring_instances: InstanceBuffer,
+    polygon_render_pipeline: ShapePipeline,
+    polygon_instances: InstanceBuffer,
+    line_render_pipeline: ShapePipeline,
+    line_instances: InstanceBuffer,
+}
+
+impl Renderer {
+    /// Remakes the shape pipelines for the highest supported sample count that isnt more than `requested`
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, requested: u32) {
+        let sample_count = self
+            .supported_sample_counts
+            .iter()
+            .copied()
+            .filter(|&count| count <= requested)
+            .max()
+            .unwrap_or(1);
+        if sample_count == self.sample_count {
+            return;
+        }
+
+        self.sample_count = sample_count;
+        for pipeline in [
+            &mut self.grid_render_pipeline,
+            &mut self.circle_render_pipeline,
+            &mut self.fast_circle_render_pipeline,
+            &mut self.density_render_pipeline,
+            &mut self.rectangle_render_pipeline,
+            &mut self.sprite_render_pipeline,
+            &mut self.ellipse_render_pipeline,
+            &mut self.capsule_render_pipeline,
+            &mut self.ring_render_pipeline,
+            &mut self.polygon_render_pipeline,
+            &mut self.line_render_pipeline,
+        ] {
+            pipeline.set_sample_count(device, self.shape_format, sample_count);
+        }
+    }
+
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target_format: wgpu::TextureFormat,
+    ) -> Renderer {
+        let shape_format = target_format.add_srgb_suffix();
+        // 1 and 4 always work, any other count needs the adapter specific format features to be enabled
+        let format_flags = adapter.get_texture_format_features(shape_format).flags;
+        let adapter_specific = device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        let supported_sample_counts = [1, 2, 4, 8]
+            .into_iter()
+            .filter(|&count| {
+                count == 1
+                    || format_flags.sample_count_supported(count)
+                        && (count == 4 || adapter_specific)
+            })
+            .collect();
+
+        let blit_bind_group_layout =
+            texture_bind_group_layout(device, "Blit Bind Group Layout", &[]);
+
+        let blit_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Blit Sampler"),
+            ..Default::default()
+        });
+
+        let blit_shader = device.create_shader_module(include_wgsl!("./blit_shader.wgsl"));
+
+        // the resolved shapes are premultiplied, and blend over the background egui has already drawn
+        let blit_render_pipeline = ShapePipeline::create_pipeline(
+            device,
+            "Blit",
+            &pipeline_layout(device, "Blit", &[&blit_bind_group_layout]),
+            &blit_shader,
+            // sampling the shape texture decodes it, so it has to be encoded again for targets that dont
+            if target_format.is_srgb() {
+                "pixel"
+            } else {
+                "pixel_encode_srgb"
+            },
+            target_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            1,
+        );
+
+        let camera_buffer = uniform_buffer(device, "Camera Buffer", GpuCamera::SHADER_SIZE);
+
+        // written by every upload as the edge softness can change
+        let output_buffer = uniform_buffer(device, "Output Buffer", GpuOutput::SHADER_SIZE);
+
+        let camera_bind_group_layout = uniform_bind_group_layout(
+            device,
+            "Camera Bind Group Layout",
+            &[
+                (wgpu::ShaderStages::VERTEX, GpuCamera::min_size()),
+                (wgpu::ShaderStages::FRAGMENT, GpuOutput::min_size()),
+            ],
+        );
+
+        let camera_bind_group = uniform_bind_group(
+            device,
+            "Camera Bind Group",
+            &camera_bind_group_layout,
+            &[&camera_buffer, &output_buffer],
+        );
+
+        let grid_buffer = uniform_buffer(device, "Grid Buffer", GpuGrid::SHADER_SIZE);
+
+        let grid_bind_group_layout = uniform_bind_group_layout(
+            device,
+            "Grid Bind Group Layout",
+            &[(wgpu::ShaderStages::FRAGMENT, GpuGrid::min_size())],
+        );
+
+        let grid_bind_group = uniform_bind_group(
+            device,
+            "Grid Bind Group",
+            &grid_bind_group_layout,
+            &[&grid_buffer],
+        );
+
+        // the lines are anti-aliased and the background has to show through between them
+        let grid_render_pipeline = ShapePipeline::with_layouts(
+            device,
+            "Grid",
+            &[&camera_bind_group_layout, &grid_bind_group_layout],
+            device.create_shader_module(include_wgsl!("./grid_shader.wgsl")),
+            shape_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let circle_instances = InstanceBuffer::new(device, "Circle", GpuCircles::min_size());
+
+        // made twice over for the two qualities, as pipelines take ownership of them
+        let create_circle_shader =
+            || device.create_shader_module(include_wgsl!("./circle_shader.wgsl"));
+        let create_circle_pipeline_layout = || {
+            pipeline_layout(
+                device,
+                "Circle",
+                &[
+                    &camera_bind_group_layout,
+                    &circle_instances.bind_group_layout,
+                ],
+            )
+        };
+        let circle_shader = create_circle_shader();
+        let circle_pipeline_layout = create_circle_pipeline_layout();
+
+        let bloom_format = blendable_format(
+            adapter,
+            wgpu::TextureFormat::Rgba16Float,
+            wgpu::TextureFormat::Rgba8Unorm,
+        );
+
+        let additive_blending = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::OVER,
+        };
+
+        // the glow of overlapping circles adds up, and it is always drawn without msaa as its blurred anyway
+        let emissive_render_pipeline = ShapePipeline::create_pipeline(
+            device,
+            "Emissive Circle",
+            &circle_pipeline_layout,
+            &circle_shader,
+            "emissive",
+            bloom_format,
+            Some(additive_blending),
+            1,
+        );
+
+        // half floats count well past 1, the bloom format is the fallback where they cant be blended into
+        let density_format = blendable_format(adapter, wgpu::TextureFormat::R16Float, bloom_format);
+
+        // every circle adds to whatever is already there, the alpha is unused
+        let density_accumulate_render_pipeline = ShapePipeline::create_pipeline(
+            device,
+            "Density Accumulate",
+            &circle_pipeline_layout,
+            &circle_shader,
+            "density",
+            density_format,
+            Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::REPLACE,
+            }),
+            1,
+        );
+
+        // the edges are anti-aliased so they need to be blended
+        let circle_render_pipeline = ShapePipeline::new(
+            device,
+            "Circle",
+            circle_pipeline_layout,
+            circle_shader,
+            shape_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+        // still blended for the circles own alpha
+        let fast_circle_render_pipeline = ShapePipeline::with_entry_point(
+            device,
+            "Fast Circle",
+            create_circle_pipeline_layout(),
+            create_circle_shader(),
+            "pixel_fast",
+            shape_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let bloom_buffer = uniform_buffer(device, "Bloom Buffer", GpuBloom::SHADER_SIZE);
+
+        let bloom_bind_group_layout = texture_bind_group_layout(
+            device,
+            "Bloom Bind Group Layout",
+            &[GpuBloom::min_size(), GpuOutput::min_size()],
+        );
+
+        // the half size glow is stretched back over the whole target so it has to be filtered
+        let bloom_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Bloom Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bloom_shader = device.create_shader_module(include_wgsl!("./bloom_shader.wgsl"));
+
+        let bloom_pipeline_layout = pipeline_layout(device, "Bloom", &[&bloom_bind_group_layout]);
+
+        let [blur_horizontal_render_pipeline, blur_vertical_render_pipeline] = [
+            ("Blur Horizontal", "blur_horizontal"),
+            ("Blur Vertical", "blur_vertical"),
+        ]
+        .map(|(name, entry_point)| {
+            ShapePipeline::create_pipeline(
+                device,
+                name,
+                &bloom_pipeline_layout,
+                &bloom_shader,
+                entry_point,
+                bloom_format,
+                None,
+                1,
+            )
+        });
+
+        // drawn into the egui pass or straight into the target after the shapes, neither is multisampled
+        let bloom_composite_render_pipeline = ShapePipeline::create_pipeline(
+            device,
+            "Bloom Composite",
+            &bloom_pipeline_layout,
+            &bloom_shader,
+            "composite",
+            shape_format,
+            Some(additive_blending),
+            1,
+        );
+
+        let density_buffer = uniform_buffer(device, "Density Buffer", GpuDensity::SHADER_SIZE);
+
+        let density_bind_group_layout = texture_bind_group_layout(
+            device,
+            "Density Bind Group Layout",
+            &[GpuDensity::min_size(), GpuOutput::min_size()],
+        );
+
+        // the density target is the same size as the shape target, so nearest is exact
+        let density_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Density Sampler"),
+            ..Default::default()
+        });
+
+        // drawn in the shape pass where the circles would be, so it has to match its sample count
+        let density_render_pipeline = ShapePipeline::with_layouts(
+            device,
+            "Density",
+            &[&density_bind_group_layout],
+            device.create_shader_module(include_wgsl!("./density_shader.wgsl")),
+            shape_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        // every other shape only needs the camera and its own instances
+        let instanced_pipeline = |name, instances: &InstanceBuffer, shader, blend| {
+            ShapePipeline::with_layouts(
+                device,
+                name,
+                &[&camera_bind_group_layout, &instances.bind_group_layout],
+                shader,
+                shape_format,
+                blend,
+            )
+        };
+
+        let rectangle_instances =
+            InstanceBuffer::new(device, "Rectangle", GpuRectangles::min_size());
+        let rectangle_render_pipeline = instanced_pipeline(
+            "Rectangle",
+            &rectangle_instances,
+            device.create_shader_module(include_wgsl!("./rectangle_shader.wgsl")),
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let sprite_instances = InstanceBuffer::new(device, "Sprite", GpuSprites::min_size());
+
+        let texture_bind_group_layout =
+            texture_bind_group_layout(device, "Texture Bind Group Layout", &[]);
+
+        let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Texture Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // images usually have transparent parts
+        let sprite_render_pipeline = ShapePipeline::with_layouts(
+            device,
+            "Sprite",
+            &[
+                &camera_bind_group_layout,
+                &sprite_instances.bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            device.create_shader_module(include_wgsl!("./sprite_shader.wgsl")),
+            shape_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let ellipse_instances = InstanceBuffer::new(device, "Ellipse", GpuEllipses::min_size());
+        // the edges are anti-aliased so they need to be blended
+        let ellipse_render_pipeline = instanced_pipeline(
+            "Ellipse",
+            &ellipse_instances,
+            device.create_shader_module(include_wgsl!("./ellipse_shader.wgsl")),
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let capsule_instances = InstanceBuffer::new(device, "Capsule", GpuCapsules::min_size());
+        let capsule_render_pipeline = instanced_pipeline(
+            "Capsule",
+            &capsule_instances,
+            device.create_shader_module(include_wgsl!("./capsule_shader.wgsl")),
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let ring_instances = InstanceBuffer::new(device, "Ring", GpuRings::min_size());
+        let ring_render_pipeline = instanced_pipeline(
+            "Ring",
+            &ring_instances,
+            device.create_shader_module(include_wgsl!("./ring_shader.wgsl")),
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let polygon_instances = InstanceBuffer::new(device, "Polygon", GpuPolygons::min_size());
+        let polygon_render_pipeline = instanced_pipeline(
+            "Polygon",
+            &polygon_instances,
+            device.create_shader_module(include_wgsl!("./polygon_shader.wgsl")),
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+
+        let line_instances = InstanceBuffer::new(device, "Line", GpuLines::min_size());
+        let line_render_pipeline = instanced_pipeline(
+            "Line",
+            &line_instances,
+            device.create_shader_module(include_wgsl!("./line_shader.wgsl")),
+            None,
+        );
+
+        Renderer {
+            target_format,
+            shape_format,
+            supported_sample_counts,
+            sample_count: 1,
+            shape_target: None,
+            timer: GpuTimer::new(device, queue),
+            latency_samples: VecDeque::new(),
+            blit_render_pipeline,
+            blit_bind_group_layout,
+            blit_sampler,
+            bloom_format,
+            bloom_target: None,
+            bloom_bind_group_layout,
+            bloom_sampler,
+            bloom_buffer,
+            emissive_render_pipeline,
+            blur_horizontal_render_pipeline,
+            blur_vertical_render_pipeline,
+            bloom_composite_render_pipeline,
+            density_format,
+            density_target: None,
+            density_bind_group_layout,
+            density_sampler,
+            density_buffer,
+            density_accumulate_render_pipeline,
+            density_render_pipeline,
+            camera_buffer,
+            output_buffer,
+            camera_bind_group,
+            grid_render_pipeline,
+            grid_buffer,
+            grid_bind_group,
+            circle_render_pipeline,
+            fast_circle_render_pipeline,
+            circle_instances,
+            rectangle_render_pipeline,
+            rectangle_instances,
+            sprite_render_pipeline,
+            sprite_instances,
+            sprite_batches: Vec::new(),
+            layer_batches: Vec::new(),
+            texture_bind_group_layout,
+            texture_sampler,
+            textures: HashMap::new(),
+            next_texture_id: 0,
+            ellipse_render_pipeline,
+            ellipse_instances,
+            capsule_render_pipeline,
+            capsule_instances,
+            ring_render_pipeline,
+            ring_instances,
+            polygon_render_pipeline,
+            polygon_instances,
+            line_render_pipeline,
+            line_instances,
+        }
+    }
+}
+
+pub fn create_render_state(cc: &eframe::CreationContext) {
+    let egui_wgpu::RenderState {
+        ref adapter,
+        ref device,
+        ref queue,
+        target_format,
+        ref renderer,
+        ..
+    } = *cc.wgpu_render_state.as_ref().unwrap();
+    renderer.write().callback_resources.insert(Renderer::new(
+        adapter,
+        device,
+        queue,
+        target_format,
+    ));
+}
+
+/// The shapes in draw order, only copied when they arent already sorted which is the usual case
+fn sorted_by_key<T: Clone, K: Ord>(shapes: &[T], key: impl Fn(&T) -> K) -> Cow<'_, [T]> {
+    if shapes.windows(2).all(|pair| key(&pair[0]) <= key(&pair[1])) {
+        Cow::Borrowed(shapes)
+    } else {
+        let mut shapes = shapes.to_vec();
+        // stable so shapes on the same layer keep their order
+        shapes.sort_by_key(|shape| key(shape));
+        Cow::Owned(shapes)
+    }
+}
+
+/// Splits circles and rectangles that are already sorted by layer into the range of each on every layer, lowest
+/// layer first, so each layer can be drawn in turn with its circles and then its rectangles
+fn layer_batches(
+    circles: &[GpuCircle],
+    rectangles: &[GpuRectangle],
+) -> Vec<(Range<u32>, Range<u32>)> {
+    let mut batches = Vec::new();
+    let (mut circle, mut rectangle) = (0, 0);
+    while circle < circles.len() || rectangle < rectangles.len() {
+        let layer = match (circles.get(circle), rectangles.get(rectangle)) {
+            (Some(c), Some(r)) => c.layer.min(r.layer),
+            (Some(c), None) => c.layer,
+            (None, Some(r)) => r.layer,
+            (None, None) => unreachable!(),
+        };
+        let circle_end = circle + circles[circle..].partition_point(|c| c.layer <= layer);
+        let rectangle_end =
+            rectangle + rectangles[rectangle..].partition_point(|r| r.layer <= layer);
+        batches.push((
+            circle as u32..circle_end as u32,
+            rectangle as u32..rectangle_end as u32,
+        ));
+        (circle, rectangle) = (circle_end, rectangle_end);
+    }
+    batches
+}
+
+/// Splits sprites into runs of consecutive sprites with the same texture, each drawn with one draw call, without
+/// reordering them so sprites later in the list are still drawn over earlier ones
+fn sprite_batches(sprites: &[GpuSprite]) -> Vec<(u32, Range<u32>)> {
+    let mut batches: Vec<(u32, Range<u32>)> = Vec::new();
+    for (index, sprite) in sprites.iter().enumerate() {
+        let index = index as u32;
+        match batches.last_mut() {
+            Some((texture_id, range)) if *texture_id == sprite.texture_id => {
+                range.end = index + 1;
+            }
+            _ => batches.push((sprite.texture_id, index..index + 1)),
+        }
+    }
+    batches
+}
+
+/// Everything drawn in one frame, in draw order
+#[derive(Clone)]
+pub struct Shapes {
+    pub camera: GpuCamera,
+    /// Drawn before every shape when set
+    pub grid: Option<GpuGrid>,
+    pub circles: Vec<GpuCircle>,
+    pub rectangles: Vec<GpuRectangle>,
+    pub sprites: Vec<GpuSprite>,
+    pub ellipses: Vec<GpuEllipse>,
+    pub capsules: Vec<GpuCapsule>,
+    pub rings: Vec<GpuRing>,
+    pub polygons: Vec<GpuPolygon>,
+    pub lines: Vec<GpuLine>,
+    /// Adds the blurred glow of the emissive circles on top of everything when set
+    pub bloom: Option<GpuBloom>,
+    /// Draws a heatmap of the circles in their place when set
+    pub density: Option<GpuDensity>,
+    /// How many pixels wide the anti-aliased edge of the circles is, 1 is the usual look
+    pub edge_softness: f32,
+    pub circle_quality: CircleQuality,
+}
+
+impl Shapes {
+    /// Nothing to draw yet, with no grid, bloom or density and the usual edge softness
+    pub fn new(camera: GpuCamera) -> Shapes {
+        Shapes {
+            camera,
+            grid: None,
+            circles: Vec::new(),
+            rectangles: Vec::new(),
+            sprites: Vec::new(),
+            ellipses: Vec::new(),
+            capsules: Vec::new(),
+            rings: Vec::new(),
+            polygons: Vec::new(),
+            lines: Vec::new(),
+            bloom: None,
+            density: None,
+            edge_softness: 1.0,
+            circle_quality: CircleQuality::Quality,
+        }
+    }
+}
+
+impl Renderer {
+    /// Writes the camera and every shape into their buffers
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, shapes: &Shapes) {
+        {
+            let camera = &shapes.camera;
+            debug_assert!(
+                camera.position.x.is_finite()
+                    && camera.position.y.is_finite()
+                    && camera.aspect.is_finite()
+                    && camera.zoom.is_finite()
+                    && camera.rotation.is_finite(),
+                "camera isnt finite, position: {:?}, aspect: {}, zoom: {}, rotation: {}",
+                camera.position,
+                camera.aspect,
+                camera.zoom,
+                camera.rotation,
+            );
+            let mut camera_buffer = UniformBuffer::new([0u8; GpuCamera::SHADER_SIZE.get() as _]);
+            camera_buffer.write(&shapes.camera).unwrap();
+            queue.write_buffer(&self.camera_buffer, 0, &camera_buffer.into_inner());
+        }
+
+        {
+            let mut output_buffer = UniformBuffer::new([0u8; GpuOutput::SHADER_SIZE.get() as _]);
+            // the shapes are given linear colors, they only have to be encoded by the shader when the shape
+            // format doesnt do it when writing, which is only the case for formats with no srgb version
+            output_buffer
+                .write(&GpuOutput {
+                    encode_srgb: !self.shape_format.is_srgb() as u32,
+                    edge_softness: shapes.edge_softness,
+                })
+                .unwrap();
+            queue.write_buffer(&self.output_buffer, 0, &output_buffer.into_inner());
+        }
+
+        if let Some(grid) = &shapes.grid {
+            let mut grid_buffer = UniformBuffer::new([0u8; GpuGrid::SHADER_SIZE.get() as _]);
+            grid_buffer.write(grid).unwrap();
+            queue.write_buffer(&self.grid_buffer, 0, &grid_buffer.into_inner());
+        }
+
+        if let Some(density) = &shapes.density {
+            let mut density_buffer = UniformBuffer::new([0u8; GpuDensity::SHADER_SIZE.get() as _]);
+            density_buffer.write(density).unwrap();
+            queue.write_buffer(&self.density_buffer, 0, &density_buffer.into_inner());
+        }
+
+        if let Some(bloom) = &shapes.bloom {
+            let mut bloom_buffer = UniformBuffer::new([0u8; GpuBloom::SHADER_SIZE.get() as _]);
+            bloom_buffer.write(bloom).unwrap();
+            queue.write_buffer(&self.bloom_buffer, 0, &bloom_buffer.into_inner());
         }
 
         {
+            let circles = sorted_by_key(&shapes.circles, |circle| circle.layer);
+            let rectangles = sorted_by_key(&shapes.rectangles, |rectangle| rectangle.layer);
+            self.layer_batches = layer_batches(&circles, &rectangles);
+
+            let mut circle_buffer = StorageBuffer::new(vec![]);
+            circle_buffer
+                .write(&GpuCircles { circles: &circles })
+                .unwrap();
+            self.circle_instances
+                .write(device, queue, &circle_buffer.into_inner());
+
             let mut rectangle_buffer = StorageBuffer::new(vec![]);
             rectangle_buffer
                 .write(&GpuRectangles {
-                    rectangles: &self.rectangles,
+                    rectangles: &rectangles,
+                })
+                .unwrap();
+            self.rectangle_instances
+                .write(device, queue, &rectangle_buffer.into_inner());
+        }
+
+        {
+            // kept in the order they were given so overlapping sprites stay on top of each other the same way
+            let sprites = &shapes.sprites;
+            self.sprite_batches = sprite_batches(sprites);
+            let mut sprite_buffer = StorageBuffer::new(vec![]);
+            sprite_buffer.write(&GpuSprites { sprites }).unwrap();
+            self.sprite_instances
+                .write(device, queue, &sprite_buffer.into_inner());
+        }
+
+        {
+            let mut ellipse_buffer = StorageBuffer::new(vec![]);
+            ellipse_buffer
+                .write(&GpuEllipses {
+                    ellipses: &shapes.ellipses,
+                })
+                .unwrap();
+            self.ellipse_instances
+                .write(device, queue, &ellipse_buffer.into_inner());
+        }
+
+        {
+            let mut capsule_buffer = StorageBuffer::new(vec![]);
+            capsule_buffer
+                .write(&GpuCapsules {
+                    capsules: &shapes.capsules,
+                })
+                .unwrap();
+            self.capsule_instances
+                .write(device, queue, &capsule_buffer.into_inner());
+        }
+
+        {
+            let mut ring_buffer = StorageBuffer::new(vec![]);
+            ring_buffer
+                .write(&GpuRings {
+                    rings: &shapes.rings,
+                })
+                .unwrap();
+            self.ring_instances
+                .write(device, queue, &ring_buffer.into_inner());
+        }
+
+        {
+            let mut polygon_buffer = StorageBuffer::new(vec![]);
+            polygon_buffer
+                .write(&GpuPolygons {
+                    polygons: &shapes.polygons,
+                })
+                .unwrap();
+            self.polygon_instances
+                .write(device, queue, &polygon_buffer.into_inner());
+        }
+
+        {
+            let mut line_buffer = StorageBuffer::new(vec![]);
+            line_buffer
+                .write(&GpuLines {
+                    lines: &shapes.lines,
                 })
                 .unwrap();
-            let rectangle_buffer = rectangle_buffer.into_inner();
+            self.line_instances
+                .write(device, queue, &line_buffer.into_inner());
+        }
+    }
 
-            if rectangle_buffer.len() as wgpu::BufferAddress > render_state.rectangle_buffer_size {
-                render_state.rectangle_buffer_size = rectangle_buffer.len() as _;
+    /// Draws the shapes last passed to [`Renderer::upload`] into a pass made for the current sample count
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, shapes: &Shapes) {
+        if shapes.grid.is_some() {
+            render_pass.set_pipeline(&self.grid_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.grid_bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
 
-                render_state.rectangle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("Rectangle Buffer"),
-                    size: render_state.rectangle_buffer_size,
-                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
-                    mapped_at_creation: false,
+        // the heatmap stands in for the circles on every layer, so it goes under all of the rectangles
+        let density_target = self
+            .density_target
+            .as_ref()
+            .filter(|_| shapes.density.is_some());
+        if let Some(density_target) = density_target {
+            render_pass.set_pipeline(&self.density_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &density_target.bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+
+        // a layer at a time so a circle on a higher layer goes over a rectangle on a lower one and the other way round
+        for (circles, rectangles) in &self.layer_batches {
+            if !circles.is_empty() && density_target.is_none() {
+                render_pass.set_pipeline(match shapes.circle_quality {
+                    CircleQuality::Quality => &self.circle_render_pipeline.pipeline,
+                    CircleQuality::Performance => &self.fast_circle_render_pipeline.pipeline,
                 });
+                render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                render_pass.set_bind_group(1, &self.circle_instances.bind_group, &[]);
+                render_pass.draw(0..4, circles.clone());
+            }
+            if !rectangles.is_empty() {
+                render_pass.set_pipeline(&self.rectangle_render_pipeline.pipeline);
+                render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+                render_pass.set_bind_group(1, &self.rectangle_instances.bind_group, &[]);
+                render_pass.draw(0..4, rectangles.clone());
+            }
+        }
 
-                render_state.rectangle_bind_group =
-                    device.create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: Some("Rectangle Bind Group"),
-                        layout: &render_state.rectangle_bind_group_layout,
-                        entries: &[wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: render_state.rectangle_buffer.as_entire_binding(),
-                        }],
-                    });
+        if !shapes.sprites.is_empty() {
+            render_pass.set_pipeline(&self.sprite_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.sprite_instances.bind_group, &[]);
+            for (texture_id, range) in &self.sprite_batches {
+                if let Some(texture) = self.textures.get(texture_id) {
+                    render_pass.set_bind_group(2, texture, &[]);
+                    render_pass.draw(0..4, range.clone());
+                }
             }
+        }
 
-            queue.write_buffer(&render_state.rectangle_buffer, 0, &rectangle_buffer);
+        if !shapes.ellipses.is_empty() {
+            render_pass.set_pipeline(&self.ellipse_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.ellipse_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.ellipses.len() as _);
+        }
+
+        if !shapes.capsules.is_empty() {
+            render_pass.set_pipeline(&self.capsule_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.capsule_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.capsules.len() as _);
+        }
+
+        if !shapes.rings.is_empty() {
+            render_pass.set_pipeline(&self.ring_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.ring_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.rings.len() as _);
+        }
+
+        if !shapes.polygons.is_empty() {
+            render_pass.set_pipeline(&self.polygon_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.polygon_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.polygons.len() as _);
+        }
+
+        if !shapes.lines.is_empty() {
+            render_pass.set_pipeline(&self.line_render_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.line_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.lines.len() as _);
+        }
+    }
+
+    /// Uploads an image for sprites to use, returning the id to put in [`GpuSprite::texture_id`]
+    pub fn load_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::RgbaImage,
+        alpha_mode: AlphaMode,
+    ) -> u32 {
+        let mut image = image.clone();
+        // premultiplied in linear space, as that is what the srgb texture is filtered in
+        if alpha_mode == AlphaMode::Straight {
+            for pixel in image.pixels_mut() {
+                let alpha = pixel[3] as f32 / 255.0;
+                for channel in &mut pixel.0[..3] {
+                    let linear = color::decode_srgb(*channel as f32 / 255.0) * alpha;
+                    *channel = (color::encode_srgb(linear) * 255.0).round() as u8;
+                }
+            }
+        }
+
+        let size = wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sprite Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &image,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(image.width() * 4),
+                rows_per_image: None,
+            },
+            size,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Texture Bind Group"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                },
+            ],
+        });
+
+        let id = self.next_texture_id;
+        self.next_texture_id += 1;
+        self.textures.insert(id, bind_group);
+        id
+    }
+
+    /// Draws the glow of the circles last passed to [`Renderer::upload`] into the bloom target and blurs it,
+    /// `width` and `height` are the size of the target it will be composited into
+    fn render_bloom(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        width: u32,
+        height: u32,
+        shapes: &Shapes,
+    ) {
+        let [width, height] = [width, height].map(|size| (size / 2).max(1));
+        let up_to_date = self
+            .bloom_target
+            .as_ref()
+            .is_some_and(|target| (target.width, target.height) == (width, height));
+        if !up_to_date {
+            self.bloom_target = Some(BloomTarget::new(
+                device,
+                self.bloom_format,
+                &self.bloom_bind_group_layout,
+                &self.bloom_sampler,
+                &self.bloom_buffer,
+                &self.output_buffer,
+                width,
+                height,
+            ));
+        }
+        let bloom_target = self.bloom_target.as_ref().unwrap();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Emissive Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &bloom_target.views[0],
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        if !shapes.circles.is_empty() {
+            render_pass.set_pipeline(&self.emissive_render_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.circle_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.circles.len() as _);
+        }
+        drop(render_pass);
+
+        // blurring each direction separately takes 2 passes of a few taps instead of one pass of the square
+        for (pipeline, source, destination) in [
+            (&self.blur_horizontal_render_pipeline, 0, 1),
+            (&self.blur_vertical_render_pipeline, 1, 0),
+        ] {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Blur Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &bloom_target.views[destination],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bloom_target.bind_groups[source], &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+
+    /// Adds up how much of each pixel the circles last passed to [`Renderer::upload`] cover in the density target,
+    /// `width` and `height` are the size of the target the density will be drawn into
+    fn render_density(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        width: u32,
+        height: u32,
+        shapes: &Shapes,
+    ) {
+        let [width, height] = [width, height].map(|size| size.max(1));
+        let up_to_date = self
+            .density_target
+            .as_ref()
+            .is_some_and(|target| (target.width, target.height) == (width, height));
+        if !up_to_date {
+            self.density_target = Some(DensityTarget::new(
+                device,
+                self.density_format,
+                &self.density_bind_group_layout,
+                &self.density_sampler,
+                &self.density_buffer,
+                &self.output_buffer,
+                width,
+                height,
+            ));
+        }
+        let density_target = self.density_target.as_ref().unwrap();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Density Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &density_target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        if !shapes.circles.is_empty() {
+            render_pass.set_pipeline(&self.density_accumulate_render_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.circle_instances.bind_group, &[]);
+            render_pass.draw(0..4, 0..shapes.circles.len() as _);
+        }
+    }
+
+    /// Adds the glow from the last [`Renderer::render_bloom`] over the whole viewport of `render_pass`
+    fn composite_bloom<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let Some(bloom_target) = &self.bloom_target {
+            render_pass.set_pipeline(&self.bloom_composite_render_pipeline);
+            render_pass.set_bind_group(0, &bloom_target.bind_groups[0], &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+
+    /// Uploads and draws the shapes into `target` in a pass of its own, which has to be in the shape format,
+    /// `clear_color` is linear like the shape colors
+    ///
+    /// With msaa the shapes are drawn into a temporary multisampled texture that is resolved into `target`
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::Texture,
+        clear_color: wgpu::Color,
+        shapes: &Shapes,
+    ) {
+        self.upload(device, queue, shapes);
+
+        // the clear color is written as is, so it has to be encoded the same way the shaders encode colors
+        let clear_color = if self.shape_format.is_srgb() {
+            clear_color
+        } else {
+            let encode = |channel: f64| color::encode_srgb(channel as f32) as f64;
+            wgpu::Color {
+                r: encode(clear_color.r),
+                g: encode(clear_color.g),
+                b: encode(clear_color.b),
+                a: clear_color.a,
+            }
+        };
+
+        if shapes.density.is_some() {
+            let size = target.size();
+            self.render_density(device, encoder, size.width, size.height, shapes);
+        }
+
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let multisampled_view = (self.sample_count > 1).then(|| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Offscreen Multisampled Texture"),
+                    size: target.size(),
+                    mip_level_count: 1,
+                    sample_count: self.sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.shape_format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Offscreen Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: multisampled_view.as_ref().unwrap_or(&view),
+                resolve_target: multisampled_view.is_some().then_some(&view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        self.draw(&mut render_pass, shapes);
+        drop(render_pass);
+
+        if shapes.bloom.is_some() {
+            let size = target.size();
+            self.render_bloom(device, encoder, size.width, size.height, shapes);
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Offscreen Bloom Composite Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            self.composite_bloom(&mut render_pass);
+        }
+    }
+
+    /// Renders the shapes into a new texture of the given size and reads it back, blocking until its done
+    fn render_to_image(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shapes: &Shapes,
+        clear_color: wgpu::Color,
+        width: u32,
+        height: u32,
+    ) -> Result<image::RgbaImage, ReadbackError> {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Export Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // srgb so the shapes blend in linear space, the bytes read back are the same encoded colors either way
+            format: self.shape_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        // rows in the copy have to be padded to the alignment, so the padding is skipped when reading back
+        let unpadded_bytes_per_row = width * 4;
+        let bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Export Readback Buffer"),
+            size: bytes_per_row as wgpu::BufferAddress * height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Export Command Encoder"),
+        });
+        self.render(device, queue, &mut encoder, &texture, clear_color, shapes);
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let readback_slice = readback_buffer.slice(..);
+        readback_slice.map_async(wgpu::MapMode::Read, move |result| {
+            _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        // on the web polling doesnt block, so the buffer may not be mapped yet
+        receiver
+            .try_recv()
+            .map_err(|_| ReadbackError)?
+            .map_err(|_| ReadbackError)?;
+
+        let swap_red_and_blue = matches!(
+            self.target_format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in readback_slice
+            .get_mapped_range()
+            .chunks_exact(bytes_per_row as usize)
+        {
+            for pixel in row[..unpadded_bytes_per_row as usize].chunks_exact(4) {
+                if swap_red_and_blue {
+                    pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                } else {
+                    pixels.extend_from_slice(pixel);
+                }
+            }
+        }
+        readback_buffer.unmap();
+
+        Ok(image::RgbaImage::from_raw(width, height, pixels).unwrap())
+    }
+}
+
+/// Draws the shapes into the egui panel they were added to, all of the drawing is done by the [`Renderer`]
+/// in the callback resources
+pub struct RenderCallback {
+    /// Shared so the app can take the vectors back to fill again once the callback is dropped
+    pub shapes: Arc<Shapes>,
+    /// The msaa sample count to draw with, the closest supported count below it is used
+    pub sample_count: u32,
+    /// The size in physical pixels of the area being painted, the shape textures are made this size
+    pub target_size: [u32; 2],
+    /// Linear like the shape colors, the shape texture is cleared to this so the edges of the shapes blend
+    /// with it in linear space too, it only shows when the shapes need a texture of their own
+    pub clear_color: wgpu::Color,
+    /// When the frame being drawn started, to measure the latency from, `None` for drawings that arent shown
+    pub frame_start: Option<web_time::Instant>,
+}
+
+impl CallbackTrait for RenderCallback {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        egui_encoder: &mut wgpu::CommandEncoder,
+        callback_resources: &mut eframe::egui_wgpu::CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let renderer: &mut Renderer = callback_resources.get_mut().unwrap();
+        if let Some(frame_start) = self.frame_start {
+            renderer.latency_samples.push_back(frame_start.elapsed());
+            while renderer.latency_samples.len() > GPU_TIME_SAMPLES {
+                renderer.latency_samples.pop_front();
+            }
+        }
+        renderer.set_sample_count(device, self.sample_count);
+        renderer.upload(device, queue, &self.shapes);
+
+        // blending straight into an srgb target is already linear, so then a texture is only needed for msaa
+        let offscreen =
+            renderer.sample_count > 1 || renderer.shape_format != renderer.target_format;
+        let timing = match &mut renderer.timer {
+            Some(timer) if offscreen || timer.inside_passes => timer.begin_frame(egui_encoder),
+            _ => false,
+        };
+
+        // before the shapes, as the glow is added to the shape texture right after them when there is one
+        if self.shapes.bloom.is_some() {
+            let [width, height] = self.target_size;
+            renderer.render_bloom(device, egui_encoder, width, height, &self.shapes);
+        } else {
+            renderer.bloom_target = None;
+        }
+        if self.shapes.density.is_some() {
+            let [width, height] = self.target_size;
+            renderer.render_density(device, egui_encoder, width, height, &self.shapes);
+        } else {
+            renderer.density_target = None;
+        }
+
+        if offscreen {
+            let [width, height] = self.target_size.map(|size| size.max(1));
+            let up_to_date = renderer.shape_target.as_ref().is_some_and(|target| {
+                (target.width, target.height, target.sample_count)
+                    == (width, height, renderer.sample_count)
+            });
+            if !up_to_date {
+                renderer.shape_target = Some(ShapeTarget::new(
+                    device,
+                    renderer.shape_format,
+                    &renderer.blit_bind_group_layout,
+                    &renderer.blit_sampler,
+                    width,
+                    height,
+                    renderer.sample_count,
+                ));
+            }
+
+            let renderer = &*renderer;
+            let shape_target = renderer.shape_target.as_ref().unwrap();
+            if timing {
+                egui_encoder.write_timestamp(&renderer.timer.as_ref().unwrap().query_set, 0);
+            }
+            let multisampled = shape_target.multisampled_view.is_some();
+            let mut render_pass = egui_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shape Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: shape_target
+                        .multisampled_view
+                        .as_ref()
+                        .unwrap_or(&shape_target.view),
+                    resolve_target: multisampled.then_some(&shape_target.view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        // with msaa only the resolved texture is needed afterwards
+                        store: !multisampled,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            renderer.draw(&mut render_pass, &self.shapes);
+            drop(render_pass);
+            if timing {
+                egui_encoder.write_timestamp(&renderer.timer.as_ref().unwrap().query_set, 1);
+            }
+
+            if self.shapes.bloom.is_some() {
+                let mut render_pass = egui_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Bloom Composite Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &shape_target.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                renderer.composite_bloom(&mut render_pass);
+            }
+        } else {
+            renderer.shape_target = None;
         }
 
         Vec::new()
@@ -351,16 +2650,394 @@ impl CallbackTrait for RenderCallback {
         render_pass: &mut wgpu::RenderPass<'a>,
         callback_resources: &'a eframe::egui_wgpu::CallbackResources,
     ) {
-        let render_state: &RenderState = callback_resources.get().unwrap();
+        let renderer: &Renderer = callback_resources.get().unwrap();
+        if let Some(shape_target) = &renderer.shape_target {
+            // the shapes and their glow were already drawn in prepare
+            render_pass.set_pipeline(&renderer.blit_render_pipeline);
+            render_pass.set_bind_group(0, &shape_target.bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        } else {
+            let timer = renderer
+                .timer
+                .as_ref()
+                .filter(|timer| timer.state == TimerState::Written);
+            if let Some(timer) = timer {
+                render_pass.write_timestamp(&timer.query_set, 0);
+            }
+            renderer.draw(render_pass, &self.shapes);
+            if let Some(timer) = timer {
+                render_pass.write_timestamp(&timer.query_set, 1);
+            }
+            if self.shapes.bloom.is_some() {
+                renderer.composite_bloom(render_pass);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReadbackError;
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to read the rendered image back from the gpu")
+    }
+}
+
+impl std::error::Error for ReadbackError {}
+
+/// The name and capacity in instances of each shape's instance buffer, to check they arent being reallocated all the time
+pub fn instance_buffer_capacities(
+    render_state: &egui_wgpu::RenderState,
+) -> Vec<(&'static str, wgpu::BufferAddress)> {
+    let egui_renderer = render_state.renderer.read();
+    let renderer: &Renderer = egui_renderer.callback_resources.get().unwrap();
+    [
+        &renderer.circle_instances,
+        &renderer.rectangle_instances,
+        &renderer.sprite_instances,
+        &renderer.ellipse_instances,
+        &renderer.capsule_instances,
+        &renderer.ring_instances,
+        &renderer.polygon_instances,
+        &renderer.line_instances,
+    ]
+    .into_iter()
+    .map(|instances| (instances.name, instances.capacity()))
+    .collect()
+}
+
+pub enum GpuTime {
+    /// The device doesnt have the timestamp queries needed to time the current way of drawing
+    Unsupported,
+    /// No timestamps have been read back yet
+    Measuring,
+    Average(Duration),
+}
+
+/// How long drawing the shapes took on the gpu, averaged over the last few measurements
+pub fn gpu_time(render_state: &egui_wgpu::RenderState) -> GpuTime {
+    let egui_renderer = render_state.renderer.read();
+    let renderer: &Renderer = egui_renderer.callback_resources.get().unwrap();
+    match &renderer.timer {
+        Some(timer) if renderer.sample_count > 1 || timer.inside_passes => {
+            if timer.samples.is_empty() {
+                GpuTime::Measuring
+            } else {
+                GpuTime::Average(
+                    timer.samples.iter().sum::<Duration>() / timer.samples.len() as u32,
+                )
+            }
+        }
+        _ => GpuTime::Unsupported,
+    }
+}
+
+/// About how long it takes from the start of a frame until it is presented, averaged over the last few frames
+///
+/// The presentation itself cant be timed, so this is measured on the cpu up to when the shapes are prepared,
+/// which is after egui has waited for a surface texture to draw into and right before the frame is submitted
+/// and presented, so it includes however long the present mode makes frames wait
+pub fn presentation_latency(render_state: &egui_wgpu::RenderState) -> Option<Duration> {
+    let egui_renderer = render_state.renderer.read();
+    let renderer: &Renderer = egui_renderer.callback_resources.get().unwrap();
+    let samples = &renderer.latency_samples;
+    (!samples.is_empty()).then(|| samples.iter().sum::<Duration>() / samples.len() as u32)
+}
+
+/// The device egui would create, plus the optional features the renderer uses when the adapter has them
+pub fn device_descriptor(adapter: &wgpu::Adapter) -> wgpu::DeviceDescriptor<'static> {
+    let mut descriptor = (egui_wgpu::WgpuConfiguration::default().device_descriptor)(adapter);
+    descriptor.features |= adapter.features()
+        & (wgpu::Features::TIMESTAMP_QUERY
+            | wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES
+            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    descriptor
+}
+
+/// The msaa sample counts that can be picked for [`RenderCallback::sample_count`]
+pub fn supported_sample_counts(render_state: &egui_wgpu::RenderState) -> Vec<u32> {
+    let egui_renderer = render_state.renderer.read();
+    let renderer: &Renderer = egui_renderer.callback_resources.get().unwrap();
+    renderer.supported_sample_counts.clone()
+}
+
+/// Loads an image file for sprites to use, returning the id to put in [`GpuSprite::texture_id`]
+pub fn load_texture(
+    render_state: &egui_wgpu::RenderState,
+    path: impl AsRef<Path>,
+    alpha_mode: AlphaMode,
+) -> Result<u32, image::ImageError> {
+    upload_texture(render_state, &image::open(path)?.into_rgba8(), alpha_mode)
+}
+
+/// Like [`load_texture`] but for an image that is already loaded, such as one dropped on the web where there is no path
+pub fn upload_texture(
+    render_state: &egui_wgpu::RenderState,
+    image: &image::RgbaImage,
+    alpha_mode: AlphaMode,
+) -> Result<u32, image::ImageError> {
+    // wgpu panics rather than returning an error for textures it cant make
+    let egui_wgpu::RenderState {
+        ref device,
+        ref queue,
+        ref renderer,
+        ..
+    } = *render_state;
+    let max_size = device.limits().max_texture_dimension_2d;
+    if !(1..=max_size).contains(&image.width()) || !(1..=max_size).contains(&image.height()) {
+        return Err(image::ImageError::Limits(
+            image::error::LimitError::from_kind(image::error::LimitErrorKind::DimensionError),
+        ));
+    }
+    let mut egui_renderer = renderer.write();
+    let renderer: &mut Renderer = egui_renderer.callback_resources.get_mut().unwrap();
+    Ok(renderer.load_texture(device, queue, image, alpha_mode))
+}
+
+/// Renders `callback` into an offscreen texture of the given size, independent of the window size
+pub fn render_to_image(
+    render_state: &egui_wgpu::RenderState,
+    callback: &RenderCallback,
+    clear_color: wgpu::Color,
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage, ReadbackError> {
+    let egui_wgpu::RenderState {
+        ref device,
+        ref queue,
+        ref renderer,
+        ..
+    } = *render_state;
+    let mut egui_renderer = renderer.write();
+    let renderer: &mut Renderer = egui_renderer.callback_resources.get_mut().unwrap();
+    renderer.set_sample_count(device, callback.sample_count);
+    renderer.render_to_image(device, queue, &callback.shapes, clear_color, width, height)
+}
+
+/// Renders shapes into an offscreen texture with its own device, without a window or egui,
+/// so the output can be compared against saved images
+#[cfg(not(target_arch = "wasm32"))]
+pub struct HeadlessRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    renderer: Renderer,
+    width: u32,
+    height: u32,
+    clear_color: wgpu::Color,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HeadlessRenderer {
+    /// The format egui draws into, so the output matches what the window shows
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    /// `None` if there is no adapter or it wont create a device
+    pub fn new(width: u32, height: u32, clear_color: wgpu::Color) -> Option<HeadlessRenderer> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))?;
+        // eguis default limits for gl have no storage buffers, theres no window here to be compatible with
+        let descriptor = wgpu::DeviceDescriptor {
+            limits: adapter.limits(),
+            ..device_descriptor(&adapter)
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&descriptor, None)).ok()?;
+        let renderer = Renderer::new(&adapter, &device, &queue, Self::FORMAT);
+        Some(HeadlessRenderer {
+            device,
+            queue,
+            renderer,
+            width,
+            height,
+            clear_color,
+        })
+    }
+
+    /// Draws the shapes the same way the window does, but without msaa
+    pub fn render(&mut self, shapes: &Shapes) -> Result<image::RgbaImage, ReadbackError> {
+        self.renderer.render_to_image(
+            &self.device,
+            &self.queue,
+            shapes,
+            self.clear_color,
+            self.width,
+            self.height,
+        )
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+        r: 0.0,
+        g: 0.0,
+        b: 1.0,
+        a: 1.0,
+    };
+
+    /// The gpu tests are ignored by default as they need a gpu adapter, run them with `cargo test -- --ignored`
+    fn headless(width: u32, height: u32, clear_color: wgpu::Color) -> HeadlessRenderer {
+        HeadlessRenderer::new(width, height, clear_color)
+            .expect("no gpu adapter to run the rendering tests with")
+    }
+
+    fn camera(width: u32, height: u32) -> GpuCamera {
+        GpuCamera {
+            position: Vector2 { x: 0.0, y: 0.0 },
+            aspect: width as f32 / height as f32,
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    /// Within a couple of steps of `expected` in every channel, so rounding differences between gpus dont matter
+    fn assert_pixel(image: &image::RgbaImage, x: u32, y: u32, expected: [u8; 4]) {
+        let pixel = image.get_pixel(x, y).0;
+        assert!(
+            pixel
+                .iter()
+                .zip(expected)
+                .all(|(&channel, expected)| channel.abs_diff(expected) <= 2),
+            "pixel ({x}, {y}) is {pixel:?}, expected {expected:?}"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a gpu adapter"]
+    fn renders_a_centered_circle() {
+        let mut headless = headless(64, 64, CLEAR_COLOR);
+        let circle = GpuCircle::new(Vector2 { x: 0.0, y: 0.0 })
+            .with_color_rgb(1.0, 0.0, 0.0)
+            .with_radius(0.5);
+        let image = headless
+            .render(&Shapes {
+                circles: vec![circle],
+                ..Shapes::new(camera(64, 64))
+            })
+            .unwrap();
+        assert_pixel(&image, 32, 32, [255, 0, 0, 255]);
+        assert_pixel(&image, 0, 0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a gpu adapter"]
+    fn translucent_circles_blend_in_linear_space() {
+        let mut headless = headless(64, 64, wgpu::Color::BLACK);
+        // overlapping in the middle of the image, the green one drawn over the red one
+        let red = GpuCircle::new(Vector2 { x: -0.25, y: 0.0 })
+            .with_color_rgb(1.0, 0.0, 0.0)
+            .with_radius(0.5)
+            .with_alpha(0.5);
+        let green = GpuCircle::new(Vector2 { x: 0.25, y: 0.0 })
+            .with_color_rgb(0.0, 1.0, 0.0)
+            .with_radius(0.5)
+            .with_alpha(0.5);
+        let image = headless
+            .render(&Shapes {
+                circles: vec![red, green],
+                ..Shapes::new(camera(64, 64))
+            })
+            .unwrap();
+        // blending 50% into black in srgb would give 128, in linear space it gives half the light
+        let encode = |linear: f32| (crate::color::encode_srgb(linear) * 255.0).round() as u8;
+        let half = encode(0.5);
+        assert_pixel(&image, 12, 32, [half, 0, 0, 255]);
+        assert_pixel(&image, 52, 32, [0, half, 0, 255]);
+        // the green covers half of the red that was already halfway over black
+        assert_pixel(&image, 32, 32, [encode(0.25), half, 0, 255]);
+    }
+
+    #[test]
+    fn fitted_cameras_show_everything_the_screen_did() {
+        let screen = camera(160, 90);
+        for (width, height) in [(160, 90), (1920, 1080), (100, 100), (90, 160), (400, 100)] {
+            let fitted = screen.fitted_to_aspect(width as f32 / height as f32);
+            let half_size = |camera: GpuCamera| (camera.aspect / camera.zoom, 1.0 / camera.zoom);
+            let (screen_width, screen_height) = half_size(screen);
+            let (fitted_width, fitted_height) = half_size(fitted);
+            assert!(fitted_width >= screen_width - 1e-5, "{width}x{height}");
+            assert!(fitted_height >= screen_height - 1e-5, "{width}x{height}");
+            // and one side matches exactly, so it doesnt zoom out more than it has to
+            assert!(
+                (fitted_width - screen_width).abs() < 1e-5
+                    || (fitted_height - screen_height).abs() < 1e-5,
+                "{width}x{height}"
+            );
+        }
+    }
+
+    #[test]
+    fn layer_batches_cover_both_kinds_a_layer_at_a_time() {
+        let circles = [0, 0, 2, 5].map(|layer| GpuCircle::default().with_layer(layer));
+        let rectangles = [-1, 2, 2, 3]
+            .map(|layer| GpuRectangle::new(Vector2 { x: 0.0, y: 0.0 }).with_layer(layer));
+        assert_eq!(
+            layer_batches(&circles, &rectangles),
+            [
+                (0..0, 0..1),
+                (0..2, 1..1),
+                (2..3, 1..3),
+                (3..3, 3..4),
+                (3..4, 4..4),
+            ]
+        );
+        assert!(layer_batches(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn sprite_batches_keep_the_sprites_in_order() {
+        let sprites = [0, 0, 1, 0, 2, 2].map(|texture_id| GpuSprite {
+            position: Vector2 { x: 0.0, y: 0.0 },
+            size: Vector2 { x: 1.0, y: 1.0 },
+            texture_id,
+            tint: Vector4 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+                w: 1.0,
+            },
+        });
+        assert_eq!(
+            sprite_batches(&sprites),
+            [(0, 0..2), (1, 2..3), (0, 3..4), (2, 4..6)]
+        );
+        assert!(sprite_batches(&[]).is_empty());
+    }
 
-        render_pass.set_pipeline(&render_state.circle_render_pipeline);
-        render_pass.set_bind_group(0, &render_state.camera_bind_group, &[]);
-        render_pass.set_bind_group(1, &render_state.circle_bind_group, &[]);
-        render_pass.draw(0..4, 0..self.circles.len() as _);
+    #[test]
+    #[ignore = "needs a gpu adapter"]
+    fn layers_order_circles_and_rectangles_together() {
+        let mut headless = headless(64, 64, CLEAR_COLOR);
+        let circle = GpuCircle::new(Vector2 { x: 0.0, y: 0.0 })
+            .with_color_rgb(1.0, 0.0, 0.0)
+            .with_radius(0.5)
+            .with_layer(10);
+        let rectangle = GpuRectangle::new(Vector2 { x: 0.0, y: 0.0 })
+            .with_color_rgb(0.0, 1.0, 0.0)
+            .with_size(Vector2 { x: 1.0, y: 1.0 });
+        let image = headless
+            .render(&Shapes {
+                circles: vec![circle.clone()],
+                rectangles: vec![rectangle.clone()],
+                ..Shapes::new(camera(64, 64))
+            })
+            .unwrap();
+        assert_pixel(&image, 32, 32, [255, 0, 0, 255]);
 
-        render_pass.set_pipeline(&render_state.rectangle_render_pipeline);
-        render_pass.set_bind_group(0, &render_state.camera_bind_group, &[]);
-        render_pass.set_bind_group(1, &render_state.rectangle_bind_group, &[]);
-        render_pass.draw(0..4, 0..self.rectangles.len() as _);
+        // and back under it on a lower layer
+        let image = headless
+            .render(&Shapes {
+                circles: vec![circle.with_layer(-1)],
+                rectangles: vec![rectangle],
+                ..Shapes::new(camera(64, 64))
+            })
+            .unwrap();
+        assert_pixel(&image, 32, 32, [0, 255, 0, 255]);
     }
 }
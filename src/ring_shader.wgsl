@@ -0,0 +1,131 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) ring_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) ring_index: u32,
+    @location(1) world_position: vec2<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+struct Ring {
+    position: vec2<f32>,
+    inner_radius: f32,
+    outer_radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    color: vec3<f32>,
+};
+
+@group(1)
+@binding(0)
+var<storage, read> rings: array<Ring>;
+
+const PI: f32 = 3.14159265358979;
+const TAU: f32 = 6.28318530717959;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.ring_index = input.ring_index;
+
+    let uv = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+
+    let ring = rings[input.ring_index];
+
+    output.world_position = uv * ring.outer_radius + ring.position;
+    output.clip_position = world_to_clip(output.world_position);
+
+    return output;
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let ring = rings[input.ring_index];
+
+    // the camera only scales and turns the world so a pixel is the same size in every direction, which
+    // makes this the same edge width as the circles get, its before the discard as derivatives need every pixel
+    let pixel_size = length(dpdx(input.world_position));
+    let edge_width = pixel_size * max(output.edge_softness, 0.01);
+
+    let span = ring.end_angle - ring.start_angle;
+    if span <= 0.0 {
+        discard;
+    }
+
+    // signed distances in world units, negative inside, like the circles
+    let offset = input.world_position - ring.position;
+    let radius = length(offset);
+    var distance = radius - ring.outer_radius;
+    // with no hole the center isnt an edge, so it doesnt get anti-aliased
+    if ring.inner_radius > 0.0 {
+        distance = max(distance, ring.inner_radius - radius);
+    }
+
+    if span < TAU {
+        // turned so the wedge is centered on the x axis, then folded onto its upper half
+        let middle = (ring.start_angle + ring.end_angle) * 0.5;
+        let half_span = min(span * 0.5, PI);
+        let turned = rotate(offset, -middle);
+        let folded = vec2<f32>(turned.x, abs(turned.y));
+        // the distance to the cut edge, which is a ray from the center, negative on the side towards the x axis
+        let edge = vec2<f32>(cos(half_span), sin(half_span));
+        let to_edge = length(folded - edge * max(dot(folded, edge), 0.0));
+        let side = sign(edge.x * folded.y - edge.y * folded.x);
+        distance = max(distance, to_edge * side);
+    }
+
+    let alpha = clamp(0.5 - distance / edge_width, 0.0, 1.0);
+    if alpha <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(encode_output(ring.color) * alpha, alpha);
+}
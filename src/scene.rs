@@ -0,0 +1,1115 @@
+use crate::{
+    physics::{self, PhysicsCircle, PhysicsSettings},
+    rendering::{
+        GpuCapsule, GpuCircle, GpuEllipse, GpuLine, GpuPolygon, GpuRectangle, GpuRing,
+        MAX_POLYGON_VERTICES,
+    },
+};
+use cgmath::{Array, InnerSpace, Vector2, Vector3, VectorSpace};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::BufRead,
+    path::Path,
+};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Every circle needs exactly one physics body
+    MismatchedBodies {
+        circles: usize,
+        bodies: usize,
+    },
+    /// A spring has to connect two different circles that are in the scene
+    InvalidSpring {
+        spring: usize,
+    },
+    /// A polygon needs from 3 to [`MAX_POLYGON_VERTICES`] vertices
+    InvalidPolygon {
+        polygon: usize,
+        vertex_count: u32,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "{error}"),
+            SceneError::Json(error) => write!(f, "invalid scene json: {error}"),
+            SceneError::MismatchedBodies { circles, bodies } => {
+                write!(f, "scene has {circles} circles but {bodies} physics bodies")
+            }
+            SceneError::InvalidSpring { spring } => {
+                write!(f, "spring {spring} doesnt connect two circles in the scene")
+            }
+            SceneError::InvalidPolygon {
+                polygon,
+                vertex_count,
+            } => write!(
+                f,
+                "polygon {polygon} has {vertex_count} vertices, it needs 3 to {MAX_POLYGON_VERTICES}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(error: std::io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(error: serde_json::Error) -> Self {
+        SceneError::Json(error)
+    }
+}
+
+pub use crate::physics::ShapeId;
+
+/// A [`physics::Spring`] between two circles by id, so it stays attached as the circles around it are removed
+#[derive(Clone, Copy)]
+pub struct CircleSpring {
+    pub a: ShapeId,
+    pub b: ShapeId,
+    pub rest_length: f32,
+    /// See [`physics::Spring::stiffness`]
+    pub stiffness: f32,
+}
+
+/// What happens when adding shapes would go over the shape limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitPolicy {
+    /// Nothing is added
+    Reject,
+    /// The oldest circles that were going to be removed anyway, the ones with a lifetime, are removed to make room,
+    /// circles without a lifetime are never recycled
+    Recycle,
+}
+
+impl LimitPolicy {
+    pub const ALL: [LimitPolicy; 2] = [LimitPolicy::Reject, LimitPolicy::Recycle];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LimitPolicy::Reject => "Reject",
+            LimitPolicy::Recycle => "Recycle Oldest Spawned",
+        }
+    }
+}
+
+/// How a scene is saved, the springs are saved by index as the ids arent
+#[derive(Serialize)]
+struct SavedScene<'a> {
+    #[serde(flatten)]
+    scene: &'a Scene,
+    springs: Vec<physics::Spring>,
+}
+
+#[derive(Deserialize)]
+struct LoadedScene {
+    #[serde(flatten)]
+    scene: Scene,
+    #[serde(default)]
+    springs: Vec<physics::Spring>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Scene {
+    /// Kept contiguous and in draw order so they can be copied straight into the instance buffer
+    pub circles: Vec<GpuCircle>,
+    /// The physics state for each circle, `bodies[i]` always belongs to `circles[i]`
+    pub bodies: Vec<PhysicsCircle>,
+    /// Where each circle was before the last physics step, used to interpolate between steps when drawing
+    #[serde(skip)]
+    pub previous_positions: Vec<Vector2<f32>>,
+    /// How many more seconds each circle has before it is removed, `None` for circles that stay
+    #[serde(default)]
+    pub lifetimes: Vec<Option<f32>>,
+    /// A name for each circle to find it by in the shape list
+    #[serde(default)]
+    pub names: Vec<Option<String>>,
+    pub rectangles: Vec<GpuRectangle>,
+    #[serde(default)]
+    pub ellipses: Vec<GpuEllipse>,
+    #[serde(default)]
+    pub capsules: Vec<GpuCapsule>,
+    #[serde(default)]
+    pub rings: Vec<GpuRing>,
+    #[serde(default)]
+    pub polygons: Vec<GpuPolygon>,
+    #[serde(default)]
+    pub lines: Vec<GpuLine>,
+    /// Every spring only ever connects circles that are still in the scene
+    #[serde(skip)]
+    springs: Vec<CircleSpring>,
+    /// `circle_ids[i]` is the id of `circles[i]`
+    #[serde(skip)]
+    circle_ids: Vec<ShapeId>,
+    #[serde(skip)]
+    circle_indices: HashMap<ShapeId, usize>,
+    #[serde(skip)]
+    next_id: u64,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn add_circle(&mut self, circle: GpuCircle) -> ShapeId {
+        self.add_circle_with_velocity(circle, Vector2 { x: 0.0, y: 0.0 })
+    }
+
+    pub fn add_circle_with_velocity(
+        &mut self,
+        circle: GpuCircle,
+        velocity: Vector2<f32>,
+    ) -> ShapeId {
+        self.bodies
+            .push(PhysicsCircle::new(circle.position, velocity, circle.radius));
+        self.circles.push(circle);
+        self.lifetimes.push(None);
+        self.names.push(None);
+        let id = self.new_id();
+        self.circle_indices.insert(id, self.circle_ids.len());
+        self.circle_ids.push(id);
+        id
+    }
+
+    /// Adds a circle that is removed by [`Scene::age_circles`] once `lifetime` seconds have passed
+    pub fn add_circle_with_lifetime(
+        &mut self,
+        circle: GpuCircle,
+        velocity: Vector2<f32>,
+        lifetime: f32,
+    ) -> ShapeId {
+        let id = self.add_circle_with_velocity(circle, velocity);
+        *self.lifetimes.last_mut().unwrap() = Some(lifetime);
+        id
+    }
+
+    /// Makes sure the ids handed out from now on are new to `other` too, so an id that something outside of the
+    /// scene could still be holding on to, like the selection after an undo, never comes back as another circle
+    pub fn continue_ids_from(&mut self, other: &Scene) {
+        self.next_id = self.next_id.max(other.next_id);
+    }
+
+    /// Gives every circle a new id that `other` has never handed out, for a scene that replaces `other` but was
+    /// made on its own, like a loaded file or a preset, where the ids start over from 0
+    ///
+    /// This way no id held on to from `other` belongs to a circle in this scene, even after undoing back and forth
+    pub fn renumber_ids_after(&mut self, other: &Scene) {
+        let first_id = self.next_id.max(other.next_id);
+        let new_id_of: HashMap<ShapeId, ShapeId> = self
+            .circle_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| (id, ShapeId(first_id + index as u64)))
+            .collect();
+        for id in &mut self.circle_ids {
+            *id = new_id_of[id];
+        }
+        for spring in &mut self.springs {
+            spring.a = new_id_of[&spring.a];
+            spring.b = new_id_of[&spring.b];
+        }
+        self.next_id = first_id + self.circle_ids.len() as u64;
+        self.rebuild_circle_indices();
+    }
+
+    fn new_id(&mut self) -> ShapeId {
+        let id = ShapeId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    pub fn add_rectangle(&mut self, rectangle: GpuRectangle) {
+        self.rectangles.push(rectangle);
+    }
+
+    pub fn add_ellipse(&mut self, ellipse: GpuEllipse) {
+        self.ellipses.push(ellipse);
+    }
+
+    pub fn add_capsule(&mut self, capsule: GpuCapsule) {
+        self.capsules.push(capsule);
+    }
+
+    pub fn add_ring(&mut self, ring: GpuRing) {
+        self.rings.push(ring);
+    }
+
+    pub fn add_polygon(&mut self, polygon: GpuPolygon) {
+        self.polygons.push(polygon);
+    }
+
+    pub fn add_line(&mut self, line: GpuLine) {
+        self.lines.push(line);
+    }
+
+    /// Connects two circles, nothing is added if either of them is gone or they are the same circle
+    pub fn add_spring(&mut self, spring: CircleSpring) {
+        if spring.a != spring.b
+            && self.circle(spring.a).is_some()
+            && self.circle(spring.b).is_some()
+        {
+            self.springs.push(spring);
+        }
+    }
+
+    pub fn springs(&self) -> &[CircleSpring] {
+        &self.springs
+    }
+
+    /// Removes every spring attached to any circle in `ids`
+    pub fn remove_springs(&mut self, ids: &HashSet<ShapeId>) {
+        self.springs
+            .retain(|spring| !ids.contains(&spring.a) && !ids.contains(&spring.b));
+    }
+
+    /// The springs by the index of the circles they connect, for stepping and saving
+    fn indexed_springs(&self) -> Vec<physics::Spring> {
+        self.springs
+            .iter()
+            .map(|spring| physics::Spring {
+                a: self.circle_index(spring.a).unwrap(),
+                b: self.circle_index(spring.b).unwrap(),
+                rest_length: spring.rest_length,
+                stiffness: spring.stiffness,
+            })
+            .collect()
+    }
+
+    /// Fills the box from `min` to `max` with a grid of `count` small circles, handy for testing how the physics performs
+    pub fn add_circle_grid(&mut self, min: Vector2<f32>, max: Vector2<f32>, count: u32) {
+        let size = max - min;
+        let columns = ((count as f32 * size.x / size.y).sqrt().ceil() as u32).max(1);
+        let rows = count.div_ceil(columns);
+        let spacing = Vector2 {
+            x: size.x / columns as f32,
+            y: size.y / rows as f32,
+        };
+        let radius = spacing.x.min(spacing.y) * 0.4;
+        for i in 0..count {
+            let (column, row) = (i % columns, i / columns);
+            self.add_circle(
+                GpuCircle::new(Vector2 {
+                    x: min.x + (column as f32 + 0.5) * spacing.x,
+                    y: min.y + (row as f32 + 0.5) * spacing.y,
+                })
+                .with_color_rgb(
+                    column as f32 / columns as f32,
+                    row as f32 / rows as f32,
+                    1.0,
+                )
+                .with_radius(radius),
+            );
+        }
+    }
+
+    /// Adds `count` circles with random positions inside the box from `min` to `max`, and random sizes, colors and velocities
+    ///
+    /// Everything comes from `rng`, so a generator with the same seed always adds exactly the same circles
+    pub fn add_random_circles(
+        &mut self,
+        rng: &mut impl Rng,
+        min: Vector2<f32>,
+        max: Vector2<f32>,
+        count: u32,
+    ) {
+        for _ in 0..count {
+            let radius = rng.gen_range(0.05..=0.3);
+            let position = Vector2 {
+                x: rng.gen_range(min.x..=max.x),
+                y: rng.gen_range(min.y..=max.y),
+            };
+            let color = Vector3 {
+                x: rng.gen_range(0.0..=1.0),
+                y: rng.gen_range(0.0..=1.0),
+                z: rng.gen_range(0.0..=1.0),
+            };
+            let velocity = Vector2 {
+                x: rng.gen_range(-2.0..=2.0),
+                y: rng.gen_range(-2.0..=2.0),
+            };
+            self.add_circle_with_velocity(
+                GpuCircle::new(position)
+                    .with_radius(radius)
+                    .with_color(color),
+                velocity,
+            );
+        }
+    }
+
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Loads a whole scene, nothing is returned unless the file is completely valid
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+        Scene::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// The same json that [`Scene::save_to_path`] writes
+    pub fn to_json(&self) -> Result<String, SceneError> {
+        Ok(serde_json::to_string_pretty(&SavedScene {
+            scene: self,
+            springs: self.indexed_springs(),
+        })?)
+    }
+
+    /// Parses json written by [`Scene::to_json`], nothing is returned unless it is completely valid
+    pub fn from_json(json: &str) -> Result<Scene, SceneError> {
+        let LoadedScene { mut scene, springs } = serde_json::from_str(json)?;
+        if scene.circles.len() != scene.bodies.len() {
+            return Err(SceneError::MismatchedBodies {
+                circles: scene.circles.len(),
+                bodies: scene.bodies.len(),
+            });
+        }
+        for (index, polygon) in scene.polygons.iter().enumerate() {
+            if !(3..=MAX_POLYGON_VERTICES as u32).contains(&polygon.vertex_count) {
+                return Err(SceneError::InvalidPolygon {
+                    polygon: index,
+                    vertex_count: polygon.vertex_count,
+                });
+            }
+        }
+        // scenes saved before circles had lifetimes
+        scene.lifetimes.resize(scene.circles.len(), None);
+        // or names
+        scene.names.resize(scene.circles.len(), None);
+        // or masses
+        for body in &mut scene.bodies {
+            if body.mass <= 0.0 {
+                body.mass = PhysicsCircle::default_mass(body.radius);
+            }
+        }
+        for _ in 0..scene.circles.len() {
+            let id = scene.new_id();
+            scene.circle_ids.push(id);
+        }
+        scene.rebuild_circle_indices();
+        for (index, spring) in springs.into_iter().enumerate() {
+            let (Some(&a), Some(&b)) = (
+                scene.circle_ids.get(spring.a),
+                scene.circle_ids.get(spring.b),
+            ) else {
+                return Err(SceneError::InvalidSpring { spring: index });
+            };
+            if a == b {
+                return Err(SceneError::InvalidSpring { spring: index });
+            }
+            scene.springs.push(CircleSpring {
+                a,
+                b,
+                rest_length: spring.rest_length,
+                stiffness: spring.stiffness,
+            });
+        }
+        Ok(scene)
+    }
+
+    /// Adds a circle for every `x,y,radius,r,g,b` line, the colors are linear from 0 to 1 like the shape colors
+    ///
+    /// Blank lines and lines starting with `#` are ignored, any other line that doesnt parse is skipped
+    /// and counted, the count is returned so a header row or a few bad rows dont lose the whole file
+    pub fn import_csv(&mut self, reader: impl BufRead) -> Result<usize, SceneError> {
+        let mut circles = vec![];
+        let mut skipped = 0;
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_csv_circle(line) {
+                Some(circle) => circles.push(circle),
+                None => skipped += 1,
+            }
+        }
+        for circle in circles {
+            self.add_circle(circle);
+        }
+        Ok(skipped)
+    }
+
+    pub fn clear(&mut self) {
+        self.circles.clear();
+        self.bodies.clear();
+        self.previous_positions.clear();
+        self.lifetimes.clear();
+        self.names.clear();
+        self.circle_ids.clear();
+        self.circle_indices.clear();
+        self.rectangles.clear();
+        self.ellipses.clear();
+        self.capsules.clear();
+        self.rings.clear();
+        self.polygons.clear();
+        self.lines.clear();
+        self.springs.clear();
+    }
+
+    pub fn iter_circles(&self) -> impl Iterator<Item = &GpuCircle> {
+        self.circles.iter()
+    }
+
+    /// Where the circle currently is in `circles`, `None` once it has been removed
+    pub fn circle_index(&self, id: ShapeId) -> Option<usize> {
+        self.circle_indices.get(&id).copied()
+    }
+
+    pub fn circle(&self, id: ShapeId) -> Option<&GpuCircle> {
+        Some(&self.circles[self.circle_index(id)?])
+    }
+
+    /// Puts the circle at `position` and stops it, without interpolating from where it was
+    pub fn move_circle(&mut self, id: ShapeId, position: Vector2<f32>) {
+        let Some(index) = self.circle_index(id) else {
+            return;
+        };
+        self.circles[index].position = position;
+        self.bodies[index].velocity = Vector2 { x: 0.0, y: 0.0 };
+        if let Some(previous_position) = self.previous_positions.get_mut(index) {
+            *previous_position = position;
+        }
+    }
+
+    pub fn circle_name(&self, id: ShapeId) -> Option<&str> {
+        self.names[self.circle_index(id)?].as_deref()
+    }
+
+    /// Names the circle, an empty name is the same as no name
+    pub fn set_circle_name(&mut self, id: ShapeId, name: Option<String>) {
+        if let Some(index) = self.circle_index(id) {
+            self.names[index] = name.filter(|name| !name.is_empty());
+        }
+    }
+
+    pub fn set_circle_velocity(&mut self, id: ShapeId, velocity: Vector2<f32>) {
+        if let Some(index) = self.circle_index(id) {
+            self.bodies[index].velocity = velocity;
+        }
+    }
+
+    /// Removes every circle in `ids`, the remaining circles keep their order
+    pub fn remove_circles(&mut self, ids: &HashSet<ShapeId>) {
+        // the removal goes by position rather than id as previous_positions can be shorter than the rest
+        let indices: HashSet<usize> = ids.iter().filter_map(|&id| self.circle_index(id)).collect();
+        retain_unremoved(&mut self.circles, &indices);
+        retain_unremoved(&mut self.bodies, &indices);
+        retain_unremoved(&mut self.previous_positions, &indices);
+        retain_unremoved(&mut self.lifetimes, &indices);
+        retain_unremoved(&mut self.names, &indices);
+        retain_unremoved(&mut self.circle_ids, &indices);
+        self.rebuild_circle_indices();
+        self.remove_springs(ids);
+    }
+
+    fn rebuild_circle_indices(&mut self) {
+        self.circle_indices.clear();
+        self.circle_indices.extend(
+            self.circle_ids
+                .iter()
+                .enumerate()
+                .map(|(index, &id)| (id, index)),
+        );
+    }
+
+    /// Adds a copy of each circle in `ids` moved by `offset`, returning the ids of the copies
+    ///
+    /// The copies are added in the same order as the originals are drawn so they blend the same way,
+    /// and springs between two of the originals are copied to connect their copies
+    pub fn duplicate_circles(&mut self, ids: &[ShapeId], offset: Vector2<f32>) -> Vec<ShapeId> {
+        let copies = self.extract_circles(ids);
+        self.append(&copies, offset)
+    }
+
+    /// A new scene with copies of just the circles in `ids`, in the order they are drawn,
+    /// along with the springs that connect two of them
+    pub fn extract_circles(&self, ids: &[ShapeId]) -> Scene {
+        let mut indices: Vec<usize> = ids.iter().filter_map(|&id| self.circle_index(id)).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        let mut scene = Scene::new();
+        let copy_of: HashMap<ShapeId, ShapeId> = indices
+            .iter()
+            .map(|&index| {
+                let id = scene.add_circle(self.circles[index].clone());
+                // the velocity, mass, restitution and whether its static all carry over
+                *scene.bodies.last_mut().unwrap() = self.bodies[index].clone();
+                *scene.lifetimes.last_mut().unwrap() = self.lifetimes[index];
+                *scene.names.last_mut().unwrap() = self.names[index].clone();
+                (self.circle_ids[index], id)
+            })
+            .collect();
+        scene.springs = self
+            .springs
+            .iter()
+            .filter_map(|spring| {
+                Some(CircleSpring {
+                    a: *copy_of.get(&spring.a)?,
+                    b: *copy_of.get(&spring.b)?,
+                    ..*spring
+                })
+            })
+            .collect();
+        scene
+    }
+
+    /// Adds copies of every shape in `other` moved by `offset` on top of everything already here,
+    /// returning the ids of the circles that were added
+    pub fn append(&mut self, other: &Scene, offset: Vector2<f32>) -> Vec<ShapeId> {
+        let copies: Vec<ShapeId> = (0..other.circles.len())
+            .map(|index| {
+                let id = self.add_circle(GpuCircle {
+                    position: other.circles[index].position + offset,
+                    ..other.circles[index].clone()
+                });
+                let body = PhysicsCircle {
+                    position: other.bodies[index].position + offset,
+                    ..other.bodies[index].clone()
+                };
+                *self.bodies.last_mut().unwrap() = body;
+                *self.lifetimes.last_mut().unwrap() = other.lifetimes[index];
+                *self.names.last_mut().unwrap() = other.names[index].clone();
+                id
+            })
+            .collect();
+        let copy_of: HashMap<ShapeId, ShapeId> = other
+            .circle_ids
+            .iter()
+            .copied()
+            .zip(copies.iter().copied())
+            .collect();
+        self.springs
+            .extend(other.springs.iter().map(|spring| CircleSpring {
+                a: copy_of[&spring.a],
+                b: copy_of[&spring.b],
+                ..*spring
+            }));
+
+        self.rectangles
+            .extend(other.rectangles.iter().map(|rectangle| GpuRectangle {
+                position: rectangle.position + offset,
+                ..rectangle.clone()
+            }));
+        self.ellipses
+            .extend(other.ellipses.iter().map(|ellipse| GpuEllipse {
+                position: ellipse.position + offset,
+                ..ellipse.clone()
+            }));
+        self.capsules
+            .extend(other.capsules.iter().map(|capsule| GpuCapsule {
+                start: capsule.start + offset,
+                end: capsule.end + offset,
+                ..capsule.clone()
+            }));
+        self.rings.extend(other.rings.iter().map(|ring| GpuRing {
+            position: ring.position + offset,
+            ..ring.clone()
+        }));
+        self.polygons.extend(other.polygons.iter().map(|polygon| {
+            let mut polygon = polygon.clone();
+            for vertex in &mut polygon.vertices {
+                *vertex += offset;
+            }
+            polygon
+        }));
+        self.lines.extend(other.lines.iter().map(|line| GpuLine {
+            start: line.start + offset,
+            end: line.end + offset,
+            ..line.clone()
+        }));
+        copies
+    }
+
+    /// How many shapes of every kind there are
+    pub fn shape_count(&self) -> usize {
+        self.circles.len()
+            + self.rectangles.len()
+            + self.ellipses.len()
+            + self.capsules.len()
+            + self.rings.len()
+            + self.polygons.len()
+            + self.lines.len()
+    }
+
+    /// Whether `count` more shapes fit without going over `max_shapes`, with [`LimitPolicy::Recycle`]
+    /// the oldest circles with a lifetime are removed to make them fit when there are enough of them
+    ///
+    /// Nothing is removed unless it all fits afterwards
+    pub fn make_room(&mut self, count: usize, max_shapes: usize, policy: LimitPolicy) -> bool {
+        let excess = (self.shape_count() + count).saturating_sub(max_shapes);
+        if excess == 0 {
+            return true;
+        }
+        if policy == LimitPolicy::Reject {
+            return false;
+        }
+        // only the spawned circles that would go away by themselves, never anything put there on purpose
+        let mut ids: Vec<ShapeId> = self
+            .circle_ids
+            .iter()
+            .zip(&self.lifetimes)
+            .filter(|(_, lifetime)| lifetime.is_some())
+            .map(|(&id, _)| id)
+            .collect();
+        if excess > ids.len() {
+            return false;
+        }
+        // ids only ever go up, so the smallest ones are the oldest circles
+        ids.select_nth_unstable_by_key(excess - 1, |id| id.0);
+        self.remove_circles(&ids[..excess].iter().copied().collect());
+        true
+    }
+
+    /// The ids of every circle whose center is inside the box from `min` to `max`
+    pub fn circles_in_rect(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<ShapeId> {
+        self.circles
+            .iter()
+            .zip(&self.circle_ids)
+            .filter(|(circle, _)| {
+                (min.x..=max.x).contains(&circle.position.x)
+                    && (min.y..=max.y).contains(&circle.position.y)
+            })
+            .map(|(_, &id)| id)
+            .collect()
+    }
+
+    /// The id, center and radius of every circle, for the queries in [`physics`]
+    pub fn query_circles(&self) -> impl Iterator<Item = physics::QueryCircle> + '_ {
+        self.circle_ids
+            .iter()
+            .zip(&self.circles)
+            .map(|(&id, circle)| (id, circle.position, circle.radius))
+    }
+
+    /// Finds the circle containing `point`, picking the one whose center is nearest if several overlap
+    pub fn circle_at(&self, point: Vector2<f32>) -> Option<ShapeId> {
+        physics::point_query(self.query_circles(), point)
+            .into_iter()
+            .min_by(|&a, &b| {
+                let distance_squared =
+                    |id| (self.circle(id).unwrap().position - point).magnitude2();
+                distance_squared(a).total_cmp(&distance_squared(b))
+            })
+    }
+
+    /// The smallest axis aligned box containing every shape, `None` if the scene is empty
+    pub fn bounding_box(&self) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let circles = self.circles.iter().map(|circle| {
+            let extent = Vector2 {
+                x: circle.radius,
+                y: circle.radius,
+            };
+            (circle.position - extent, circle.position + extent)
+        });
+        let rectangles = self.rectangles.iter().map(|rectangle| {
+            let (sin, cos) = rectangle.rotation.sin_cos();
+            let half_size = rectangle.size * 0.5;
+            let extent = Vector2 {
+                x: (cos * half_size.x).abs() + (sin * half_size.y).abs(),
+                y: (sin * half_size.x).abs() + (cos * half_size.y).abs(),
+            };
+            (rectangle.position - extent, rectangle.position + extent)
+        });
+        let ellipses = self.ellipses.iter().map(|ellipse| {
+            (
+                ellipse.position - ellipse.radii,
+                ellipse.position + ellipse.radii,
+            )
+        });
+        let capsules = self.capsules.iter().map(|capsule| {
+            let extent = Vector2 {
+                x: capsule.radius,
+                y: capsule.radius,
+            };
+            (
+                Vector2 {
+                    x: capsule.start.x.min(capsule.end.x),
+                    y: capsule.start.y.min(capsule.end.y),
+                } - extent,
+                Vector2 {
+                    x: capsule.start.x.max(capsule.end.x),
+                    y: capsule.start.y.max(capsule.end.y),
+                } + extent,
+            )
+        });
+        // the whole circle even when only part of it is drawn
+        let rings = self.rings.iter().map(|ring| {
+            let extent = Vector2 {
+                x: ring.outer_radius,
+                y: ring.outer_radius,
+            };
+            (ring.position - extent, ring.position + extent)
+        });
+        let polygons = self.polygons.iter().filter_map(|polygon| {
+            polygon
+                .vertices()
+                .iter()
+                .map(|&vertex| (vertex, vertex))
+                .reduce(|(min, max), (vertex, _)| {
+                    (
+                        Vector2 {
+                            x: min.x.min(vertex.x),
+                            y: min.y.min(vertex.y),
+                        },
+                        Vector2 {
+                            x: max.x.max(vertex.x),
+                            y: max.y.max(vertex.y),
+                        },
+                    )
+                })
+        });
+        let lines = self.lines.iter().map(|line| {
+            let extent = Vector2 {
+                x: line.width * 0.5,
+                y: line.width * 0.5,
+            };
+            (
+                Vector2 {
+                    x: line.start.x.min(line.end.x),
+                    y: line.start.y.min(line.end.y),
+                } - extent,
+                Vector2 {
+                    x: line.start.x.max(line.end.x),
+                    y: line.start.y.max(line.end.y),
+                } + extent,
+            )
+        });
+
+        circles
+            .chain(rectangles)
+            .chain(ellipses)
+            .chain(capsules)
+            .chain(rings)
+            .chain(polygons)
+            .chain(lines)
+            .reduce(|(min_a, max_a), (min_b, max_b)| {
+                (
+                    Vector2 {
+                        x: min_a.x.min(min_b.x),
+                        y: min_a.y.min(min_b.y),
+                    },
+                    Vector2 {
+                        x: max_a.x.max(max_b.x),
+                        y: max_a.y.max(max_b.y),
+                    },
+                )
+            })
+    }
+
+    /// Runs one physics step, the circles are the source of truth for position and radius
+    pub fn step_physics(&mut self, settings: &PhysicsSettings, dt: f32) {
+        let springs = self.indexed_springs();
+        self.step_bodies(|_, bodies| physics::step(bodies, &springs, settings, dt));
+    }
+
+    /// Like [`Scene::step_physics`] but stepping rapier, which keeps a body for each circle id between steps
+    #[cfg(feature = "rapier")]
+    pub fn step_physics_rapier(
+        &mut self,
+        world: &mut physics::rapier::RapierWorld,
+        settings: &PhysicsSettings,
+        dt: f32,
+    ) {
+        self.step_bodies(|ids, bodies| world.step(ids, bodies, settings, dt));
+    }
+
+    /// Copies the circles into their bodies, calls `step` with the circle ids and bodies, then copies the positions back
+    fn step_bodies(&mut self, step: impl FnOnce(&[ShapeId], &mut [PhysicsCircle])) {
+        self.previous_positions.clear();
+        self.previous_positions
+            .extend(self.circles.iter().map(|circle| circle.position));
+
+        for (body, circle) in self.bodies.iter_mut().zip(&self.circles) {
+            body.position = circle.position;
+            body.radius = circle.radius;
+        }
+
+        step(&self.circle_ids, &mut self.bodies);
+
+        // one bad value would spread to every circle it touches, so a body that isnt finite anymore
+        // is put back where it was and stopped
+        for (body, &previous_position) in self.bodies.iter_mut().zip(&self.previous_positions) {
+            if !body.position.is_finite() || !body.velocity.is_finite() {
+                body.position = if previous_position.is_finite() {
+                    previous_position
+                } else {
+                    Vector2 { x: 0.0, y: 0.0 }
+                };
+                body.velocity = Vector2 { x: 0.0, y: 0.0 };
+            }
+        }
+
+        for (circle, body) in self.circles.iter_mut().zip(&self.bodies) {
+            circle.position = body.position;
+        }
+    }
+
+    /// Counts down the lifetimes by `dt` seconds and removes the circles whose time is up
+    pub fn age_circles(&mut self, dt: f32) {
+        let mut expired = HashSet::new();
+        for (lifetime, &id) in self.lifetimes.iter_mut().zip(&self.circle_ids) {
+            if let Some(lifetime) = lifetime {
+                *lifetime -= dt;
+                if *lifetime <= 0.0 {
+                    expired.insert(id);
+                }
+            }
+        }
+        if !expired.is_empty() {
+            self.remove_circles(&expired);
+        }
+    }
+
+    /// Where one circle is drawn `alpha` of the way between physics steps, `None` once it has been removed
+    pub fn interpolated_position(&self, id: ShapeId, alpha: f32) -> Option<Vector2<f32>> {
+        let index = self.circle_index(id)?;
+        let position = self.circles[index].position;
+        Some(match self.previous_positions.get(index) {
+            Some(&previous_position) => previous_position.lerp(position, alpha),
+            None => position,
+        })
+    }
+
+    /// A copy of the circles placed `alpha` of the way from their previous physics step to the current one
+    pub fn interpolated_circles(&self, alpha: f32) -> Vec<GpuCircle> {
+        let mut circles = Vec::with_capacity(self.circles.len());
+        self.extend_interpolated_circles(alpha, &mut circles);
+        circles
+    }
+
+    /// Like [`Scene::interpolated_circles`] but adds them to the end of `circles`, so a buffer can be reused
+    pub fn extend_interpolated_circles(&self, alpha: f32, circles: &mut Vec<GpuCircle>) {
+        let start = circles.len();
+        circles.extend_from_slice(&self.circles);
+        // circles added since the last step are at the end and dont have a previous position yet
+        for (circle, &previous_position) in
+            circles[start..].iter_mut().zip(&self.previous_positions)
+        {
+            circle.position = previous_position.lerp(circle.position, alpha);
+        }
+    }
+}
+
+fn parse_csv_circle(line: &str) -> Option<GpuCircle> {
+    let values: Vec<f32> = line
+        .split(',')
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|value: &f32| value.is_finite())
+        })
+        .collect::<Option<_>>()?;
+    let &[x, y, radius, r, g, b] = values.as_slice() else {
+        return None;
+    };
+    if radius <= 0.0 {
+        return None;
+    }
+    Some(
+        GpuCircle::new(Vector2 { x, y })
+            .with_radius(radius)
+            .with_color_rgb(r, g, b),
+    )
+}
+
+fn retain_unremoved<T>(values: &mut Vec<T>, removed: &HashSet<usize>) {
+    let mut index = 0;
+    values.retain(|_| {
+        let keep = !removed.contains(&index);
+        index += 1;
+        keep
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, y: f32, radius: f32) -> GpuCircle {
+        GpuCircle::new(Vector2 { x, y }).with_radius(radius)
+    }
+
+    #[test]
+    fn circle_at_picks_the_nearest_center() {
+        let mut scene = Scene::new();
+        let left = scene.add_circle(circle(0.0, 0.0, 1.0));
+        let right = scene.add_circle(circle(1.5, 0.0, 1.0));
+        scene.add_circle(circle(10.0, 10.0, 0.5));
+
+        assert_eq!(scene.circle_at(Vector2 { x: -0.5, y: 0.0 }), Some(left));
+        // inside both, but closer to the right one
+        assert_eq!(scene.circle_at(Vector2 { x: 1.0, y: 0.0 }), Some(right));
+        assert_eq!(scene.circle_at(Vector2 { x: 5.0, y: 5.0 }), None);
+    }
+
+    #[test]
+    fn circles_in_rect_checks_the_centers() {
+        let mut scene = Scene::new();
+        let inside = scene.add_circle(circle(1.0, 1.0, 0.1));
+        // mostly outside, but the center is in
+        let overlapping = scene.add_circle(circle(1.9, 1.0, 5.0));
+        let on_edge = scene.add_circle(circle(2.0, 0.0, 0.1));
+        // overlaps the box but the center is outside
+        scene.add_circle(circle(2.5, 1.0, 1.0));
+        scene.add_circle(circle(-1.0, -1.0, 0.1));
+
+        let mut found =
+            scene.circles_in_rect(Vector2 { x: 0.0, y: 0.0 }, Vector2 { x: 2.0, y: 2.0 });
+        found.sort_by_key(|id| id.0);
+        assert_eq!(found, [inside, overlapping, on_edge]);
+
+        assert!(scene
+            .circles_in_rect(Vector2 { x: 5.0, y: 5.0 }, Vector2 { x: 6.0, y: 6.0 })
+            .is_empty());
+    }
+
+    #[test]
+    fn circle_at_ignores_removed_circles() {
+        let mut scene = Scene::new();
+        let id = scene.add_circle(circle(0.0, 0.0, 1.0));
+        scene.remove_circles(&HashSet::from([id]));
+        assert_eq!(scene.circle_at(Vector2 { x: 0.0, y: 0.0 }), None);
+    }
+
+    #[test]
+    fn import_csv_skips_bad_rows() {
+        let csv = "\
+# x,y,radius,r,g,b
+0,0,1,1,0,0
+
+1.5, -2, 0.25, 0, 1, 0.5
+not,a,circle,at,all,here
+3,3,-1,0,0,0
+4,4,1,0,0
+";
+        let mut scene = Scene::new();
+        let skipped = scene.import_csv(csv.as_bytes()).unwrap();
+        assert_eq!(skipped, 3);
+        assert_eq!(scene.circles.len(), 2);
+        assert_eq!(scene.bodies.len(), 2);
+
+        let circle = &scene.circles[1];
+        assert_eq!(circle.position, Vector2 { x: 1.5, y: -2.0 });
+        assert_eq!(circle.radius, 0.25);
+        assert_eq!(
+            circle.color,
+            Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn recycling_only_removes_circles_with_a_lifetime() {
+        let velocity = Vector2 { x: 0.0, y: 0.0 };
+        let mut scene = Scene::new();
+        let anchor = scene.add_circle(circle(0.0, 0.0, 1.0));
+        let old = scene.add_circle_with_lifetime(circle(1.0, 0.0, 1.0), velocity, 5.0);
+        let new = scene.add_circle_with_lifetime(circle(2.0, 0.0, 1.0), velocity, 5.0);
+
+        // the anchor is the oldest, but only spawned circles get recycled
+        assert!(scene.make_room(1, 3, LimitPolicy::Recycle));
+        assert!(scene.circle(anchor).is_some());
+        assert!(scene.circle(old).is_none());
+        assert!(scene.circle(new).is_some());
+
+        // not enough spawned circles to fit three more, so nothing is removed
+        assert!(!scene.make_room(3, 3, LimitPolicy::Recycle));
+        assert!(scene.circle(new).is_some());
+        assert!(!scene.make_room(1, 2, LimitPolicy::Reject));
+        assert!(scene.circle(new).is_some());
+    }
+
+    #[test]
+    fn from_json_rejects_bad_polygons() {
+        let mut scene = Scene::new();
+        scene.add_polygon(GpuPolygon::regular(Vector2 { x: 0.0, y: 0.0 }, 1.0, 5));
+        let json = scene.to_json().unwrap();
+        assert!(Scene::from_json(&json).is_ok());
+
+        for vertex_count in [0, 2, 9, 1000] {
+            let bad = json.replace(
+                "\"vertex_count\": 5",
+                &format!("\"vertex_count\": {vertex_count}"),
+            );
+            assert!(matches!(
+                Scene::from_json(&bad),
+                Err(SceneError::InvalidPolygon { polygon: 0, vertex_count: count }) if count == vertex_count
+            ));
+        }
+    }
+
+    #[test]
+    fn extracted_circles_round_trip_through_json() {
+        let mut scene = Scene::new();
+        scene.add_circle(circle(0.0, 0.0, 1.0));
+        let kept = scene.add_circle(circle(4.0, -2.0, 0.5));
+
+        // nothing selected still writes a scene that loads
+        let empty = Scene::from_json(&scene.extract_circles(&[]).to_json().unwrap()).unwrap();
+        assert!(empty.circles.is_empty());
+        assert!(empty.bodies.is_empty());
+
+        let loaded = Scene::from_json(&scene.extract_circles(&[kept]).to_json().unwrap()).unwrap();
+        assert_eq!(loaded.circles.len(), 1);
+        assert_eq!(loaded.bodies.len(), 1);
+        assert_eq!(loaded.circles[0].position, Vector2 { x: 4.0, y: -2.0 });
+        assert_eq!(loaded.circles[0].radius, 0.5);
+    }
+
+    #[test]
+    fn renumbered_ids_are_new_to_the_replaced_scene() {
+        let mut old = Scene::new();
+        let old_ids = [
+            old.add_circle(circle(0.0, 0.0, 1.0)),
+            old.add_circle(circle(3.0, 0.0, 1.0)),
+        ];
+
+        // made on its own, so its ids start over at the same numbers
+        let mut loaded = Scene::new();
+        let a = loaded.add_circle(circle(0.0, 5.0, 1.0));
+        let b = loaded.add_circle(circle(3.0, 5.0, 1.0));
+        loaded.add_spring(CircleSpring {
+            a,
+            b,
+            rest_length: 3.0,
+            stiffness: 10.0,
+        });
+        assert!(old_ids.contains(&a));
+
+        loaded.renumber_ids_after(&old);
+        for id in old_ids {
+            assert!(loaded.circle(id).is_none());
+        }
+        let [a, b] = [loaded.circle_ids[0], loaded.circle_ids[1]];
+        assert_eq!(
+            loaded.circle(a).unwrap().position,
+            Vector2 { x: 0.0, y: 5.0 }
+        );
+        assert_eq!(
+            loaded.circle(b).unwrap().position,
+            Vector2 { x: 3.0, y: 5.0 }
+        );
+        assert_eq!((loaded.springs()[0].a, loaded.springs()[0].b), (a, b));
+
+        let added = loaded.add_circle(circle(6.0, 5.0, 1.0));
+        assert!(![a, b].contains(&added) && !old_ids.contains(&added));
+    }
+}
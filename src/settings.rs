@@ -0,0 +1,145 @@
+use crate::{rendering::CircleQuality, scene::LimitPolicy};
+use cgmath::Vector2;
+use eframe::wgpu;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Mirrors the automatic `wgpu::PresentMode`s as wgpu is built without its serde feature
+///
+/// Only the automatic modes are offered, they fall back to a mode the surface supports, while asking for a mode
+/// the surface doesnt support makes eframe panic on every launch. Settings saved with one of the explicit modes
+/// load as the automatic mode that prefers it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentMode {
+    #[serde(alias = "Fifo")]
+    AutoVsync,
+    #[default]
+    #[serde(alias = "Mailbox", alias = "Immediate")]
+    AutoNoVsync,
+}
+
+impl PresentMode {
+    pub const ALL: [PresentMode; 2] = [PresentMode::AutoVsync, PresentMode::AutoNoVsync];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PresentMode::AutoVsync => "Auto Vsync",
+            PresentMode::AutoNoVsync => "Auto No Vsync",
+        }
+    }
+
+    pub fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+/// Mirrors `wgpu::PowerPreference` for the same reason as [`PresentMode`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerPreference {
+    None,
+    LowPower,
+    #[default]
+    HighPerformance,
+}
+
+impl PowerPreference {
+    pub const ALL: [PowerPreference; 3] = [
+        PowerPreference::None,
+        PowerPreference::LowPower,
+        PowerPreference::HighPerformance,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerPreference::None => "None",
+            PowerPreference::LowPower => "Low Power",
+            PowerPreference::HighPerformance => "High Performance",
+        }
+    }
+
+    pub fn to_wgpu(self) -> wgpu::PowerPreference {
+        match self {
+            PowerPreference::None => wgpu::PowerPreference::None,
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Options read once at startup, kept outside of the scene
+///
+/// eframe 0.23 owns the surface inside its painter and only reads the present mode and power preference from
+/// `WgpuConfiguration` when it first creates it, so a change to those only applies on the next launch,
+/// the rest are only where the app starts and can be changed while it runs
+///
+/// Anything missing from the file gets its default, so older settings files still load
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub present_mode: PresentMode,
+    pub power_preference: PowerPreference,
+    pub physics_ticks: u32,
+    pub time_scale: f32,
+    /// Srgb like eguis colors
+    pub background_color: [u8; 3],
+    pub camera_position: Vector2<f32>,
+    pub camera_zoom: f32,
+    /// Frames per second, `None` for no limit
+    pub frame_rate_limit: Option<u32>,
+    pub circle_quality: CircleQuality,
+    pub max_shapes: usize,
+    pub edit_limit_policy: LimitPolicy,
+    pub spawner_limit_policy: LimitPolicy,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            present_mode: PresentMode::default(),
+            power_preference: PowerPreference::default(),
+            physics_ticks: 100,
+            time_scale: 1.0,
+            background_color: [0, 0, 0],
+            camera_position: Vector2 { x: 0.0, y: 0.0 },
+            camera_zoom: crate::DEFAULT_CAMERA_ZOOM,
+            frame_rate_limit: None,
+            circle_quality: CircleQuality::default(),
+            max_shapes: 50_000,
+            edit_limit_policy: LimitPolicy::Reject,
+            spawner_limit_policy: LimitPolicy::Recycle,
+        }
+    }
+}
+
+impl Settings {
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+
+    /// Falls back to the defaults when there is no settings file yet or it cant be read
+    pub fn load_from_path_or_default(path: impl AsRef<Path>) -> Settings {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_present_modes_load_as_automatic_ones() {
+        let load =
+            |name: &str| serde_json::from_str::<PresentMode>(&format!("\"{name}\"")).unwrap();
+        assert_eq!(load("Fifo"), PresentMode::AutoVsync);
+        assert_eq!(load("Mailbox"), PresentMode::AutoNoVsync);
+        assert_eq!(load("Immediate"), PresentMode::AutoNoVsync);
+        assert_eq!(load("AutoVsync"), PresentMode::AutoVsync);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    camera::rotate,
+    rendering::GpuCircle,
+    scene::{LimitPolicy, Scene},
+};
+use cgmath::{Vector2, Vector3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Emits circles into a scene at a steady rate, each one is removed again after `lifetime` seconds
+pub struct Spawner {
+    pub enabled: bool,
+    pub position: Vector2<f32>,
+    /// Circles per second
+    pub rate: f32,
+    pub initial_velocity: Vector2<f32>,
+    /// The widest angle in radians the velocity is turned by, so the circles dont all stack up on one path
+    pub spread: f32,
+    pub radius: f32,
+    /// Linear like the rest of the shape colors
+    pub color: Vector3<f32>,
+    /// How brightly the circles glow when bloom is on
+    pub emissive: f32,
+    /// Seconds each circle lives for
+    pub lifetime: f32,
+    /// What happens to circles that would go over the shape limit
+    pub limit_policy: LimitPolicy,
+    /// Time since the last circle was emitted
+    elapsed: f32,
+    /// Picks the launch angles, seeded so the same seed always emits the same circles
+    rng: StdRng,
+}
+
+impl Spawner {
+    pub fn new(position: Vector2<f32>, seed: u64) -> Spawner {
+        Spawner {
+            enabled: false,
+            position,
+            rate: 10.0,
+            initial_velocity: Vector2 { x: 2.0, y: 6.0 },
+            spread: 0.5,
+            radius: 0.1,
+            color: Vector3 {
+                x: 1.0,
+                y: 0.5,
+                z: 0.0,
+            },
+            emissive: 2.0,
+            lifetime: 5.0,
+            limit_policy: LimitPolicy::Recycle,
+            elapsed: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Starts the launch angles over from `seed`, and the time until the next circle along with them
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.elapsed = 0.0;
+    }
+
+    /// Emits however many circles are due after `dt` more seconds, as long as they fit under `max_shapes`
+    pub fn update(&mut self, scene: &mut Scene, max_shapes: usize, dt: f32) {
+        if !self.enabled || self.rate <= 0.0 {
+            self.elapsed = 0.0;
+            return;
+        }
+
+        let interval = 1.0 / self.rate;
+        self.elapsed += dt;
+        while self.elapsed >= interval {
+            self.elapsed -= interval;
+
+            let t: f32 = self.rng.gen_range(-1.0..=1.0);
+            let velocity = rotate(self.initial_velocity, t * self.spread * 0.5);
+            // still counted as emitted, so there isnt a burst of them once there is room again
+            if !scene.make_room(1, max_shapes, self.limit_policy) {
+                continue;
+            }
+            scene.add_circle_with_lifetime(
+                GpuCircle::new(self.position)
+                    .with_color(self.color)
+                    .with_radius(self.radius)
+                    .with_emissive(self.emissive),
+                velocity,
+                self.lifetime,
+            );
+        }
+    }
+}
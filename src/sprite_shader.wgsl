@@ -0,0 +1,109 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) sprite_index: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) sprite_index: u32,
+    @location(1) uv: vec2<f32>,
+};
+
+struct Camera {
+    position: vec2<f32>,
+    aspect: f32,
+    zoom: f32,
+    rotation: f32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+// rotates `v` counterclockwise by `angle` radians
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let s = sin(angle);
+    let c = cos(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+fn world_to_clip(world_position: vec2<f32>) -> vec4<f32> {
+    // the view is turned by the camera rotation, so the world is turned the other way
+    let view = rotate(world_position - camera.position, -camera.rotation);
+    return vec4<f32>(view * camera.zoom / vec2<f32>(camera.aspect, 1.0), 0.0, 1.0);
+}
+
+struct Output {
+    encode_srgb: u32,
+    edge_softness: f32,
+};
+
+@group(0)
+@binding(1)
+var<uniform> output: Output;
+
+// the colors are linear, non srgb targets store whatever is written so they have to be encoded here
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    if output.encode_srgb == 0u {
+        return color;
+    }
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+struct Sprite {
+    position: vec2<f32>,
+    size: vec2<f32>,
+    texture_id: u32,
+    tint: vec4<f32>,
+};
+
+@group(1)
+@binding(0)
+var<storage, read> sprites: array<Sprite>;
+
+// the sprites are drawn a texture at a time, so every sprite in a draw samples the same texture
+@group(2)
+@binding(0)
+var image: texture_2d<f32>;
+
+@group(2)
+@binding(1)
+var image_sampler: sampler;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.sprite_index = input.sprite_index;
+
+    let corner = vec2<f32>(
+        f32((input.vertex_index >> 0u) & 1u) * 2.0 - 1.0,
+        f32((input.vertex_index >> 1u) & 1u) * 2.0 - 1.0,
+    );
+    // texture coordinates go down while the world goes up
+    output.uv = vec2<f32>(corner.x * 0.5 + 0.5, 0.5 - corner.y * 0.5);
+
+    let sprite = sprites[input.sprite_index];
+    let world_position = corner * sprite.size * 0.5 + sprite.position;
+
+    output.clip_position = world_to_clip(world_position);
+
+    return output;
+}
+
+@fragment
+fn pixel(input: VertexOutput) -> @location(0) vec4<f32> {
+    let sprite = sprites[input.sprite_index];
+
+    // the textures are srgb so this is linear, and premultiplied so filtering doesnt bleed in the color of clear texels
+    let texel = textureSample(image, image_sampler, input.uv);
+    let alpha = texel.a * sprite.tint.a;
+    if alpha <= 0.0 {
+        discard;
+    }
+
+    // the premultiplication has to be undone for the encoding, as its the encoded color that gets premultiplied
+    let color = texel.rgb / texel.a * sprite.tint.rgb;
+    return vec4<f32>(encode_output(color) * alpha, alpha);
+}
@@ -0,0 +1,174 @@
+use crate::{camera::Camera, color, scene::Scene};
+use cgmath::{Vector2, Vector3};
+use std::fmt::Write;
+
+/// `#rrggbb` for a linear shape color
+fn hex_color(color: Vector3<f32>) -> String {
+    let [r, g, b, _] = color::linear_to_srgb(color).to_array();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// svg has y going down while the world has it going up
+fn to_svg(position: Vector2<f32>) -> Vector2<f32> {
+    Vector2 {
+        x: position.x,
+        y: -position.y,
+    }
+}
+
+/// Writes the circles and rectangles of the scene as an svg document in world units, with a view box
+/// covering what the camera sees in a view of the given aspect
+///
+/// Gradients arent exported, shapes are filled with their first color, and they are drawn in the
+/// same order as the renderer draws them, circles by layer and then rectangles by layer
+pub fn scene_to_svg(
+    scene: &Scene,
+    camera: &Camera,
+    aspect: f32,
+    background_color: Vector3<f32>,
+) -> String {
+    // the view is 1 / zoom world units from the center to the top, and aspect / zoom to the side
+    let half_size = Vector2 {
+        x: aspect / camera.zoom,
+        y: 1.0 / camera.zoom,
+    };
+    let center = to_svg(camera.position);
+    let min = center - half_size;
+
+    let mut svg = String::new();
+    // writing into a string cant fail
+    _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min.x,
+        min.y,
+        half_size.x * 2.0,
+        half_size.y * 2.0,
+    );
+    _ = writeln!(
+        svg,
+        r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        min.x,
+        min.y,
+        half_size.x * 2.0,
+        half_size.y * 2.0,
+        hex_color(background_color),
+    );
+    // the camera turns the view counterclockwise so the world turns clockwise on screen,
+    // which is a positive angle in svg as its y axis is flipped
+    _ = writeln!(
+        svg,
+        r#"  <g transform="rotate({} {} {})">"#,
+        camera.rotation.to_degrees(),
+        center.x,
+        center.y,
+    );
+
+    let mut circles: Vec<_> = scene.circles.iter().collect();
+    circles.sort_by_key(|circle| circle.layer);
+    for circle in circles {
+        let position = to_svg(circle.position);
+        // the outline is drawn inside of the radius, while svg strokes are centered on the edge, the fill
+        // stops where the outline starts so the alpha isnt applied twice where they would overlap
+        let outline_width = circle.outline_width.clamp(0.0, circle.radius);
+        if outline_width < circle.radius {
+            _ = write!(
+                svg,
+                r#"    <circle cx="{}" cy="{}" r="{}" fill="{}""#,
+                position.x,
+                position.y,
+                circle.radius - outline_width,
+                hex_color(circle.color),
+            );
+            if circle.alpha < 1.0 {
+                _ = write!(svg, r#" fill-opacity="{}""#, circle.alpha);
+            }
+            _ = writeln!(svg, "/>");
+        }
+        if outline_width > 0.0 {
+            _ = write!(
+                svg,
+                r#"    <circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="{}""#,
+                position.x,
+                position.y,
+                circle.radius - outline_width * 0.5,
+                hex_color(circle.outline_color),
+                outline_width,
+            );
+            if circle.alpha < 1.0 {
+                _ = write!(svg, r#" stroke-opacity="{}""#, circle.alpha);
+            }
+            _ = writeln!(svg, "/>");
+        }
+    }
+
+    let mut rectangles: Vec<_> = scene.rectangles.iter().collect();
+    rectangles.sort_by_key(|rectangle| rectangle.layer);
+    for rectangle in rectangles {
+        let position = to_svg(rectangle.position);
+        let corner_radius = rectangle
+            .corner_radius
+            .min(rectangle.size.x.min(rectangle.size.y) * 0.5);
+        // the rotation is the same for the fill and the outline
+        let mut transform = String::new();
+        // counterclockwise in the world is a negative angle in svg
+        if rectangle.rotation != 0.0 {
+            _ = write!(
+                transform,
+                r#" transform="rotate({} {} {})""#,
+                -rectangle.rotation.to_degrees(),
+                position.x,
+                position.y,
+            );
+        }
+        let outline_width = rectangle
+            .outline_width
+            .clamp(0.0, rectangle.size.x.min(rectangle.size.y) * 0.5);
+        // inset by the outline like the circles, so the fill and the outline dont overlap
+        let fill_size = rectangle.size - Vector2::new(outline_width, outline_width) * 2.0;
+        if fill_size.x > 0.0 && fill_size.y > 0.0 {
+            _ = write!(
+                svg,
+                r#"    <rect x="{}" y="{}" width="{}" height="{}" fill="{}""#,
+                position.x - fill_size.x * 0.5,
+                position.y - fill_size.y * 0.5,
+                fill_size.x,
+                fill_size.y,
+                hex_color(rectangle.color),
+            );
+            if corner_radius > outline_width {
+                _ = write!(svg, r#" rx="{}""#, corner_radius - outline_width);
+            }
+            if rectangle.alpha < 1.0 {
+                _ = write!(svg, r#" fill-opacity="{}""#, rectangle.alpha);
+            }
+            _ = writeln!(svg, "{transform}/>");
+        }
+        // centered half the width in from the edge, the dashes are in pixels which an svg doesnt have so the
+        // outline is always solid
+        if outline_width > 0.0 {
+            let size = rectangle.size - Vector2::new(outline_width, outline_width);
+            _ = write!(
+                svg,
+                r#"    <rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="{}""#,
+                position.x - size.x * 0.5,
+                position.y - size.y * 0.5,
+                size.x,
+                size.y,
+                hex_color(rectangle.outline_color),
+                outline_width,
+            );
+            if corner_radius > outline_width * 0.5 {
+                _ = write!(svg, r#" rx="{}""#, corner_radius - outline_width * 0.5);
+            }
+            if rectangle.alpha < 1.0 {
+                _ = write!(svg, r#" stroke-opacity="{}""#, rectangle.alpha);
+            }
+            _ = writeln!(svg, "{transform}/>");
+        }
+    }
+
+    _ = writeln!(svg, "  </g>");
+    _ = writeln!(svg, "</svg>");
+    svg
+}
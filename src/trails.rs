@@ -0,0 +1,70 @@
+use crate::{
+    rendering::GpuCircle,
+    scene::{Scene, ShapeId},
+};
+use cgmath::Vector2;
+use std::collections::{HashMap, VecDeque};
+
+/// The last few positions of every circle, drawn behind them as fading circles
+pub struct Trails {
+    pub enabled: bool,
+    /// How many positions are kept for each circle, one is recorded every physics step
+    pub length: usize,
+    /// How quickly the trail fades out along its length, `1.0` is linear and higher fades out sooner
+    pub fade: f32,
+    /// Newest first, for every circle that was there at the last step
+    positions: HashMap<ShapeId, VecDeque<Vector2<f32>>>,
+}
+
+impl Trails {
+    pub fn new() -> Trails {
+        Trails {
+            enabled: false,
+            length: 30,
+            fade: 1.0,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Adds where every circle is now to the front of its trail, and drops the trails of circles that are gone
+    pub fn record(&mut self, scene: &Scene) {
+        if !self.enabled {
+            self.positions.clear();
+            return;
+        }
+        self.positions.retain(|&id, _| scene.circle(id).is_some());
+        for (id, position, _) in scene.query_circles() {
+            let trail = self.positions.entry(id).or_default();
+            trail.push_front(position);
+            trail.truncate(self.length);
+        }
+    }
+
+    /// Forgets every trail, for when the scene is replaced and the ids could belong to other circles
+    pub fn clear(&mut self) {
+        self.positions.clear();
+    }
+
+    /// A circle for every recorded position, the same color and layer as the circle it follows but
+    /// shrinking and fading out towards the end of the trail
+    ///
+    /// They are added to the end of `circles` in the same order as the circles, oldest first in each trail,
+    /// so adding them before the circles draws each trail behind its circle
+    pub fn extend_circles(&self, scene: &Scene, circles: &mut Vec<GpuCircle>) {
+        for (id, _, _) in scene.query_circles() {
+            let (Some(trail), Some(circle)) = (self.positions.get(&id), scene.circle(id)) else {
+                continue;
+            };
+            for (age, &position) in trail.iter().enumerate().rev() {
+                let fade = (1.0 - (age + 1) as f32 / (self.length + 1) as f32).powf(self.fade);
+                circles.push(
+                    GpuCircle::new(position)
+                        .with_color(circle.color)
+                        .with_radius(circle.radius * fade)
+                        .with_alpha(circle.alpha * fade)
+                        .with_layer(circle.layer),
+                );
+            }
+        }
+    }
+}